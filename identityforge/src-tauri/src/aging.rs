@@ -0,0 +1,88 @@
+use crate::database::{Database, DatabaseError, Profile};
+use crate::fingerprint::age_user_agent;
+use std::sync::Arc;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// Setting key for automatic aging, in days between bumps (0 = off)
+pub const AUTO_AGE_INTERVAL_SETTING: &str = "auto_age_interval_days";
+
+/// How often the background task checks for profiles due for aging
+const CHECK_INTERVAL: Duration = Duration::from_secs(60 * 60);
+
+fn now_secs() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+/// Bump the profile's browser version and record when it happened.
+/// Returns `false` (and leaves the profile untouched) if there is no newer version
+/// or it is the system profile.
+pub fn age_profile(db: &Database, profile: &mut Profile) -> Result<bool, DatabaseError> {
+    if profile.is_system {
+        return Ok(false);
+    }
+    let aged = match age_user_agent(&profile.user_agent) {
+        Some(ua) => ua,
+        None => return Ok(false),
+    };
+
+    profile.user_agent = aged;
+    profile.last_aged = Some(now_secs().to_string());
+    db.update_profile(profile)?;
+    Ok(true)
+}
+
+/// Whether a profile last aged (or created) at `since` is due after `interval_days`
+fn is_due(since: u64, now: u64, interval_days: u64) -> bool {
+    interval_days > 0 && now.saturating_sub(since) >= interval_days * 24 * 60 * 60
+}
+
+/// Age every profile that is due under the `auto_age_interval_days` setting
+fn age_due_profiles(db: &Database) -> Result<usize, DatabaseError> {
+    let interval_days = db.get_setting_or(AUTO_AGE_INTERVAL_SETTING, 0u64);
+    if interval_days == 0 {
+        return Ok(0);
+    }
+
+    let now = now_secs();
+    let mut aged = 0;
+    for mut profile in db.get_all_profiles()? {
+        let since = profile
+            .last_aged
+            .as_deref()
+            .unwrap_or(&profile.created_at)
+            .parse()
+            .unwrap_or(0);
+        if is_due(since, now, interval_days) && age_profile(db, &mut profile)? {
+            aged += 1;
+        }
+    }
+    Ok(aged)
+}
+
+/// Background loop that periodically ages due profiles
+pub async fn run_scheduler(db: Arc<Database>) {
+    loop {
+        match age_due_profiles(&db) {
+            Ok(0) => {}
+            Ok(count) => log::info!("Aged {} profile fingerprint(s)", count),
+            Err(e) => log::warn!("Automatic fingerprint aging failed: {}", e),
+        }
+        tokio::time::sleep(CHECK_INTERVAL).await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_is_due() {
+        let day = 24 * 60 * 60;
+        assert!(!is_due(0, 100 * day, 0)); // 0 = off
+        assert!(!is_due(10 * day, 15 * day, 7));
+        assert!(is_due(10 * day, 17 * day, 7));
+    }
+}
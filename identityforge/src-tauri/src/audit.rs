@@ -0,0 +1,256 @@
+use crate::database::{Database, DatabaseError, Profile};
+use crate::fingerprint::{self, Fingerprint};
+use crate::launcher::{validate_proxy, validate_proxy_bypass};
+use serde::Serialize;
+use std::collections::HashMap;
+
+/// Setting key: the Chrome major version real users are on (unset = newest in the UA pool)
+pub const CURRENT_CHROME_VERSION_SETTING: &str = "current_chrome_version";
+
+/// Setting key: how many Chrome major versions a profile may trail the current one
+pub const MAX_CHROME_LAG_SETTING: &str = "max_chrome_versions_behind";
+
+/// Allowed lag when `max_chrome_versions_behind` isn't set
+const DEFAULT_MAX_CHROME_LAG: u32 = 2;
+
+/// One problem found by `audit_profiles`
+#[derive(Serialize, Debug, Clone)]
+pub struct ProfileIssue {
+    pub profile_id: String,
+    /// Stable machine-readable kind, e.g. `invalid_proxy`
+    pub code: String,
+    pub message: String,
+}
+
+fn issue(profile_id: &str, code: &str, message: String) -> ProfileIssue {
+    ProfileIssue {
+        profile_id: profile_id.to_string(),
+        code: code.to_string(),
+        message,
+    }
+}
+
+/// Problems that can be decided from a single profile row
+fn profile_issues(profile: &Profile) -> Vec<ProfileIssue> {
+    let mut issues = Vec::new();
+    let id = profile.id.as_str();
+
+    // The system profile mirrors the host, so whatever it reports is real
+    if !profile.is_system {
+        if let Err(e) = profile.validate() {
+            issues.push(issue(id, "invalid_field", e));
+        }
+        for message in Fingerprint::from(profile).coherence_issues() {
+            issues.push(issue(id, "incoherent_fingerprint", message));
+        }
+    }
+    if let Err(e) = validate_proxy(&profile.get_proxy_config()) {
+        issues.push(issue(id, "invalid_proxy", e));
+    }
+    if let Err(e) = validate_proxy_bypass(&profile.proxy_bypass) {
+        issues.push(issue(id, "invalid_proxy_bypass", e));
+    }
+    issues
+}
+
+/// Flag a Chrome UA more than `max_lag` major versions behind `current`
+fn stale_version_issue(profile: &Profile, current: u32, max_lag: u32) -> Option<ProfileIssue> {
+    if profile.is_system {
+        return None;
+    }
+    let version = fingerprint::chrome_major_version(&profile.user_agent)?;
+    let lag = current.saturating_sub(version);
+    (lag > max_lag).then(|| {
+        issue(
+            &profile.id,
+            "stale_browser_version",
+            format!(
+                "Chrome {} is {} versions behind {}; age the fingerprint",
+                version, lag, current
+            ),
+        )
+    })
+}
+
+/// Profiles sharing an identity hash, one issue per profile naming the others
+fn duplicate_issues(profiles: &[Profile]) -> Vec<ProfileIssue> {
+    let mut by_hash: HashMap<String, Vec<&str>> = HashMap::new();
+    for profile in profiles.iter().filter(|p| !p.is_system) {
+        let hash = Fingerprint::from(profile).identity_hash();
+        by_hash.entry(hash).or_default().push(&profile.id);
+    }
+
+    by_hash
+        .values()
+        .filter(|ids| ids.len() > 1)
+        .flat_map(|ids| {
+            ids.iter().map(move |id| {
+                let others: Vec<&str> = ids.iter().copied().filter(|other| other != id).collect();
+                issue(
+                    id,
+                    "duplicate_identity",
+                    format!("Same fingerprint as {}", others.join(", ")),
+                )
+            })
+        })
+        .collect()
+}
+
+/// Timezone and language that don't match where the profile's proxy was
+/// last seen exiting
+fn location_issues(profile: &Profile, country: &str) -> Vec<ProfileIssue> {
+    if profile.is_system {
+        return Vec::new();
+    }
+    Fingerprint::from(profile)
+        .location_issues(country)
+        .into_iter()
+        .map(|message| issue(&profile.id, "proxy_location_mismatch", message))
+        .collect()
+}
+
+/// Run every profile check across the whole fleet: field and proxy
+/// validation, fingerprint coherence, data directory isolation, duplicates,
+/// Chrome versions trailing `current_chrome_version` by more than
+/// `max_chrome_versions_behind`, and timezone/language against
+/// `proxy_countries` (profile id -> recent GeoIP country).
+/// Issues are grouped by profile in the order profiles are listed.
+pub fn audit_profiles(
+    db: &Database,
+    proxy_countries: &HashMap<String, String>,
+) -> Result<Vec<ProfileIssue>, DatabaseError> {
+    let profiles = db.get_all_profiles()?;
+    let current_chrome =
+        db.get_setting_or(CURRENT_CHROME_VERSION_SETTING, fingerprint::newest_chrome_version());
+    let max_chrome_lag = db.get_setting_or(MAX_CHROME_LAG_SETTING, DEFAULT_MAX_CHROME_LAG);
+    let mut isolation = db.all_isolation_issues()?;
+    let mut duplicates: HashMap<String, Vec<ProfileIssue>> = HashMap::new();
+    for found in duplicate_issues(&profiles) {
+        duplicates.entry(found.profile_id.clone()).or_default().push(found);
+    }
+
+    let mut issues = Vec::new();
+    for profile in &profiles {
+        let id = profile.id.as_str();
+        issues.extend(profile_issues(profile));
+        issues.extend(stale_version_issue(profile, current_chrome, max_chrome_lag));
+        if let Some(country) = proxy_countries.get(id) {
+            issues.extend(location_issues(profile, country));
+        }
+
+        if !db.get_profile_data_dir(id).is_dir() {
+            issues.push(issue(id, "missing_data_dir", "Data directory does not exist".to_string()));
+        }
+        for message in isolation.remove(id).unwrap_or_default() {
+            issues.push(issue(id, "isolation", message));
+        }
+        issues.extend(duplicates.remove(id).unwrap_or_default());
+    }
+    Ok(issues)
+}
+
+/// The single-row, version and duplicate checks of `audit_profiles` for
+/// profiles not saved yet. Duplicates are looked for within the batch and
+/// against the stored profiles.
+pub fn audit_planned_profiles(db: &Database, planned: &[Profile]) -> Result<Vec<ProfileIssue>, DatabaseError> {
+    let current_chrome =
+        db.get_setting_or(CURRENT_CHROME_VERSION_SETTING, fingerprint::newest_chrome_version());
+    let max_chrome_lag = db.get_setting_or(MAX_CHROME_LAG_SETTING, DEFAULT_MAX_CHROME_LAG);
+    let mut all = db.get_all_profiles()?;
+    all.extend_from_slice(planned);
+    let mut duplicates: HashMap<String, Vec<ProfileIssue>> = HashMap::new();
+    for found in duplicate_issues(&all) {
+        duplicates.entry(found.profile_id.clone()).or_default().push(found);
+    }
+
+    let mut issues = Vec::new();
+    for profile in planned {
+        issues.extend(profile_issues(profile));
+        issues.extend(stale_version_issue(profile, current_chrome, max_chrome_lag));
+        issues.extend(duplicates.remove(&profile.id).unwrap_or_default());
+    }
+    Ok(issues)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::test_profile;
+    use crate::fingerprint::FingerprintGenerator;
+
+    /// A minimal profile row carrying `fp`'s identity
+    fn profile_with(fp: &Fingerprint, id: &str) -> Profile {
+        Profile {
+            user_agent: fp.user_agent.clone(),
+            platform: fp.platform.clone(),
+            screen_width: fp.screen_width,
+            screen_height: fp.screen_height,
+            webgl_vendor: fp.webgl_vendor.clone(),
+            webgl_renderer: fp.webgl_renderer.clone(),
+            hardware_concurrency: fp.hardware_concurrency,
+            device_memory: fp.device_memory,
+            timezone: fp.timezone.clone(),
+            language: fp.language.clone(),
+            ..test_profile(id)
+        }
+    }
+
+    #[test]
+    fn test_duplicate_issues() {
+        let fp = FingerprintGenerator::new().generate();
+        let profile = |id: &str| profile_with(&fp, id);
+        let mut distinct = profile("c");
+        distinct.hardware_concurrency += 1;
+
+        let issues = duplicate_issues(&[profile("a"), profile("b"), distinct]);
+        let mut ids: Vec<&str> = issues.iter().map(|i| i.profile_id.as_str()).collect();
+        ids.sort();
+        assert_eq!(ids, vec!["a", "b"]);
+        assert!(issues.iter().all(|i| i.code == "duplicate_identity"));
+    }
+
+    #[test]
+    fn test_audit_planned_profiles() {
+        let dir = std::env::temp_dir().join(format!("identityforge-audit-plan-{}", std::process::id()));
+        std::fs::remove_dir_all(&dir).ok();
+        let db = Database::new(&dir.join("test.db"), dir.join("profiles")).unwrap();
+        let fp = FingerprintGenerator::new().generate();
+        db.create_profile(&profile_with(&fp, "stored")).unwrap();
+
+        let mut distinct = profile_with(&fp, "fresh");
+        distinct.hardware_concurrency += 1;
+        let issues = audit_planned_profiles(&db, &[profile_with(&fp, "copy"), distinct]).unwrap();
+        let duplicates: Vec<&ProfileIssue> = issues.iter().filter(|i| i.code == "duplicate_identity").collect();
+        assert_eq!(duplicates.len(), 1);
+        assert_eq!(duplicates[0].profile_id, "copy");
+        assert_eq!(duplicates[0].message, "Same fingerprint as stored");
+        // Nothing was saved by the check
+        assert_eq!(db.get_all_profiles().unwrap().len(), 1);
+
+        std::fs::remove_dir_all(&dir).ok();
+    }
+
+    #[test]
+    fn test_stale_version_issue() {
+        let mut profile = test_profile("a");
+        profile.user_agent = "Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/119.0.0.0 Safari/537.36".to_string();
+
+        assert!(stale_version_issue(&profile, 121, 2).is_none());
+        let found = stale_version_issue(&profile, 124, 2).unwrap();
+        assert_eq!(found.code, "stale_browser_version");
+        assert!(found.message.contains("5 versions behind 124"));
+
+        profile.user_agent = "Mozilla/5.0 (X11; Linux x86_64; rv:109.0) Gecko/20100101 Firefox/115.0".to_string();
+        assert!(stale_version_issue(&profile, 124, 2).is_none());
+    }
+
+    #[test]
+    fn test_system_profile_fields_not_validated() {
+        let mut profile = test_profile("system");
+        profile.user_agent = String::new();
+        assert!(profile_issues(&profile).iter().any(|i| i.code == "invalid_field"));
+
+        profile.is_system = true;
+        assert!(profile_issues(&profile).is_empty());
+    }
+}
@@ -0,0 +1,226 @@
+use crate::database::normalize_proxy_type;
+use crate::fingerprint::{self, Fingerprint};
+
+/// Tools `automation_config` writes launch code for
+pub const AUTOMATION_TARGETS: &[&str] = &["playwright", "puppeteer"];
+
+/// Environment variables the exported code reads proxy credentials from
+/// unless secrets are included
+const USERNAME_ENV: &str = "IDENTITYFORGE_PROXY_USERNAME";
+const PASSWORD_ENV: &str = "IDENTITYFORGE_PROXY_PASSWORD";
+
+fn js(value: &impl serde::Serialize) -> String {
+    serde_json::to_string(value).unwrap_or_else(|_| "null".to_string())
+}
+
+/// Chrome-style `Accept-Language` for `navigator.languages`: each language
+/// after the first gets a q-value 0.1 lower, down to 0.1
+fn accept_language(languages: &[String]) -> String {
+    languages
+        .iter()
+        .enumerate()
+        .map(|(i, language)| match i {
+            0 => language.clone(),
+            _ => format!("{};q={:.1}", language, (10 - i.min(9)) as f64 / 10.0),
+        })
+        .collect::<Vec<_>>()
+        .join(",")
+}
+
+/// Proxy server URL and credential expressions, or `None` without a proxy.
+/// Credentials are literals with `include_secrets`, else environment lookups.
+fn proxy_parts(fingerprint: &Fingerprint, include_secrets: bool) -> Option<(String, Option<(String, String)>)> {
+    if !fingerprint.proxy_enabled || fingerprint.proxy_host.trim().is_empty() {
+        return None;
+    }
+    // An "https" proxy is an HTTP proxy tunnelling via CONNECT
+    let scheme = match normalize_proxy_type(&fingerprint.proxy_type) {
+        Ok("socks5") => "socks5",
+        _ => "http",
+    };
+    let server = format!("{}://{}:{}", scheme, fingerprint.proxy_host.trim(), fingerprint.proxy_port);
+    let username = fingerprint.proxy_username.as_deref().filter(|u| !u.is_empty());
+    let credentials = username.map(|username| match include_secrets {
+        true => (js(&username), js(&fingerprint.proxy_password.as_deref().unwrap_or_default())),
+        false => (format!("process.env.{}", USERNAME_ENV), format!("process.env.{}", PASSWORD_ENV)),
+    });
+    Some((server, credentials))
+}
+
+/// Node.js code launching a browser in `target` ("playwright" or
+/// "puppeteer") as the profile: user agent, viewport, locale, timezone,
+/// proxy and custom headers, with the spoof script injected before page
+/// scripts. Proxy credentials are read from `IDENTITYFORGE_PROXY_USERNAME`
+/// and `IDENTITYFORGE_PROXY_PASSWORD` unless `include_secrets` is set.
+pub fn automation_config(
+    fingerprint: &Fingerprint,
+    profile_id: &str,
+    profile_name: &str,
+    target: &str,
+    include_secrets: bool,
+) -> Result<String, String> {
+    let target = target.trim().to_lowercase();
+    if !AUTOMATION_TARGETS.contains(&target.as_str()) {
+        return Err(format!(
+            "Unknown automation target '{}' (expected one of: {})",
+            target,
+            AUTOMATION_TARGETS.join(", ")
+        ));
+    }
+
+    let script = fingerprint::generate_spoof_script(fingerprint, profile_id);
+    let (viewport_width, viewport_height) = fingerprint::profile_viewport(fingerprint, profile_id);
+    let languages = fingerprint::navigator_languages(&fingerprint.language, &fingerprint.languages);
+    let proxy = proxy_parts(fingerprint, include_secrets);
+    let one_line = |value: &str| value.replace(['\r', '\n'], " ");
+
+    let mut lines = vec![
+        format!(
+            "// IdentityForge profile \"{}\" ({}) for {}",
+            one_line(profile_name),
+            one_line(profile_id),
+            target
+        ),
+        format!("// Spoof script version {}", fingerprint::spoof_script_version(&script)),
+    ];
+    if proxy.as_ref().is_some_and(|(_, credentials)| credentials.is_some()) && !include_secrets {
+        lines.push(format!("// Proxy credentials are read from {} and {}", USERNAME_ENV, PASSWORD_ENV));
+    }
+    if proxy.as_ref().is_some_and(|(server, credentials)| server.starts_with("socks5") && credentials.is_some()) {
+        lines.push("// Chromium can't authenticate to SOCKS5 proxies; use the provider's HTTP endpoint".to_string());
+    }
+
+    if target == "playwright" {
+        let launch_options = match &proxy {
+            None => String::new(),
+            Some((server, None)) => format!("{{ proxy: {{ server: {} }} }}", js(server)),
+            Some((server, Some((username, password)))) => format!(
+                "{{ proxy: {{ server: {}, username: {}, password: {} }} }}",
+                js(server),
+                username,
+                password
+            ),
+        };
+        lines.extend([
+            "const { chromium } = require('playwright');".to_string(),
+            String::new(),
+            format!("const SPOOF_SCRIPT = {};", js(&script)),
+            String::new(),
+            "async function launchProfile() {".to_string(),
+            format!("  const browser = await chromium.launch({});", launch_options),
+            "  const context = await browser.newContext({".to_string(),
+            format!("    userAgent: {},", js(&fingerprint.user_agent)),
+            format!("    viewport: {{ width: {}, height: {} }},", viewport_width, viewport_height),
+            format!(
+                "    screen: {{ width: {}, height: {} }},",
+                fingerprint.screen_width, fingerprint.screen_height
+            ),
+            format!("    locale: {},", js(&fingerprint.language)),
+            format!("    timezoneId: {},", js(&fingerprint.timezone)),
+            format!("    extraHTTPHeaders: {},", js(&fingerprint.custom_headers)),
+            "  });".to_string(),
+            "  await context.addInitScript({ content: SPOOF_SCRIPT });".to_string(),
+            "  return { browser, context };".to_string(),
+            "}".to_string(),
+        ]);
+    } else {
+        let mut args = vec![
+            format!("--lang={}", fingerprint.language),
+            format!("--window-size={},{}", fingerprint.screen_width, fingerprint.screen_height),
+        ];
+        if let Some((server, _)) = &proxy {
+            args.push(format!("--proxy-server={}", server));
+        }
+        let mut headers = fingerprint.custom_headers.clone();
+        headers
+            .entry("Accept-Language".to_string())
+            .or_insert_with(|| accept_language(&languages));
+
+        lines.extend([
+            "const puppeteer = require('puppeteer');".to_string(),
+            String::new(),
+            format!("const SPOOF_SCRIPT = {};", js(&script)),
+            String::new(),
+            "async function launchProfile() {".to_string(),
+            "  const browser = await puppeteer.launch({".to_string(),
+            format!("    args: {},", js(&args)),
+            format!(
+                "    defaultViewport: {{ width: {}, height: {} }},",
+                viewport_width, viewport_height
+            ),
+            "  });".to_string(),
+            "  const page = await browser.newPage();".to_string(),
+        ]);
+        if let Some((_, Some((username, password)))) = &proxy {
+            lines.push(format!(
+                "  await page.authenticate({{ username: {}, password: {} }});",
+                username, password
+            ));
+        }
+        lines.extend([
+            format!("  await page.setUserAgent({});", js(&fingerprint.user_agent)),
+            format!("  await page.emulateTimezone({});", js(&fingerprint.timezone)),
+            format!("  await page.setExtraHTTPHeaders({});", js(&headers)),
+            "  await page.evaluateOnNewDocument(SPOOF_SCRIPT);".to_string(),
+            "  return { browser, page };".to_string(),
+            "}".to_string(),
+        ]);
+    }
+    lines.extend([String::new(), "module.exports = { launchProfile };".to_string(), String::new()]);
+    Ok(lines.join("\n"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fingerprint::FingerprintGenerator;
+
+    /// Evaluate exported code in QuickJS with stub `require`/`module`, so a
+    /// syntax error fails the test
+    fn parses(code: &str) -> bool {
+        let runtime = rquickjs::Runtime::new().unwrap();
+        let context = rquickjs::Context::full(&runtime).unwrap();
+        context.with(|ctx| {
+            let prelude = "var module = {}; var process = { env: {} }; function require() { return {}; }";
+            ctx.eval::<(), _>(format!("{}\n{}", prelude, code)).is_ok()
+                && ctx.eval::<String, _>("typeof module.exports.launchProfile").unwrap() == "function"
+        })
+    }
+
+    #[test]
+    fn test_accept_language() {
+        let languages = ["de-DE", "de", "en-US", "en"].map(String::from);
+        assert_eq!(accept_language(&languages), "de-DE,de;q=0.9,en-US;q=0.8,en;q=0.7");
+    }
+
+    #[test]
+    fn test_automation_config() {
+        let mut fp = FingerprintGenerator::new().generate_for_platform("windows");
+        fp.user_agent = "Mozilla/5.0 \"quoted\"".to_string();
+        fp.proxy_enabled = true;
+        fp.proxy_type = "https".to_string();
+        fp.proxy_host = "gw.example.net".to_string();
+        fp.proxy_port = 8080;
+        fp.proxy_username = Some("user".to_string());
+        fp.proxy_password = Some("hunter2".to_string());
+
+        for target in AUTOMATION_TARGETS {
+            let code = automation_config(&fp, "p1", "Shop\n EU", target, false).unwrap();
+            assert!(parses(&code), "{} export doesn't parse:\n{}", target, code);
+            assert!(code.starts_with(&format!("// IdentityForge profile \"Shop  EU\" (p1) for {}\n", target)));
+            assert!(code.contains("http://gw.example.net:8080"));
+            assert!(code.contains(&js(&fp.user_agent)));
+            assert!(code.contains(&format!("process.env.{}", PASSWORD_ENV)));
+            assert!(!code.contains("hunter2"));
+
+            let code = automation_config(&fp, "p1", "Shop", target, true).unwrap();
+            assert!(parses(&code));
+            assert!(code.contains("\"hunter2\""));
+        }
+
+        fp.proxy_enabled = false;
+        let code = automation_config(&fp, "p1", "Shop", "Playwright", false).unwrap();
+        assert!(code.contains("chromium.launch()"));
+        assert!(automation_config(&fp, "p1", "Shop", "selenium", false).is_err());
+    }
+}
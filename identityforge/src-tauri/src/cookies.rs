@@ -0,0 +1,473 @@
+use crate::database::Database;
+use serde::{Deserialize, Serialize};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Cookie structure for import/export
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Cookie {
+    pub name: String,
+    pub value: String,
+    pub domain: String,
+    pub path: String,
+    #[serde(alias = "expirationDate")]
+    pub expires: Option<f64>,
+    #[serde(alias = "httpOnly")]
+    pub http_only: Option<bool>,
+    pub secure: Option<bool>,
+    #[serde(alias = "sameSite")]
+    pub same_site: Option<String>,
+}
+
+/// Why a single cookie entry was rejected
+#[derive(Serialize, Debug, Clone)]
+pub struct CookieError {
+    /// Zero-based position in the imported array
+    pub index: usize,
+    pub name: Option<String>,
+    pub message: String,
+}
+
+impl std::fmt::Display for CookieError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match &self.name {
+            Some(name) => write!(f, "Cookie #{} ({}): {}", self.index, name, self.message),
+            None => write!(f, "Cookie #{}: {}", self.index, self.message),
+        }
+    }
+}
+
+/// Current time as fractional seconds since the Unix epoch
+fn now_secs() -> f64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs_f64())
+        .unwrap_or(0.0)
+}
+
+/// Normalize a `same_site` value to `Strict`/`Lax`/`None`.
+/// Browser extension exports use `no_restriction` and `unspecified` as well.
+fn normalize_same_site(value: &str) -> Result<Option<String>, String> {
+    match value.trim().to_lowercase().as_str() {
+        "strict" => Ok(Some("Strict".to_string())),
+        "lax" => Ok(Some("Lax".to_string())),
+        "none" | "no_restriction" => Ok(Some("None".to_string())),
+        "" | "unspecified" => Ok(None),
+        other => Err(format!(
+            "invalid same_site value '{}' (expected Strict, Lax or None)",
+            other
+        )),
+    }
+}
+
+/// Check and normalize one deserialized cookie in place. Browsers drop
+/// `SameSite=None` cookies that aren't secure; with `secure_same_site_none`
+/// they are marked secure instead of rejected. Expiry is checked separately.
+fn validate_cookie(cookie: &mut Cookie, secure_same_site_none: bool) -> Result<(), String> {
+    if cookie.name.trim().is_empty() {
+        return Err("name must not be empty".to_string());
+    }
+    if cookie.domain.trim().is_empty() {
+        return Err("domain must not be empty".to_string());
+    }
+    if let Some(same_site) = cookie.same_site.as_deref() {
+        cookie.same_site = normalize_same_site(same_site)?;
+    }
+    if cookie.same_site.as_deref() == Some("None") && cookie.secure != Some(true) {
+        if !secure_same_site_none {
+            return Err("same_site None requires secure, or browsers drop the cookie".to_string());
+        }
+        log::info!("Marking cookie '{}' ({}) secure because same_site is None", cookie.name, cookie.domain);
+        cookie.secure = Some(true);
+    }
+    Ok(())
+}
+
+/// Why a cookie is unusable because of its expiry, if it is
+fn expiry_error(cookie: &Cookie, now: f64) -> Option<String> {
+    cookie
+        .expires
+        .filter(|&expires| expires <= now)
+        .map(|expires| format!("already expired (expires {})", expires as i64))
+}
+
+/// Parse the top-level JSON array of a cookies file
+fn parse_entries(json: &str) -> Result<Vec<serde_json::Value>, CookieError> {
+    serde_json::from_str(json).map_err(|e| CookieError {
+        index: 0,
+        name: None,
+        message: format!("expected a JSON array of cookies: {}", e),
+    })
+}
+
+/// Deserialize and validate one array entry, returning its name for error reports
+fn parse_entry(entry: serde_json::Value, secure_same_site_none: bool) -> (Option<String>, Result<Cookie, String>) {
+    let name = entry
+        .get("name")
+        .and_then(|n| n.as_str())
+        .map(|n| n.to_string());
+
+    let result = serde_json::from_value::<Cookie>(entry)
+        .map_err(|e| e.to_string())
+        .and_then(|mut cookie| validate_cookie(&mut cookie, secure_same_site_none).map(|_| cookie));
+    (name, result)
+}
+
+/// Parse a cookies JSON array, validating each entry individually.
+/// Returns the normalized cookies, or every entry that failed and why.
+pub fn parse_cookies(json: &str, secure_same_site_none: bool) -> Result<Vec<Cookie>, Vec<CookieError>> {
+    let entries = parse_entries(json).map_err(|e| vec![e])?;
+
+    let now = now_secs();
+    let mut cookies = Vec::with_capacity(entries.len());
+    let mut errors = Vec::new();
+
+    for (index, entry) in entries.into_iter().enumerate() {
+        let (name, result) = parse_entry(entry, secure_same_site_none);
+        let result = result.and_then(|cookie| match expiry_error(&cookie, now) {
+            Some(message) => Err(message),
+            None => Ok(cookie),
+        });
+
+        match result {
+            Ok(cookie) => cookies.push(cookie),
+            Err(message) => errors.push(CookieError { index, name, message }),
+        }
+    }
+
+    if errors.is_empty() {
+        Ok(cookies)
+    } else {
+        Err(errors)
+    }
+}
+
+/// Cookies sharing one (name, domain, path) key
+#[derive(Serialize, Debug, Clone)]
+pub struct DuplicateCookie {
+    pub name: String,
+    pub domain: String,
+    pub path: String,
+    /// Zero-based positions of every entry with this key
+    pub indices: Vec<usize>,
+}
+
+/// Everything `import_cookies` would object to in a cookies file
+#[derive(Serialize, Debug, Clone)]
+pub struct CookieValidation {
+    pub total: usize,
+    /// Entries rejected for a reason other than expiry
+    pub errors: Vec<CookieError>,
+    /// Keys that appear more than once; the last entry wins on merge
+    pub duplicates: Vec<DuplicateCookie>,
+    /// Otherwise valid entries that have already expired
+    pub expired: Vec<CookieError>,
+}
+
+/// Check a cookies JSON array without importing it. Only a file that isn't
+/// a JSON array is an error; per-entry problems go in the report.
+pub fn validate_cookies(json: &str, secure_same_site_none: bool) -> Result<CookieValidation, CookieError> {
+    let entries = parse_entries(json)?;
+
+    let now = now_secs();
+    let mut report = CookieValidation {
+        total: entries.len(),
+        errors: Vec::new(),
+        duplicates: Vec::new(),
+        expired: Vec::new(),
+    };
+    let mut keys: Vec<DuplicateCookie> = Vec::new();
+
+    for (index, entry) in entries.into_iter().enumerate() {
+        let (name, result) = parse_entry(entry, secure_same_site_none);
+        let cookie = match result {
+            Ok(cookie) => cookie,
+            Err(message) => {
+                report.errors.push(CookieError { index, name, message });
+                continue;
+            }
+        };
+        if let Some(message) = expiry_error(&cookie, now) {
+            report.expired.push(CookieError { index, name, message });
+        }
+
+        let existing = keys.iter_mut().find(|key| {
+            key.name == cookie.name && key.domain == cookie.domain && key.path == cookie.path
+        });
+        match existing {
+            Some(key) => key.indices.push(index),
+            None => keys.push(DuplicateCookie {
+                name: cookie.name,
+                domain: cookie.domain,
+                path: cookie.path,
+                indices: vec![index],
+            }),
+        }
+    }
+
+    report.duplicates = keys.into_iter().filter(|key| key.indices.len() > 1).collect();
+    Ok(report)
+}
+
+/// Merge `incoming` into `existing`, deduplicating by (name, domain, path).
+/// Incoming cookies win on conflict; existing order is kept and new cookies are appended.
+pub fn merge_cookies(existing: Vec<Cookie>, incoming: Vec<Cookie>) -> Vec<Cookie> {
+    let mut merged = existing;
+    for cookie in incoming {
+        let position = merged.iter().position(|c| {
+            c.name == cookie.name && c.domain == cookie.domain && c.path == cookie.path
+        });
+        match position {
+            Some(i) => merged[i] = cookie,
+            None => merged.push(cookie),
+        }
+    }
+    merged
+}
+
+/// Read a profile's stored cookies (empty if none saved yet)
+pub fn read_cookies(db: &Database, profile_id: &str) -> Result<Vec<Cookie>, String> {
+    let cookies_path = db.get_cookies_path(profile_id);
+    if !cookies_path.exists() {
+        return Ok(Vec::new());
+    }
+    let content = std::fs::read_to_string(&cookies_path)
+        .map_err(|e| format!("Failed to read cookies: {}", e))?;
+    serde_json::from_str(&content).map_err(|e| format!("Stored cookies are corrupt: {}", e))
+}
+
+/// Write a profile's cookies in normalized form
+pub fn write_cookies(db: &Database, profile_id: &str, cookies: &[Cookie]) -> Result<(), String> {
+    let cookies_path = db.get_cookies_path(profile_id);
+
+    // Ensure parent directory exists
+    if let Some(parent) = cookies_path.parent() {
+        std::fs::create_dir_all(parent).ok();
+    }
+
+    let json = serde_json::to_string_pretty(cookies)
+        .map_err(|e| format!("Failed to serialize cookies: {}", e))?;
+    std::fs::write(&cookies_path, json).map_err(|e| format!("Failed to save cookies: {}", e))
+}
+
+/// Drop cookies past their expiry and, with `drop_session`, session cookies
+/// (those without one). Returns how many were removed.
+fn purge_stale(cookies: &mut Vec<Cookie>, now: f64, drop_session: bool) -> usize {
+    let before = cookies.len();
+    cookies.retain(|cookie| match cookie.expires {
+        Some(_) => expiry_error(cookie, now).is_none(),
+        None => !drop_session,
+    });
+    before - cookies.len()
+}
+
+/// Remove expired cookies from a profile's stored cookies, returning how
+/// many went. Cookies carry no creation time, so with `older_than_days`
+/// session cookies are dropped too when the file was last saved at least
+/// that long ago.
+/// The file is only rewritten when something is removed.
+pub fn purge_stored_cookies(db: &Database, profile_id: &str, older_than_days: Option<u32>) -> Result<usize, String> {
+    let mut cookies = read_cookies(db, profile_id)?;
+    if cookies.is_empty() {
+        return Ok(0);
+    }
+    let now = now_secs();
+    let saved_at = std::fs::metadata(db.get_cookies_path(profile_id))
+        .and_then(|meta| meta.modified())
+        .map_err(|e| format!("Failed to read cookies: {}", e))?
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs_f64())
+        .unwrap_or(0.0);
+    let drop_session = older_than_days.is_some_and(|days| now - saved_at >= days as f64 * 86400.0);
+
+    let removed = purge_stale(&mut cookies, now, drop_session);
+    if removed > 0 {
+        write_cookies(db, profile_id, &cookies)?;
+    }
+    Ok(removed)
+}
+
+/// Convert a cookie read from a live webview into the stored form
+pub fn from_webview(cookie: &tauri::webview::Cookie<'_>) -> Cookie {
+    Cookie {
+        name: cookie.name().to_string(),
+        value: cookie.value().to_string(),
+        domain: cookie.domain().unwrap_or_default().to_string(),
+        path: cookie.path().unwrap_or("/").to_string(),
+        expires: cookie.expires_datetime().map(|e| e.unix_timestamp() as f64),
+        http_only: cookie.http_only(),
+        secure: cookie.secure(),
+        same_site: cookie.same_site().map(|s| s.to_string()),
+    }
+}
+
+/// Serialize cookies in the Netscape `cookies.txt` layout used by curl and wget.
+/// Session cookies get an expiry of 0; HttpOnly cookies get the `#HttpOnly_` domain prefix.
+pub fn to_netscape(cookies: &[Cookie]) -> String {
+    let mut out = String::from("# Netscape HTTP Cookie File\n");
+    for cookie in cookies {
+        let flag = |b: bool| if b { "TRUE" } else { "FALSE" };
+        let prefix = if cookie.http_only.unwrap_or(false) { "#HttpOnly_" } else { "" };
+        let path = if cookie.path.is_empty() { "/" } else { &cookie.path };
+        out.push_str(&format!(
+            "{}{}\t{}\t{}\t{}\t{}\t{}\t{}\n",
+            prefix,
+            cookie.domain,
+            flag(cookie.domain.starts_with('.')),
+            path,
+            flag(cookie.secure.unwrap_or(false)),
+            cookie.expires.map(|e| e as i64).unwrap_or(0),
+            cookie.name,
+            cookie.value,
+        ));
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_valid_cookies_normalizes_same_site() {
+        let json = r#"[
+            {"name": "a", "value": "1", "domain": ".example.com", "path": "/", "sameSite": "no_restriction", "secure": true},
+            {"name": "b", "value": "2", "domain": ".example.com", "path": "/", "same_site": "LAX"},
+            {"name": "c", "value": "3", "domain": ".example.com", "path": "/", "sameSite": "unspecified"}
+        ]"#;
+        let cookies = parse_cookies(json, false).unwrap();
+        assert_eq!(cookies[0].same_site.as_deref(), Some("None"));
+        assert_eq!(cookies[1].same_site.as_deref(), Some("Lax"));
+        assert_eq!(cookies[2].same_site, None);
+    }
+
+    #[test]
+    fn test_same_site_none_requires_secure() {
+        let json = r#"[
+            {"name": "a", "value": "1", "domain": ".example.com", "path": "/", "sameSite": "none"},
+            {"name": "b", "value": "2", "domain": ".example.com", "path": "/", "sameSite": "none", "secure": false}
+        ]"#;
+        let errors = parse_cookies(json, false).unwrap_err();
+        assert_eq!(errors.len(), 2);
+        assert!(errors[0].message.contains("requires secure"));
+
+        let cookies = parse_cookies(json, true).unwrap();
+        assert!(cookies.iter().all(|c| c.secure == Some(true)));
+    }
+
+    #[test]
+    fn test_parse_reports_each_failing_entry() {
+        let json = r#"[
+            {"value": "1", "domain": ".example.com", "path": "/"},
+            {"name": "ok", "value": "2", "domain": ".example.com", "path": "/"},
+            {"name": "bad", "value": "3", "domain": ".example.com", "path": "/", "sameSite": "sometimes"},
+            {"name": "old", "value": "4", "domain": ".example.com", "path": "/", "expirationDate": 1000.0}
+        ]"#;
+        let errors = parse_cookies(json, false).unwrap_err();
+        assert_eq!(errors.len(), 3);
+        assert_eq!(errors[0].index, 0);
+        assert!(errors[0].message.contains("name"));
+        assert_eq!(errors[1].name.as_deref(), Some("bad"));
+        assert!(errors[1].message.contains("same_site"));
+        assert!(errors[2].message.contains("expired"));
+    }
+
+    #[test]
+    fn test_validate_cookies_reports_without_rejecting() {
+        let json = r#"[
+            {"name": "sid", "value": "1", "domain": ".example.com", "path": "/"},
+            {"value": "2", "domain": ".example.com", "path": "/"},
+            {"name": "old", "value": "3", "domain": ".example.com", "path": "/", "expirationDate": 1000.0},
+            {"name": "sid", "value": "4", "domain": ".example.com", "path": "/"},
+            {"name": "sid", "value": "5", "domain": ".example.com", "path": "/app"}
+        ]"#;
+        let report = validate_cookies(json, false).unwrap();
+        assert_eq!(report.total, 5);
+        assert_eq!(report.errors.len(), 1);
+        assert_eq!(report.errors[0].index, 1);
+        assert_eq!(report.expired.len(), 1);
+        assert_eq!(report.expired[0].name.as_deref(), Some("old"));
+        assert_eq!(report.duplicates.len(), 1);
+        assert_eq!(report.duplicates[0].indices, vec![0, 3]);
+
+        assert!(validate_cookies("{}", false).is_err());
+    }
+
+    #[test]
+    fn test_merge_prefers_incoming() {
+        let cookie = |name: &str, domain: &str, value: &str| Cookie {
+            name: name.to_string(),
+            value: value.to_string(),
+            domain: domain.to_string(),
+            path: "/".to_string(),
+            expires: None,
+            http_only: None,
+            secure: None,
+            same_site: None,
+        };
+        let existing = vec![cookie("sid", "a.com", "old"), cookie("pref", "a.com", "x")];
+        let incoming = vec![cookie("sid", "a.com", "new"), cookie("sid", "b.com", "other")];
+
+        let merged = merge_cookies(existing, incoming);
+        assert_eq!(merged.len(), 3);
+        assert_eq!(merged[0].value, "new");
+        assert_eq!(merged[1].name, "pref");
+        assert_eq!(merged[2].domain, "b.com");
+    }
+
+    #[test]
+    fn test_purge_stale_cookies() {
+        let json = r#"[
+            {"name": "old", "value": "1", "domain": ".example.com", "path": "/", "expires": 1000.0},
+            {"name": "live", "value": "2", "domain": ".example.com", "path": "/", "expires": 4000000000.0},
+            {"name": "session", "value": "3", "domain": ".example.com", "path": "/"}
+        ]"#;
+        let mut cookies: Vec<Cookie> = serde_json::from_str(json).unwrap();
+        assert_eq!(purge_stale(&mut cookies, 2000.0, false), 1);
+        assert_eq!(cookies.iter().map(|c| c.name.as_str()).collect::<Vec<_>>(), ["live", "session"]);
+        assert_eq!(purge_stale(&mut cookies, 2000.0, true), 1);
+        assert_eq!(cookies[0].name, "live");
+
+        let dir = std::env::temp_dir().join(format!("identityforge-cookie-purge-{}", std::process::id()));
+        std::fs::remove_dir_all(&dir).ok();
+        let db = Database::new(&dir.join("test.db"), dir.join("profiles")).unwrap();
+        std::fs::create_dir_all(dir.join("profiles").join("p")).unwrap();
+        std::fs::write(db.get_cookies_path("p"), json).unwrap();
+        // Just saved, so its session cookie isn't old enough yet
+        assert_eq!(purge_stored_cookies(&db, "p", Some(1)).unwrap(), 1);
+        assert_eq!(purge_stored_cookies(&db, "p", Some(0)).unwrap(), 1);
+        assert_eq!(read_cookies(&db, "p").unwrap().len(), 1);
+        assert_eq!(purge_stored_cookies(&db, "missing", None).unwrap(), 0);
+        std::fs::remove_dir_all(&dir).ok();
+    }
+
+    #[test]
+    fn test_to_netscape() {
+        let cookies = vec![
+            Cookie {
+                name: "sid".to_string(),
+                value: "abc".to_string(),
+                domain: ".example.com".to_string(),
+                path: "/".to_string(),
+                expires: Some(1999999999.5),
+                http_only: Some(true),
+                secure: Some(true),
+                same_site: None,
+            },
+            Cookie {
+                name: "pref".to_string(),
+                value: "x".to_string(),
+                domain: "example.com".to_string(),
+                path: "/app".to_string(),
+                expires: None,
+                http_only: None,
+                secure: None,
+                same_site: None,
+            },
+        ];
+
+        let exported = to_netscape(&cookies);
+        let lines: Vec<&str> = exported.lines().skip(1).collect();
+        assert_eq!(lines[0], "#HttpOnly_.example.com\tTRUE\t/\tTRUE\t1999999999\tsid\tabc");
+        assert_eq!(lines[1], "example.com\tFALSE\t/app\tFALSE\t0\tpref\tx");
+    }
+}
@@ -0,0 +1,265 @@
+use crate::database::{Database, DatabaseError};
+use crate::snapshots::copy_dir;
+use std::ffi::OsString;
+use std::path::{Path, PathBuf};
+use thiserror::Error;
+
+/// Environment variable that relocates the data directory, ahead of any
+/// `relocate_data_dir` override
+pub const DATA_DIR_ENV: &str = "IDENTITYFORGE_DATA_DIR";
+
+/// File in the default app data directory holding the relocated path. It
+/// lives outside the database because it says where the database is.
+const OVERRIDE_FILE: &str = "data_dir_override";
+
+/// File in a new data directory naming the one it was copied from, which is
+/// deleted at the next startup
+const PREVIOUS_LOCATION_FILE: &str = ".previous_location";
+
+pub const DB_FILE: &str = "identityforge.db";
+pub const PROFILES_DIR: &str = "profiles";
+pub const SNAPSHOTS_DIR: &str = "snapshots";
+
+#[derive(Error, Debug)]
+pub enum RelocateError {
+    #[error("The data directory is set by {DATA_DIR_ENV}; change the variable instead")]
+    SetByEnvironment,
+    #[error("New data directory must be an absolute path: {0}")]
+    NotAbsolute(String),
+    #[error("New data directory must be empty: {0}")]
+    NotEmpty(String),
+    #[error("New data directory can't be inside the current one or contain it")]
+    Nested,
+    #[error("Database error: {0}")]
+    Database(#[from] DatabaseError),
+    #[error("IO error: {0}")]
+    Io(#[from] std::io::Error),
+}
+
+/// Where the database and profiles live: `env_override` (the value of
+/// `DATA_DIR_ENV`), else the path saved by `relocate`, else `default_dir`
+pub fn resolve_data_dir(default_dir: &Path, env_override: Option<OsString>) -> PathBuf {
+    if let Some(dir) = env_override.filter(|dir| !dir.is_empty()) {
+        return PathBuf::from(dir);
+    }
+    match std::fs::read_to_string(default_dir.join(OVERRIDE_FILE)) {
+        Ok(path) if !path.trim().is_empty() => PathBuf::from(path.trim()),
+        _ => default_dir.to_path_buf(),
+    }
+}
+
+/// Copy the database, profiles and snapshots from `current` into the empty
+/// directory `target`, then point future startups at it. Until the pointer
+/// is switched nothing refers to the copy, so a failure midway leaves the
+/// current directory in use and the partial copy is removed. The running
+/// app keeps using `current` and has to restart.
+pub fn relocate(db: &Database, current: &Path, target: &Path, default_dir: &Path) -> Result<(), RelocateError> {
+    if !target.is_absolute() {
+        return Err(RelocateError::NotAbsolute(target.display().to_string()));
+    }
+    let target_existed = target.exists();
+    if target_existed {
+        // Moving back to the default directory, which holds the override file
+        let blocking = std::fs::read_dir(target)?
+            .filter_map(|entry| entry.ok())
+            .any(|entry| !(target == default_dir && entry.file_name() == OVERRIDE_FILE));
+        if blocking {
+            return Err(RelocateError::NotEmpty(target.display().to_string()));
+        }
+    }
+    std::fs::create_dir_all(target)?;
+
+    let result = check_not_nested(current, target).and_then(|_| copy_data(db, current, target));
+    if let Err(e) = result {
+        discard_copy(target, target_existed);
+        return Err(e);
+    }
+    if let Err(e) = point_to(default_dir, target) {
+        discard_copy(target, target_existed);
+        return Err(e.into());
+    }
+    Ok(())
+}
+
+fn check_not_nested(current: &Path, target: &Path) -> Result<(), RelocateError> {
+    let current = std::fs::canonicalize(current)?;
+    let target = std::fs::canonicalize(target)?;
+    if current.starts_with(&target) || target.starts_with(&current) {
+        return Err(RelocateError::Nested);
+    }
+    Ok(())
+}
+
+fn copy_data(db: &Database, current: &Path, target: &Path) -> Result<(), RelocateError> {
+    db.copy_to(&target.join(DB_FILE))?;
+    for dir in [PROFILES_DIR, SNAPSHOTS_DIR] {
+        if current.join(dir).is_dir() {
+            copy_dir(&current.join(dir), &target.join(dir))?;
+        }
+    }
+    std::fs::write(target.join(PREVIOUS_LOCATION_FILE), current.to_string_lossy().as_bytes())?;
+    Ok(())
+}
+
+/// Remove a failed copy, keeping a directory that existed before
+fn discard_copy(target: &Path, target_existed: bool) {
+    let names = [DB_FILE, PROFILES_DIR, SNAPSHOTS_DIR, PREVIOUS_LOCATION_FILE];
+    let result = if target_existed {
+        names.iter().map(|name| target.join(name)).try_for_each(|path| match path.is_dir() {
+            true => std::fs::remove_dir_all(&path),
+            false if path.exists() => std::fs::remove_file(&path),
+            false => Ok(()),
+        })
+    } else {
+        std::fs::remove_dir_all(target)
+    };
+    if let Err(e) = result {
+        log::warn!("Failed to remove partial data directory copy at {}: {}", target.display(), e);
+    }
+}
+
+/// Save `target` as the data directory for future startups. The file is
+/// replaced by rename, so a crash leaves either the old or the new path.
+fn point_to(default_dir: &Path, target: &Path) -> std::io::Result<()> {
+    let file = default_dir.join(OVERRIDE_FILE);
+    if target == default_dir {
+        return match std::fs::remove_file(&file) {
+            Err(e) if e.kind() != std::io::ErrorKind::NotFound => Err(e),
+            _ => Ok(()),
+        };
+    }
+    std::fs::create_dir_all(default_dir)?;
+    let staging = default_dir.join(format!("{}.tmp", OVERRIDE_FILE));
+    std::fs::write(&staging, target.to_string_lossy().as_bytes())?;
+    std::fs::rename(&staging, &file)
+}
+
+/// Delete what `copy_dir` copied from `dir`: regular files and directories.
+/// Symlinks weren't copied, so they're left, along with the directories
+/// holding them.
+fn remove_copied(dir: &Path) -> std::io::Result<()> {
+    for entry in std::fs::read_dir(dir)? {
+        let entry = entry?;
+        let file_type = entry.file_type()?;
+        if file_type.is_dir() {
+            remove_copied(&entry.path())?;
+        } else if file_type.is_file() {
+            std::fs::remove_file(entry.path())?;
+        }
+    }
+    match std::fs::remove_dir(dir) {
+        Err(e) if std::fs::read_dir(dir).map_or(true, |mut entries| entries.next().is_none()) => Err(e),
+        _ => Ok(()),
+    }
+}
+
+/// Delete the data a relocation into `data_dir` copied from, once the app
+/// is running from the new location and `db`, opened there, reads back
+/// cleanly. Failures are logged and retried at the next startup.
+pub fn remove_previous_location(db: &Database, data_dir: &Path) {
+    let marker = data_dir.join(PREVIOUS_LOCATION_FILE);
+    let Ok(previous) = std::fs::read_to_string(&marker) else {
+        return;
+    };
+    let sane = match (db.check_integrity(), db.get_all_profiles()) {
+        (Ok(report), Ok(_)) => report.ok,
+        _ => false,
+    };
+    if !sane {
+        log::warn!(
+            "Keeping previous data directory {}: the database here failed its check",
+            previous.trim()
+        );
+        return;
+    }
+    let previous = PathBuf::from(previous.trim());
+    let same = match (std::fs::canonicalize(&previous), std::fs::canonicalize(data_dir)) {
+        (Ok(previous), Ok(current)) => previous == current,
+        _ => false,
+    };
+
+    // Only what a relocation copies; the directory itself may hold other files
+    let mut result = Ok(());
+    if !same {
+        for name in [DB_FILE, PROFILES_DIR, SNAPSHOTS_DIR] {
+            let path = previous.join(name);
+            let removed = if path.is_dir() {
+                remove_copied(&path)
+            } else if path.exists() {
+                std::fs::remove_file(&path)
+            } else {
+                Ok(())
+            };
+            result = result.and(removed);
+        }
+    }
+    match result.and_then(|_| std::fs::remove_file(&marker)) {
+        Ok(()) => log::info!("Removed previous data directory contents at {}", previous.display()),
+        Err(e) => log::warn!("Failed to clean up previous data directory {}: {}", previous.display(), e),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_relocate_and_clean_up() {
+        let base = std::env::temp_dir().join(format!("identityforge-relocate-{}", std::process::id()));
+        std::fs::remove_dir_all(&base).ok();
+        let default_dir = base.join("default");
+        let db = Database::new(&default_dir.join(DB_FILE), default_dir.join(PROFILES_DIR)).unwrap();
+        db.set_setting("marker", "kept").unwrap();
+        std::fs::create_dir_all(default_dir.join(PROFILES_DIR).join("p")).unwrap();
+        std::fs::write(default_dir.join(PROFILES_DIR).join("p").join("cookies.json"), "[]").unwrap();
+        // Symlinked profile data isn't copied, so the cleanup mustn't drop it
+        #[cfg(unix)]
+        {
+            std::fs::create_dir_all(base.join("outside")).unwrap();
+            std::os::unix::fs::symlink(base.join("outside"), default_dir.join(PROFILES_DIR).join("linked")).unwrap();
+        }
+        assert_eq!(resolve_data_dir(&default_dir, None), default_dir);
+
+        // Refused targets leave nothing behind
+        let busy = base.join("busy");
+        std::fs::create_dir_all(&busy).unwrap();
+        std::fs::write(busy.join("other"), "x").unwrap();
+        assert!(matches!(relocate(&db, &default_dir, &busy, &default_dir), Err(RelocateError::NotEmpty(_))));
+        let nested = default_dir.join("inner");
+        assert!(matches!(relocate(&db, &default_dir, &nested, &default_dir), Err(RelocateError::Nested)));
+        assert!(!nested.exists());
+        assert!(matches!(
+            relocate(&db, &default_dir, Path::new("relative"), &default_dir),
+            Err(RelocateError::NotAbsolute(_))
+        ));
+        assert_eq!(resolve_data_dir(&default_dir, None), default_dir);
+
+        let moved = base.join("volume").join("data");
+        relocate(&db, &default_dir, &moved, &default_dir).unwrap();
+        assert_eq!(resolve_data_dir(&default_dir, None), moved);
+        assert_eq!(resolve_data_dir(&default_dir, Some("/env".into())), PathBuf::from("/env"));
+        assert!(moved.join(PROFILES_DIR).join("p").join("cookies.json").exists());
+        let copied = Database::new(&moved.join(DB_FILE), moved.join(PROFILES_DIR)).unwrap();
+        assert_eq!(copied.get_setting("marker").unwrap().as_deref(), Some("kept"));
+
+        // The next startup from the new location removes the old copy
+        remove_previous_location(&copied, &moved);
+        assert!(!default_dir.join(DB_FILE).exists());
+        assert!(!default_dir.join(PROFILES_DIR).join("p").exists());
+        #[cfg(unix)]
+        assert!(default_dir.join(PROFILES_DIR).join("linked").is_dir());
+        #[cfg(not(unix))]
+        assert!(!default_dir.join(PROFILES_DIR).exists());
+        assert!(default_dir.join(OVERRIDE_FILE).exists());
+        assert!(!moved.join(PREVIOUS_LOCATION_FILE).exists());
+
+        // Moving back to the default directory drops the override
+        #[cfg(unix)]
+        std::fs::remove_dir_all(default_dir.join(PROFILES_DIR)).unwrap();
+        relocate(&copied, &moved, &default_dir, &default_dir).unwrap();
+        assert_eq!(resolve_data_dir(&default_dir, None), default_dir);
+        assert!(default_dir.join(DB_FILE).exists());
+
+        std::fs::remove_dir_all(&base).ok();
+    }
+}
@@ -1,6 +1,9 @@
-use rusqlite::{params, Connection};
+use crate::migrations;
+use crate::templates::{builtin_templates, FingerprintTemplate};
+use rusqlite::{params, Connection, OptionalExtension};
 use serde::{Deserialize, Serialize};
-use std::path::PathBuf;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::path::{Component, Path, PathBuf};
 use std::sync::Mutex;
 use thiserror::Error;
 
@@ -10,8 +13,98 @@ pub enum DatabaseError {
     Sqlite(#[from] rusqlite::Error),
     #[error("Profile not found: {0}")]
     ProfileNotFound(String),
+    #[error("Fingerprint template not found: {0}")]
+    TemplateNotFound(String),
     #[error("IO error: {0}")]
     Io(#[from] std::io::Error),
+    /// Already phrased for the user by `describe_dir_error`
+    #[error("{0}")]
+    DataDir(String),
+    #[error("Repair failed: {0}")]
+    Repair(String),
+    #[error("Profile {0} is leased by another worker")]
+    LeasedByOther(String),
+}
+
+/// OS error codes for a full disk: ERROR_DISK_FULL, ERROR_HANDLE_DISK_FULL / ENOSPC
+#[cfg(windows)]
+const DISK_FULL_CODES: &[i32] = &[112, 39];
+#[cfg(not(windows))]
+const DISK_FULL_CODES: &[i32] = &[28];
+
+/// OS error codes for a read-only volume: ERROR_WRITE_PROTECT / EROFS
+#[cfg(windows)]
+const READ_ONLY_CODES: &[i32] = &[19];
+#[cfg(not(windows))]
+const READ_ONLY_CODES: &[i32] = &[30];
+
+/// Explain why a data directory couldn't be created in terms the user can act on
+fn describe_dir_error(path: &Path, error: &std::io::Error) -> String {
+    let code = error.raw_os_error().unwrap_or_default();
+    let blocking_file = path.ancestors().find(|p| p.exists()).filter(|p| !p.is_dir());
+
+    let reason = if let Some(file) = blocking_file {
+        format!("{} exists as a file, not a directory", file.display())
+    } else if DISK_FULL_CODES.contains(&code) {
+        "the disk is full".to_string()
+    } else if READ_ONLY_CODES.contains(&code) {
+        "the disk is read-only".to_string()
+    } else if error.kind() == std::io::ErrorKind::PermissionDenied {
+        "permission denied".to_string()
+    } else {
+        error.to_string()
+    };
+    format!("Can't create data directory {}: {}", path.display(), reason)
+}
+
+/// `create_dir_all` with failures reported through `describe_dir_error`
+pub fn create_data_dir(path: &Path) -> Result<(), DatabaseError> {
+    std::fs::create_dir_all(path).map_err(|e| DatabaseError::DataDir(describe_dir_error(path, &e)))
+}
+
+/// Data directories and profile rows that have drifted apart
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct ReconcileReport {
+    /// Directories in the profiles directory with no matching profile
+    pub orphan_dirs: Vec<String>,
+    /// Profiles whose data directory doesn't exist
+    pub missing_dirs: Vec<String>,
+    /// Whether orphans were deleted and missing directories recreated
+    pub repaired: bool,
+}
+
+/// What `import_config` applied and what it left alone
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct ConfigImportReport {
+    pub settings_imported: usize,
+    /// Keys already set, kept because `overwrite` was off
+    pub settings_skipped: Vec<String>,
+    pub templates_imported: usize,
+    /// Built-in template names, plus existing ones when `overwrite` is off
+    pub templates_skipped: Vec<String>,
+}
+
+/// Outcome of `check_database_integrity`
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct IntegrityReport {
+    /// Both checks came back clean
+    pub ok: bool,
+    /// `PRAGMA integrity_check` messages
+    pub integrity_issues: Vec<String>,
+    /// `PRAGMA foreign_key_check` rows, as "table row N references parent"
+    pub foreign_key_issues: Vec<String>,
+    /// Set when a repair was run before these checks
+    pub repair: Option<RepairReport>,
+}
+
+/// What `Database::repair` salvaged
+#[derive(Debug, Clone, Serialize)]
+pub struct RepairReport {
+    pub rows_recovered: u64,
+    /// Tables a read error cut short, so rows past it are lost
+    pub incomplete_tables: Vec<String>,
+    /// The damaged file, kept in case more can be recovered by hand
+    pub backup_path: String,
 }
 
 /// Proxy configuration for a profile
@@ -23,6 +116,23 @@ pub struct ProxyConfig {
     pub port: i32,
     pub username: Option<String>,
     pub password: Option<String>,
+    /// Host patterns (`example.com`, `*.example.com`) that connect directly
+    pub proxy_bypass: Vec<String>,
+}
+
+/// Canonical proxy type for a stored or typed one: trimmed and lowercased,
+/// with `socks`/`socks5h` meaning `socks5` and an empty type meaning `http`,
+/// the default everywhere else. Anything else is rejected by name.
+pub fn normalize_proxy_type(proxy_type: &str) -> Result<&'static str, String> {
+    match proxy_type.trim().to_ascii_lowercase().as_str() {
+        "" | "http" => Ok("http"),
+        "https" => Ok("https"),
+        "socks" | "socks5" | "socks5h" => Ok("socks5"),
+        other => Err(format!(
+            "unknown proxy type '{}' (expected http, https or socks5)",
+            other
+        )),
+    }
 }
 
 /// Represents a browser profile with fingerprint configuration
@@ -40,6 +150,24 @@ pub struct Profile {
     pub platform: String,
     pub timezone: String,
     pub language: String,
+    /// Ordered `navigator.languages`, stored as a JSON array. Empty for rows
+    /// that predate the column; see `fingerprint::navigator_languages`.
+    #[serde(default)]
+    pub languages: Vec<String>,
+    /// Pinned Do Not Track signal; `None` lets the profile seed decide
+    pub do_not_track: Option<bool>,
+    /// Canvas noise intensity: "low", "medium" or "high"
+    pub canvas_noise_level: String,
+    /// Pinned `window.innerWidth`; `None` derives it from the profile seed
+    pub inner_width: Option<i32>,
+    /// Pinned `window.innerHeight`; `None` derives it from the profile seed
+    pub inner_height: Option<i32>,
+    /// WebRTC handling; `None` follows the `default_webrtc_mode` setting
+    #[serde(default)]
+    pub webrtc_mode: Option<String>,
+    /// Extra request headers (name → value), stored as a JSON object
+    #[serde(default)]
+    pub custom_headers: BTreeMap<String, String>,
     pub default_url: String,
     // Proxy settings
     pub proxy_enabled: bool,
@@ -48,23 +176,214 @@ pub struct Profile {
     pub proxy_port: i32,
     pub proxy_username: Option<String>,
     pub proxy_password: Option<String>,
+    /// Host patterns that skip the proxy, stored as a JSON array
+    #[serde(default)]
+    pub proxy_bypass: Vec<String>,
     pub created_at: String,
     pub last_used: Option<String>,
+    /// Unix seconds when the user agent was last bumped by `age_fingerprint`
+    pub last_aged: Option<String>,
+    /// Unspoofed profile mirroring the host; launched without the spoof script
+    #[serde(default)]
+    pub is_system: bool,
+    /// Free-form labels for grouping profiles, stored as a JSON array
+    #[serde(default)]
+    pub tags: Vec<String>,
+    /// Times the profile has been launched. Only `record_launch` writes it,
+    /// so saving a stale copy of the profile can't lose an increment.
+    #[serde(default)]
+    pub launch_count: i64,
+    /// Automation worker holding the profile. Only `lease_profile` and
+    /// `release_profile` write the lease, like `launch_count`.
+    #[serde(default)]
+    pub leased_by: Option<String>,
+    /// Unix seconds when the current lease was taken
+    #[serde(default)]
+    pub leased_at: Option<i64>,
+    /// Pinned to the top of the profile list. Written by `toggle_favorite`.
+    #[serde(default)]
+    pub is_favorite: bool,
+    /// Manual list position from `reorder_profiles`; 0 = never placed, listed
+    /// first by creation date
+    #[serde(default)]
+    pub sort_order: i64,
+    /// Single required grouping such as `dev` or `prod`, unlike free-form tags
+    #[serde(default = "default_environment")]
+    pub environment: String,
+    /// Load every page fresh, for reproducible test runs. Only WebView2
+    /// (Windows) can switch its HTTP cache off; elsewhere just same-origin
+    /// fetch/XHR ask for fresh responses and page loads still hit the cache.
+    #[serde(default)]
+    pub disable_cache: bool,
+}
+
+/// Environment profiles belong to unless given another
+pub const DEFAULT_ENVIRONMENT: &str = "default";
+
+/// How long `create_profile_once` remembers an idempotency key
+pub const IDEMPOTENCY_KEY_TTL_SECS: i64 = 24 * 60 * 60;
+
+fn default_environment() -> String {
+    DEFAULT_ENVIRONMENT.to_string()
+}
+
+/// Upper bounds on free-text profile fields, in characters
+#[derive(Debug, Clone, Copy)]
+pub struct TextLimits {
+    pub name: usize,
+    /// User agent, default URL, environment and each tag
+    pub text: usize,
+}
+
+/// Reject `value` if it's longer than `max` characters, naming `field`
+pub fn check_text_length(field: &str, value: &str, max: usize) -> Result<(), String> {
+    let length = value.chars().count();
+    if length > max {
+        return Err(format!(
+            "{} is too long ({} characters, limit {})",
+            field, length, max
+        ));
+    }
+    Ok(())
 }
 
 impl Profile {
+    /// The profile's proxy, with `proxy_type` normalized when it's a known
+    /// spelling. Unknown types are kept as stored so building the URL names them.
     pub fn get_proxy_config(&self) -> ProxyConfig {
         ProxyConfig {
             enabled: self.proxy_enabled,
-            proxy_type: self.proxy_type.clone(),
+            proxy_type: normalize_proxy_type(&self.proxy_type)
+                .map(str::to_string)
+                .unwrap_or_else(|_| self.proxy_type.clone()),
             host: self.proxy_host.clone(),
             port: self.proxy_port,
             username: self.proxy_username.clone(),
             password: self.proxy_password.clone(),
+            proxy_bypass: self.proxy_bypass.clone(),
+        }
+    }
+
+    /// Check the fields the spoof script depends on, naming the first bad one
+    pub fn validate(&self) -> Result<(), String> {
+        let required = [
+            ("user_agent", &self.user_agent),
+            ("platform", &self.platform),
+            ("timezone", &self.timezone),
+            ("language", &self.language),
+            ("environment", &self.environment),
+        ];
+        if let Some((field, _)) = required.iter().find(|(_, value)| value.trim().is_empty()) {
+            return Err(format!("{} must not be empty", field));
+        }
+        if self.languages.iter().any(|l| l.trim().is_empty()) {
+            return Err("languages must not contain empty entries".to_string());
+        }
+
+        let positive = [
+            ("screen_width", self.screen_width),
+            ("screen_height", self.screen_height),
+            ("hardware_concurrency", self.hardware_concurrency),
+            ("device_memory", self.device_memory),
+        ];
+        if let Some((field, value)) = positive.iter().find(|(_, value)| *value <= 0) {
+            return Err(format!("{} must be positive (got {})", field, value));
+        }
+        Ok(())
+    }
+
+    /// Reject free-text fields longer than `limits`, so a huge paste can't
+    /// bloat the database or the spoof script
+    pub fn validate_lengths(&self, limits: TextLimits) -> Result<(), String> {
+        let fields = [("name", self.name.as_str(), limits.name)]
+            .into_iter()
+            .chain([
+                ("user_agent", self.user_agent.as_str(), limits.text),
+                ("default_url", self.default_url.as_str(), limits.text),
+                ("environment", self.environment.as_str(), limits.text),
+            ])
+            .chain(self.tags.iter().map(|tag| ("tag", tag.as_str(), limits.text)));
+        for (field, value, max) in fields {
+            check_text_length(field, value, max)?;
         }
+        Ok(())
     }
 }
 
+/// Minimal valid profile for tests: a Windows identity in UTC with no proxy
+#[cfg(test)]
+pub(crate) fn test_profile(id: &str) -> Profile {
+    serde_json::from_value(serde_json::json!({
+        "id": id, "name": id,
+        "user_agent": "Mozilla/5.0", "platform": "Win32",
+        "screen_width": 1920, "screen_height": 1080,
+        "webgl_vendor": "", "webgl_renderer": "",
+        "hardware_concurrency": 8, "device_memory": 8,
+        "timezone": "UTC", "language": "en-US",
+        "do_not_track": null, "canvas_noise_level": "medium",
+        "inner_width": null, "inner_height": null,
+        "default_url": "https://example.com",
+        "proxy_enabled": false, "proxy_type": "http", "proxy_host": "", "proxy_port": 0,
+        "proxy_username": null, "proxy_password": null,
+        "created_at": "0", "last_used": null, "last_aged": null,
+    }))
+    .unwrap()
+}
+
+/// Column list shared by every profile SELECT, in `row_to_profile` order
+const PROFILE_COLUMNS: &str = "id, name, user_agent, screen_width, screen_height,
+    webgl_vendor, webgl_renderer, hardware_concurrency,
+    device_memory, platform, timezone, language, default_url,
+    proxy_enabled, proxy_type, proxy_host, proxy_port, proxy_username, proxy_password,
+    created_at, last_used, do_not_track, canvas_noise_level, inner_width, inner_height,
+    custom_headers, last_aged, is_system, proxy_bypass, languages, tags,
+    webrtc_mode, launch_count, leased_by, leased_at, is_favorite, sort_order, environment,
+    disable_cache";
+
+fn row_to_profile(row: &rusqlite::Row) -> rusqlite::Result<Profile> {
+    Ok(Profile {
+        id: row.get(0)?,
+        name: row.get(1)?,
+        user_agent: row.get(2)?,
+        screen_width: row.get(3)?,
+        screen_height: row.get(4)?,
+        webgl_vendor: row.get(5)?,
+        webgl_renderer: row.get(6)?,
+        hardware_concurrency: row.get(7)?,
+        device_memory: row.get(8)?,
+        platform: row.get(9)?,
+        timezone: row.get(10)?,
+        language: row.get(11)?,
+        default_url: row.get(12)?,
+        proxy_enabled: row.get(13)?,
+        proxy_type: row.get(14)?,
+        proxy_host: row.get(15)?,
+        proxy_port: row.get(16)?,
+        proxy_username: row.get(17)?,
+        proxy_password: row.get(18)?,
+        created_at: row.get(19)?,
+        last_used: row.get(20)?,
+        do_not_track: row.get(21)?,
+        canvas_noise_level: row.get(22)?,
+        inner_width: row.get(23)?,
+        inner_height: row.get(24)?,
+        custom_headers: serde_json::from_str(&row.get::<_, String>(25)?).unwrap_or_default(),
+        last_aged: row.get(26)?,
+        is_system: row.get(27)?,
+        proxy_bypass: serde_json::from_str(&row.get::<_, String>(28)?).unwrap_or_default(),
+        languages: serde_json::from_str(&row.get::<_, String>(29)?).unwrap_or_default(),
+        tags: serde_json::from_str(&row.get::<_, String>(30)?).unwrap_or_default(),
+        webrtc_mode: row.get(31)?,
+        launch_count: row.get(32)?,
+        leased_by: row.get(33)?,
+        leased_at: row.get(34)?,
+        is_favorite: row.get(35)?,
+        sort_order: row.get(36)?,
+        environment: row.get(37)?,
+        disable_cache: row.get(38)?,
+    })
+}
+
 /// Database wrapper for thread-safe access
 pub struct Database {
     conn: Mutex<Connection>,
@@ -78,11 +397,11 @@ impl Database {
         if let Some(parent) = db_path.parent() {
             std::fs::create_dir_all(parent)?;
         }
-        std::fs::create_dir_all(&profiles_dir)?;
+        create_data_dir(&profiles_dir)?;
 
-        let conn = Connection::open(db_path)?;
+        let mut conn = Connection::open(db_path)?;
         
-        // Create profiles table with proxy fields
+        // Baseline profiles table; later columns are added by versioned migrations
         conn.execute(
             "CREATE TABLE IF NOT EXISTS profiles (
                 id TEXT PRIMARY KEY,
@@ -97,33 +416,13 @@ impl Database {
                 platform TEXT NOT NULL,
                 timezone TEXT NOT NULL,
                 language TEXT NOT NULL,
-                default_url TEXT NOT NULL DEFAULT 'https://www.google.com',
-                proxy_enabled INTEGER NOT NULL DEFAULT 0,
-                proxy_type TEXT NOT NULL DEFAULT 'http',
-                proxy_host TEXT NOT NULL DEFAULT '',
-                proxy_port INTEGER NOT NULL DEFAULT 0,
-                proxy_username TEXT,
-                proxy_password TEXT,
                 created_at TEXT NOT NULL,
                 last_used TEXT
             )",
             [],
         )?;
 
-        // Migration: Add columns if they don't exist
-        let migrations = [
-            "ALTER TABLE profiles ADD COLUMN default_url TEXT NOT NULL DEFAULT 'https://www.google.com'",
-            "ALTER TABLE profiles ADD COLUMN proxy_enabled INTEGER NOT NULL DEFAULT 0",
-            "ALTER TABLE profiles ADD COLUMN proxy_type TEXT NOT NULL DEFAULT 'http'",
-            "ALTER TABLE profiles ADD COLUMN proxy_host TEXT NOT NULL DEFAULT ''",
-            "ALTER TABLE profiles ADD COLUMN proxy_port INTEGER NOT NULL DEFAULT 0",
-            "ALTER TABLE profiles ADD COLUMN proxy_username TEXT",
-            "ALTER TABLE profiles ADD COLUMN proxy_password TEXT",
-        ];
-        
-        for migration in migrations {
-            let _ = conn.execute(migration, []);
-        }
+        migrations::run(&mut conn)?;
 
         // Create settings table for extensibility
         conn.execute(
@@ -146,6 +445,14 @@ impl Database {
             [],
         )?;
 
+        // Re-seed built-in templates so they track the shipped definitions
+        for template in builtin_templates() {
+            conn.execute(
+                "INSERT OR REPLACE INTO fingerprint_templates (name, template, builtin) VALUES (?1, ?2, 1)",
+                params![template.name, serde_json::to_string(&template).unwrap_or_default()],
+            )?;
+        }
+
         Ok(Database {
             conn: Mutex::new(conn),
             profiles_dir,
@@ -154,84 +461,126 @@ impl Database {
 
     /// Create a new profile
     pub fn create_profile(&self, profile: &Profile) -> Result<(), DatabaseError> {
+        // Create the data directory first so a full or read-only disk leaves no
+        // row behind that would later launch without isolation. The lock is
+        // taken before that so reconciling never sees the new directory as an orphan.
         let conn = self.conn.lock().unwrap();
+        self.with_new_data_dir(&profile.id, || Ok(insert_profile(&conn, profile)?))
+    }
+
+    /// Create a profile's data directory, then run `insert`. If that fails the
+    /// directory is removed again, unless it was already there.
+    fn with_new_data_dir<T>(
+        &self,
+        profile_id: &str,
+        insert: impl FnOnce() -> Result<T, DatabaseError>,
+    ) -> Result<T, DatabaseError> {
+        let dir = self.profiles_dir.join(profile_id);
+        let existed = dir.exists();
+        create_data_dir(&dir)?;
+        insert().inspect_err(|_| {
+            if !existed {
+                std::fs::remove_dir_all(&dir).ok();
+            }
+        })
+    }
+
+    /// Create `profile` unless a creation with the same idempotency `key`
+    /// happened within `IDEMPOTENCY_KEY_TTL_SECS`; then that profile is
+    /// returned instead and `profile` is discarded. The flag is true when a
+    /// row was created. Expired keys are swept on every call.
+    pub fn create_profile_once(&self, profile: &Profile, key: &str) -> Result<(Profile, bool), DatabaseError> {
+        let now = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap()
+            .as_secs() as i64;
+        let mut conn = self.conn.lock().unwrap();
         conn.execute(
-            "INSERT INTO profiles (
-                id, name, user_agent, screen_width, screen_height,
-                webgl_vendor, webgl_renderer, hardware_concurrency,
-                device_memory, platform, timezone, language, default_url,
-                proxy_enabled, proxy_type, proxy_host, proxy_port, proxy_username, proxy_password,
-                created_at, last_used
-            ) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15, ?16, ?17, ?18, ?19, ?20, ?21)",
-            params![
-                profile.id,
-                profile.name,
-                profile.user_agent,
-                profile.screen_width,
-                profile.screen_height,
-                profile.webgl_vendor,
-                profile.webgl_renderer,
-                profile.hardware_concurrency,
-                profile.device_memory,
-                profile.platform,
-                profile.timezone,
-                profile.language,
-                profile.default_url,
-                profile.proxy_enabled,
-                profile.proxy_type,
-                profile.proxy_host,
-                profile.proxy_port,
-                profile.proxy_username,
-                profile.proxy_password,
-                profile.created_at,
-                profile.last_used,
-            ],
+            "DELETE FROM idempotency_keys WHERE created_at <= ?1",
+            [now - IDEMPOTENCY_KEY_TTL_SECS],
         )?;
 
-        // Create profile data directory
-        let profile_dir = self.profiles_dir.join(&profile.id);
-        std::fs::create_dir_all(&profile_dir)?;
+        // A key whose profile was deleted since doesn't block a new one
+        let existing = conn
+            .query_row(
+                &format!(
+                    "SELECT {} FROM profiles WHERE id = (SELECT profile_id FROM idempotency_keys WHERE key = ?1)",
+                    PROFILE_COLUMNS
+                ),
+                [key],
+                row_to_profile,
+            )
+            .optional()?;
+        if let Some(existing) = existing {
+            return Ok((existing, false));
+        }
+
+        self.with_new_data_dir(&profile.id, || {
+            let tx = conn.transaction()?;
+            insert_profile(&tx, profile)?;
+            tx.execute(
+                "INSERT OR REPLACE INTO idempotency_keys (key, profile_id, created_at) VALUES (?1, ?2, ?3)",
+                params![key, profile.id, now],
+            )?;
+            tx.commit()?;
+            Ok(())
+        })?;
+        Ok((profile.clone(), true))
+    }
 
+    /// Insert many profiles in a single transaction, all or nothing. Data
+    /// directories are not created; follow up with `create_profile_dirs`.
+    pub fn create_profiles(&self, profiles: &[Profile]) -> Result<(), DatabaseError> {
+        let mut conn = self.conn.lock().unwrap();
+        let tx = conn.transaction()?;
+        for profile in profiles {
+            insert_profile(&tx, profile)?;
+        }
+        tx.commit()?;
         Ok(())
     }
 
-    /// Get all profiles
+    /// Create data directories for the given profiles across worker threads.
+    /// Existing directories are left alone. Returns how many were processed.
+    pub fn create_profile_dirs(&self, ids: &[String]) -> Result<usize, DatabaseError> {
+        let workers = std::thread::available_parallelism().map_or(4, |n| n.get()).min(8);
+        let chunk_size = ids.len().div_ceil(workers).max(1);
+
+        std::thread::scope(|scope| {
+            let handles: Vec<_> = ids
+                .chunks(chunk_size)
+                .map(|chunk| {
+                    scope.spawn(move || {
+                        chunk
+                            .iter()
+                            .try_for_each(|id| create_data_dir(&self.profiles_dir.join(id)))
+                    })
+                })
+                .collect();
+            handles
+                .into_iter()
+                .try_for_each(|handle| {
+                    handle
+                        .join()
+                        .map_err(|_| DatabaseError::DataDir("profile dir worker panicked".to_string()))?
+                })
+        })?;
+
+        Ok(ids.len())
+    }
+
+    /// Get all profiles: favorites first, then by manual order, then newest
+    /// first. `created_at` only has whole seconds, so profiles created in the
+    /// same second fall back to id.
     pub fn get_all_profiles(&self) -> Result<Vec<Profile>, DatabaseError> {
         let conn = self.conn.lock().unwrap();
-        let mut stmt = conn.prepare(
-            "SELECT id, name, user_agent, screen_width, screen_height,
-                    webgl_vendor, webgl_renderer, hardware_concurrency,
-                    device_memory, platform, timezone, language, default_url,
-                    proxy_enabled, proxy_type, proxy_host, proxy_port, proxy_username, proxy_password,
-                    created_at, last_used
-             FROM profiles ORDER BY created_at DESC"
-        )?;
+        let mut stmt = conn.prepare(&format!(
+            "SELECT {} FROM profiles
+             ORDER BY is_favorite DESC, sort_order, CAST(created_at AS INTEGER) DESC, id",
+            PROFILE_COLUMNS
+        ))?;
 
-        let profiles = stmt.query_map([], |row| {
-            Ok(Profile {
-                id: row.get(0)?,
-                name: row.get(1)?,
-                user_agent: row.get(2)?,
-                screen_width: row.get(3)?,
-                screen_height: row.get(4)?,
-                webgl_vendor: row.get(5)?,
-                webgl_renderer: row.get(6)?,
-                hardware_concurrency: row.get(7)?,
-                device_memory: row.get(8)?,
-                platform: row.get(9)?,
-                timezone: row.get(10)?,
-                language: row.get(11)?,
-                default_url: row.get(12)?,
-                proxy_enabled: row.get(13)?,
-                proxy_type: row.get(14)?,
-                proxy_host: row.get(15)?,
-                proxy_port: row.get(16)?,
-                proxy_username: row.get(17)?,
-                proxy_password: row.get(18)?,
-                created_at: row.get(19)?,
-                last_used: row.get(20)?,
-            })
-        })?;
+        let profiles = stmt.query_map([], row_to_profile)?;
 
         let mut result = Vec::new();
         for profile in profiles {
@@ -243,40 +592,14 @@ impl Database {
     /// Get a single profile by ID
     pub fn get_profile(&self, id: &str) -> Result<Profile, DatabaseError> {
         let conn = self.conn.lock().unwrap();
-        let mut stmt = conn.prepare(
-            "SELECT id, name, user_agent, screen_width, screen_height,
-                    webgl_vendor, webgl_renderer, hardware_concurrency,
-                    device_memory, platform, timezone, language, default_url,
-                    proxy_enabled, proxy_type, proxy_host, proxy_port, proxy_username, proxy_password,
-                    created_at, last_used
-             FROM profiles WHERE id = ?1"
-        )?;
+        let mut stmt = conn.prepare(&format!(
+            "SELECT {} FROM profiles WHERE id = ?1",
+            PROFILE_COLUMNS
+        ))?;
 
-        let profile = stmt.query_row([id], |row| {
-            Ok(Profile {
-                id: row.get(0)?,
-                name: row.get(1)?,
-                user_agent: row.get(2)?,
-                screen_width: row.get(3)?,
-                screen_height: row.get(4)?,
-                webgl_vendor: row.get(5)?,
-                webgl_renderer: row.get(6)?,
-                hardware_concurrency: row.get(7)?,
-                device_memory: row.get(8)?,
-                platform: row.get(9)?,
-                timezone: row.get(10)?,
-                language: row.get(11)?,
-                default_url: row.get(12)?,
-                proxy_enabled: row.get(13)?,
-                proxy_type: row.get(14)?,
-                proxy_host: row.get(15)?,
-                proxy_port: row.get(16)?,
-                proxy_username: row.get(17)?,
-                proxy_password: row.get(18)?,
-                created_at: row.get(19)?,
-                last_used: row.get(20)?,
-            })
-        }).map_err(|_| DatabaseError::ProfileNotFound(id.to_string()))?;
+        let profile = stmt
+            .query_row([id], row_to_profile)
+            .map_err(|_| DatabaseError::ProfileNotFound(id.to_string()))?;
 
         Ok(profile)
     }
@@ -284,55 +607,151 @@ impl Database {
     /// Update profile
     pub fn update_profile(&self, profile: &Profile) -> Result<(), DatabaseError> {
         let conn = self.conn.lock().unwrap();
-        let rows = conn.execute(
-            "UPDATE profiles SET
-                name = ?2, user_agent = ?3, screen_width = ?4, screen_height = ?5,
-                webgl_vendor = ?6, webgl_renderer = ?7, hardware_concurrency = ?8,
-                device_memory = ?9, platform = ?10, timezone = ?11, language = ?12,
-                default_url = ?13, proxy_enabled = ?14, proxy_type = ?15, proxy_host = ?16,
-                proxy_port = ?17, proxy_username = ?18, proxy_password = ?19, last_used = ?20
-             WHERE id = ?1",
-            params![
-                profile.id,
-                profile.name,
-                profile.user_agent,
-                profile.screen_width,
-                profile.screen_height,
-                profile.webgl_vendor,
-                profile.webgl_renderer,
-                profile.hardware_concurrency,
-                profile.device_memory,
-                profile.platform,
-                profile.timezone,
-                profile.language,
-                profile.default_url,
-                profile.proxy_enabled,
-                profile.proxy_type,
-                profile.proxy_host,
-                profile.proxy_port,
-                profile.proxy_username,
-                profile.proxy_password,
-                profile.last_used,
-            ],
-        )?;
+        update_profile_row(&conn, profile)
+    }
 
-        if rows == 0 {
-            return Err(DatabaseError::ProfileNotFound(profile.id.clone()));
+    /// Update many profiles in a single transaction, all or nothing
+    pub fn update_profiles(&self, profiles: &[Profile]) -> Result<(), DatabaseError> {
+        let mut conn = self.conn.lock().unwrap();
+        let tx = conn.transaction()?;
+        for profile in profiles {
+            update_profile_row(&tx, profile)?;
         }
+        tx.commit()?;
         Ok(())
     }
 
-    /// Update last used timestamp
-    pub fn update_last_used(&self, id: &str) -> Result<(), DatabaseError> {
+    /// Stamp last used and bump the launch count in one statement, so
+    /// concurrent launches can't overwrite each other's increment
+    pub fn record_launch(&self, id: &str) -> Result<(), DatabaseError> {
         let conn = self.conn.lock().unwrap();
         let now = chrono_now();
         conn.execute(
-            "UPDATE profiles SET last_used = ?2 WHERE id = ?1",
+            "UPDATE profiles SET last_used = ?2, launch_count = launch_count + 1 WHERE id = ?1",
             params![id, now],
         )?;
         Ok(())
     }
 
+    /// Lease the least recently used free profile to `worker_id`, optionally
+    /// only among those tagged `tag`. Free means not the system profile, not
+    /// in `active_ids` and not leased; leases older than `ttl_secs` are
+    /// released first (0 = leases never expire). `None` if nothing is free.
+    pub fn lease_profile(
+        &self,
+        worker_id: &str,
+        tag: Option<&str>,
+        ttl_secs: u64,
+        active_ids: &[String],
+    ) -> Result<Option<Profile>, DatabaseError> {
+        let now = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap()
+            .as_secs() as i64;
+        let mut conn = self.conn.lock().unwrap();
+        let tx = conn.transaction()?;
+
+        if ttl_secs > 0 {
+            tx.execute(
+                "UPDATE profiles SET leased_by = NULL, leased_at = NULL WHERE leased_at <= ?1",
+                [now - ttl_secs as i64],
+            )?;
+        }
+
+        let picked = {
+            let mut stmt = tx.prepare(&format!(
+                "SELECT {} FROM profiles
+                 WHERE is_system = 0 AND leased_by IS NULL
+                   AND (?1 IS NULL OR EXISTS (SELECT 1 FROM json_each(profiles.tags) WHERE json_each.value = ?1))
+                 ORDER BY last_used IS NOT NULL, CAST(last_used AS INTEGER), created_at",
+                PROFILE_COLUMNS
+            ))?;
+            let mut candidates = stmt.query_map([tag], row_to_profile)?;
+            candidates
+                .find(|candidate| !matches!(candidate, Ok(p) if active_ids.contains(&p.id)))
+                .transpose()?
+        };
+        let Some(mut profile) = picked else {
+            tx.commit()?;
+            return Ok(None);
+        };
+
+        tx.execute(
+            "UPDATE profiles SET leased_by = ?2, leased_at = ?3 WHERE id = ?1",
+            params![profile.id, worker_id, now],
+        )?;
+        tx.commit()?;
+
+        profile.leased_by = Some(worker_id.to_string());
+        profile.leased_at = Some(now);
+        Ok(Some(profile))
+    }
+
+    /// Flip a profile's favorite flag; returns the new value
+    pub fn toggle_favorite(&self, id: &str) -> Result<bool, DatabaseError> {
+        let conn = self.conn.lock().unwrap();
+        let rows = conn.execute(
+            "UPDATE profiles SET is_favorite = NOT is_favorite WHERE id = ?1",
+            [id],
+        )?;
+        if rows == 0 {
+            return Err(DatabaseError::ProfileNotFound(id.to_string()));
+        }
+        Ok(conn.query_row("SELECT is_favorite FROM profiles WHERE id = ?1", [id], |row| row.get(0))?)
+    }
+
+    /// Store `ids` as the manual list order (1, 2, ...) in one transaction.
+    /// Profiles not listed keep their position; an unknown id rolls back.
+    pub fn reorder_profiles(&self, ids: &[String]) -> Result<(), DatabaseError> {
+        let mut conn = self.conn.lock().unwrap();
+        let tx = conn.transaction()?;
+        for (position, id) in ids.iter().enumerate() {
+            let rows = tx.execute(
+                "UPDATE profiles SET sort_order = ?2 WHERE id = ?1",
+                params![id, position as i64 + 1],
+            )?;
+            if rows == 0 {
+                return Err(DatabaseError::ProfileNotFound(id.clone()));
+            }
+        }
+        tx.commit()?;
+        Ok(())
+    }
+
+    /// End `worker_id`'s lease on a profile; releasing an unleased profile
+    /// is a no-op. Another worker's lease is left in place and reported.
+    pub fn release_profile(&self, id: &str, worker_id: &str) -> Result<(), DatabaseError> {
+        let conn = self.conn.lock().unwrap();
+        let rows = conn.execute(
+            "UPDATE profiles SET leased_by = NULL, leased_at = NULL
+             WHERE id = ?1 AND (leased_by IS NULL OR leased_by = ?2)",
+            params![id, worker_id],
+        )?;
+        if rows == 0 {
+            let exists: bool =
+                conn.query_row("SELECT EXISTS(SELECT 1 FROM profiles WHERE id = ?1)", [id], |row| row.get(0))?;
+            return Err(if exists {
+                DatabaseError::LeasedByOther(id.to_string())
+            } else {
+                DatabaseError::ProfileNotFound(id.to_string())
+            });
+        }
+        Ok(())
+    }
+
+    /// Point every non-system profile tagged `tag` at `url` in a single
+    /// statement; returns how many profiles changed
+    pub fn set_default_url_for_tag(&self, tag: &str, url: &str) -> Result<usize, DatabaseError> {
+        let conn = self.conn.lock().unwrap();
+        let rows = conn.execute(
+            "UPDATE profiles SET default_url = ?2
+             WHERE is_system = 0
+               AND EXISTS (SELECT 1 FROM json_each(profiles.tags) WHERE json_each.value = ?1)",
+            params![tag, url],
+        )?;
+        Ok(rows)
+    }
+
     /// Delete a profile
     pub fn delete_profile(&self, id: &str) -> Result<(), DatabaseError> {
         let conn = self.conn.lock().unwrap();
@@ -361,6 +780,210 @@ impl Database {
         self.profiles_dir.join(id).join("cookies.json")
     }
 
+    /// Reasons a profile's data directory might not be private to it (empty if isolated)
+    pub fn isolation_issues(&self, id: &str) -> Result<Vec<String>, DatabaseError> {
+        self.get_profile(id)?;
+        Ok(self.all_isolation_issues()?.remove(id).unwrap_or_default())
+    }
+
+    /// Isolation problems for every profile, keyed by id; isolated profiles are omitted
+    pub fn all_isolation_issues(&self) -> Result<HashMap<String, Vec<String>>, DatabaseError> {
+        let ids: Vec<String> = self.get_all_profiles()?.into_iter().map(|p| p.id).collect();
+        Ok(data_dir_issues(&self.profiles_dir, &ids))
+    }
+
+    /// Compare the profiles directory with the profile rows; with `repair`,
+    /// delete orphan directories and recreate missing ones
+    pub fn reconcile_profile_dirs(&self, repair: bool) -> Result<ReconcileReport, DatabaseError> {
+        let (orphan_dirs, missing_dirs) = {
+            // Held while listing so a profile created meanwhile can't be mistaken for an orphan
+            let conn = self.conn.lock().unwrap();
+            let ids = profile_ids(&conn)?;
+            let mismatches = dir_mismatches(&self.profiles_dir, &ids)?;
+            if repair {
+                for id in &mismatches.1 {
+                    create_data_dir(&self.profiles_dir.join(id))?;
+                }
+            }
+            mismatches
+        };
+
+        // Deleting can take a while for a big profile, so it happens without
+        // the lock; a directory matching a profile created since is left alone
+        if repair {
+            let known = lowercase_ids(&profile_ids(&self.conn.lock().unwrap())?);
+            for name in orphan_dirs.iter().filter(|name| is_orphan_dir(name, &known)) {
+                let dir = self.profiles_dir.join(name);
+                if std::fs::symlink_metadata(&dir)?.file_type().is_symlink() {
+                    std::fs::remove_file(&dir)?;
+                } else {
+                    std::fs::remove_dir_all(&dir)?;
+                }
+            }
+        }
+
+        Ok(ReconcileReport {
+            orphan_dirs,
+            missing_dirs,
+            repaired: repair,
+        })
+    }
+
+    /// All fingerprint templates, built-ins first
+    pub fn get_templates(&self) -> Result<Vec<FingerprintTemplate>, DatabaseError> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare(
+            "SELECT template, builtin FROM fingerprint_templates ORDER BY builtin DESC, name",
+        )?;
+        let templates = stmt
+            .query_map([], |row| Ok((row.get::<_, String>(0)?, row.get::<_, bool>(1)?)))?
+            .filter_map(|row| row.ok())
+            .filter_map(|(json, builtin)| {
+                serde_json::from_str::<FingerprintTemplate>(&json)
+                    .ok()
+                    .map(|template| FingerprintTemplate { builtin, ..template })
+            })
+            .collect();
+        Ok(templates)
+    }
+
+    /// Get a fingerprint template by name
+    pub fn get_template(&self, name: &str) -> Result<FingerprintTemplate, DatabaseError> {
+        self.get_templates()?
+            .into_iter()
+            .find(|t| t.name == name)
+            .ok_or_else(|| DatabaseError::TemplateNotFound(name.to_string()))
+    }
+
+    /// Insert or replace a user-defined fingerprint template
+    pub fn save_template(&self, template: &FingerprintTemplate) -> Result<(), DatabaseError> {
+        let conn = self.conn.lock().unwrap();
+        let json = serde_json::to_string(template).unwrap_or_default();
+        conn.execute(
+            "INSERT OR REPLACE INTO fingerprint_templates (name, template, builtin) VALUES (?1, ?2, 0)",
+            params![template.name, json],
+        )?;
+        Ok(())
+    }
+
+    /// Delete a user-defined fingerprint template; built-ins are never removed
+    pub fn delete_template(&self, name: &str) -> Result<(), DatabaseError> {
+        let conn = self.conn.lock().unwrap();
+        let rows = conn.execute(
+            "DELETE FROM fingerprint_templates WHERE name = ?1 AND builtin = 0",
+            [name],
+        )?;
+        if rows == 0 {
+            return Err(DatabaseError::TemplateNotFound(name.to_string()));
+        }
+        Ok(())
+    }
+
+    /// Write a consistent copy of the whole database to `path`, which must not exist
+    pub fn copy_to(&self, path: &Path) -> Result<(), DatabaseError> {
+        let conn = self.conn.lock().unwrap();
+        conn.execute("VACUUM INTO ?1", params![path.to_string_lossy()])?;
+        Ok(())
+    }
+
+    /// Run `PRAGMA integrity_check` and `PRAGMA foreign_key_check`. Damage
+    /// bad enough to stop a check is reported as an issue too.
+    pub fn check_integrity(&self) -> Result<IntegrityReport, DatabaseError> {
+        let conn = self.conn.lock().unwrap();
+        let integrity_check = || -> rusqlite::Result<Vec<String>> {
+            conn.prepare("PRAGMA integrity_check")?
+                .query_map([], |row| row.get(0))?
+                .collect()
+        };
+        let foreign_key_check = || -> rusqlite::Result<Vec<String>> {
+            conn.prepare("PRAGMA foreign_key_check")?
+                .query_map([], |row| {
+                    let table: String = row.get(0)?;
+                    let rowid: Option<i64> = row.get(1)?;
+                    let parent: String = row.get(2)?;
+                    Ok(format!("{} row {} references missing {}", table, rowid.unwrap_or_default(), parent))
+                })?
+                .collect()
+        };
+        let mut integrity_issues = integrity_check().unwrap_or_else(|e| vec![format!("integrity check stopped: {}", e)]);
+        integrity_issues.retain(|issue| issue != "ok");
+        let foreign_key_issues =
+            foreign_key_check().unwrap_or_else(|e| vec![format!("foreign key check stopped: {}", e)]);
+        Ok(IntegrityReport {
+            ok: integrity_issues.is_empty() && foreign_key_issues.is_empty(),
+            integrity_issues,
+            foreign_key_issues,
+            repair: None,
+        })
+    }
+
+    /// Rebuild a damaged database: copy its schema and every row that can
+    /// still be read into a fresh file, then swap that in. The damaged file
+    /// is kept beside it as `<name>.corrupt-<unix time>`.
+    pub fn repair(&self) -> Result<RepairReport, DatabaseError> {
+        let mut conn = self.conn.lock().unwrap();
+        let path = match conn.path().filter(|p| !p.is_empty()) {
+            Some(path) => PathBuf::from(path),
+            None => return Err(DatabaseError::Repair("the database isn't a file".to_string())),
+        };
+        let sibling = |suffix: &str| {
+            let mut name = path.as_os_str().to_os_string();
+            name.push(suffix);
+            PathBuf::from(name)
+        };
+        let staging = sibling(".repair");
+        if staging.exists() {
+            std::fs::remove_file(&staging)?;
+        }
+
+        let (rows_recovered, incomplete_tables) = {
+            let fresh = Connection::open(&staging)?;
+            let copied = copy_readable_rows(&conn, &fresh)?;
+            let check: String = fresh.query_row("PRAGMA integrity_check", [], |row| row.get(0))?;
+            if check != "ok" {
+                drop(fresh);
+                std::fs::remove_file(&staging).ok();
+                return Err(DatabaseError::Repair(format!("the rebuilt copy is damaged too: {}", check)));
+            }
+            copied
+        };
+
+        let now = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or_default();
+        let backup = sibling(&format!(".corrupt-{}", now));
+        // Close the damaged file so it can be moved, along with any journal
+        // that would otherwise be rolled into the rebuilt one
+        drop(std::mem::replace(&mut *conn, Connection::open_in_memory()?));
+        let swapped = std::fs::rename(&path, &backup).and_then(|_| {
+            for suffix in ["-journal", "-wal", "-shm"] {
+                let file = sibling(suffix);
+                if file.exists() {
+                    std::fs::rename(&file, sibling(&format!(".corrupt-{}{}", now, suffix)))?;
+                }
+            }
+            std::fs::rename(&staging, &path).inspect_err(|_| {
+                std::fs::rename(&backup, &path).ok();
+            })
+        });
+        // The rebuilt file, or the original when the swap failed
+        *conn = Connection::open(&path)?;
+        swapped?;
+
+        log::warn!(
+            "Repaired database: {} rows recovered, incomplete tables {:?}, damaged copy at {}",
+            rows_recovered,
+            incomplete_tables,
+            backup.display()
+        );
+        Ok(RepairReport {
+            rows_recovered,
+            incomplete_tables,
+            backup_path: backup.display().to_string(),
+        })
+    }
+
     // Settings management for extensibility
     pub fn set_setting(&self, key: &str, value: &str) -> Result<(), DatabaseError> {
         let conn = self.conn.lock().unwrap();
@@ -381,6 +1004,352 @@ impl Database {
             Err(e) => Err(DatabaseError::Sqlite(e)),
         }
     }
+
+    /// Every stored setting, by key
+    pub fn get_all_settings(&self) -> Result<BTreeMap<String, String>, DatabaseError> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare("SELECT key, value FROM settings")?;
+        let settings = stmt
+            .query_map([], |row| Ok((row.get(0)?, row.get(1)?)))?
+            .collect::<rusqlite::Result<_>>()?;
+        Ok(settings)
+    }
+
+    /// Store settings and user-defined templates in one transaction. Existing
+    /// keys and template names are only replaced with `overwrite`; built-in
+    /// templates never are.
+    pub fn import_config(
+        &self,
+        settings: &BTreeMap<String, String>,
+        templates: &[FingerprintTemplate],
+        overwrite: bool,
+    ) -> Result<ConfigImportReport, DatabaseError> {
+        let mut conn = self.conn.lock().unwrap();
+        let tx = conn.transaction()?;
+        let mut report = ConfigImportReport::default();
+
+        for (key, value) in settings {
+            let exists = tx
+                .query_row("SELECT 1 FROM settings WHERE key = ?1", [key], |_| Ok(()))
+                .map(|_| true)
+                .or_else(|e| match e {
+                    rusqlite::Error::QueryReturnedNoRows => Ok(false),
+                    e => Err(e),
+                })?;
+            if exists && !overwrite {
+                report.settings_skipped.push(key.clone());
+                continue;
+            }
+            tx.execute(
+                "INSERT OR REPLACE INTO settings (key, value) VALUES (?1, ?2)",
+                params![key, value],
+            )?;
+            report.settings_imported += 1;
+        }
+
+        for template in templates {
+            let builtin: Option<bool> = tx
+                .query_row(
+                    "SELECT builtin FROM fingerprint_templates WHERE name = ?1",
+                    [&template.name],
+                    |row| row.get(0),
+                )
+                .map(Some)
+                .or_else(|e| match e {
+                    rusqlite::Error::QueryReturnedNoRows => Ok(None),
+                    e => Err(e),
+                })?;
+            if builtin == Some(true) || (builtin.is_some() && !overwrite) {
+                report.templates_skipped.push(template.name.clone());
+                continue;
+            }
+            let template = FingerprintTemplate { builtin: false, ..template.clone() };
+            tx.execute(
+                "INSERT OR REPLACE INTO fingerprint_templates (name, template, builtin) VALUES (?1, ?2, 0)",
+                params![template.name, serde_json::to_string(&template).unwrap_or_default()],
+            )?;
+            report.templates_imported += 1;
+        }
+
+        tx.commit()?;
+        Ok(report)
+    }
+
+    /// Get a setting parsed into `T`, falling back to `default` when unset or unparsable
+    pub fn get_setting_or<T: std::str::FromStr>(&self, key: &str, default: T) -> T {
+        self.get_setting(key)
+            .ok()
+            .flatten()
+            .and_then(|value| value.trim().parse().ok())
+            .unwrap_or(default)
+    }
+}
+
+/// Write every stored field of `profile` except `launch_count`, the lease,
+/// the favorite flag and the manual order
+fn update_profile_row(conn: &Connection, profile: &Profile) -> Result<(), DatabaseError> {
+    let rows = conn.execute(
+        "UPDATE profiles SET
+            name = ?2, user_agent = ?3, screen_width = ?4, screen_height = ?5,
+            webgl_vendor = ?6, webgl_renderer = ?7, hardware_concurrency = ?8,
+            device_memory = ?9, platform = ?10, timezone = ?11, language = ?12,
+            default_url = ?13, proxy_enabled = ?14, proxy_type = ?15, proxy_host = ?16,
+            proxy_port = ?17, proxy_username = ?18, proxy_password = ?19, last_used = ?20,
+            do_not_track = ?21, canvas_noise_level = ?22, inner_width = ?23,
+            inner_height = ?24, custom_headers = ?25, last_aged = ?26, is_system = ?27,
+            proxy_bypass = ?28, languages = ?29, tags = ?30,
+            webrtc_mode = ?31, environment = ?32, disable_cache = ?33
+         WHERE id = ?1",
+        params![
+            profile.id,
+            profile.name,
+            profile.user_agent,
+            profile.screen_width,
+            profile.screen_height,
+            profile.webgl_vendor,
+            profile.webgl_renderer,
+            profile.hardware_concurrency,
+            profile.device_memory,
+            profile.platform,
+            profile.timezone,
+            profile.language,
+            profile.default_url,
+            profile.proxy_enabled,
+            profile.proxy_type,
+            profile.proxy_host,
+            profile.proxy_port,
+            profile.proxy_username,
+            profile.proxy_password,
+            profile.last_used,
+            profile.do_not_track,
+            profile.canvas_noise_level,
+            profile.inner_width,
+            profile.inner_height,
+            serde_json::to_string(&profile.custom_headers).unwrap_or_default(),
+            profile.last_aged,
+            profile.is_system,
+            serde_json::to_string(&profile.proxy_bypass).unwrap_or_default(),
+            serde_json::to_string(&profile.languages).unwrap_or_default(),
+            serde_json::to_string(&profile.tags).unwrap_or_default(),
+            profile.webrtc_mode,
+            profile.environment,
+            profile.disable_cache,
+        ],
+    )?;
+
+    if rows == 0 {
+        return Err(DatabaseError::ProfileNotFound(profile.id.clone()));
+    }
+    Ok(())
+}
+
+/// Copy the schema and every readable row of `source` into the empty
+/// `target`. Returns how many rows were copied and which tables a read
+/// error cut short.
+fn copy_readable_rows(source: &Connection, target: &Connection) -> Result<(u64, Vec<String>), DatabaseError> {
+    let schema: Vec<(String, String, String)> = source
+        .prepare("SELECT type, name, sql FROM sqlite_master WHERE sql IS NOT NULL AND name NOT LIKE 'sqlite_%'")?
+        .query_map([], |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)))?
+        .collect::<rusqlite::Result<_>>()?;
+    let of_type = |kind: &'static str| schema.iter().filter(move |(t, _, _)| t == kind);
+
+    // Indexes before the rows so duplicates are dropped; triggers and views
+    // after, so copying doesn't fire them
+    for (_, _, sql) in of_type("table").chain(of_type("index")) {
+        target.execute_batch(sql)?;
+    }
+
+    let tx = target.unchecked_transaction()?;
+    let mut rows_recovered = 0;
+    let mut incomplete_tables = Vec::new();
+    for (_, table, _) in of_type("table") {
+        let quoted = format!("\"{}\"", table.replace('"', "\"\""));
+        let mut copied = 0;
+        let result = (|| -> rusqlite::Result<()> {
+            let mut select = source.prepare(&format!("SELECT * FROM {}", quoted))?;
+            let columns = select.column_count();
+            let placeholders = vec!["?"; columns].join(", ");
+            let mut insert = tx.prepare(&format!("INSERT OR IGNORE INTO {} VALUES ({})", quoted, placeholders))?;
+            let mut rows = select.query([])?;
+            while let Some(row) = rows.next()? {
+                let values = (0..columns)
+                    .map(|i| row.get::<_, rusqlite::types::Value>(i))
+                    .collect::<rusqlite::Result<Vec<_>>>()?;
+                copied += insert.execute(rusqlite::params_from_iter(values))? as u64;
+            }
+            Ok(())
+        })();
+        // Rows read before an error are kept
+        rows_recovered += copied;
+        if let Err(e) = result {
+            log::warn!("Repair stopped reading table {}: {}", table, e);
+            incomplete_tables.push(table.clone());
+        }
+    }
+    tx.commit()?;
+
+    for (_, _, sql) in of_type("trigger").chain(of_type("view")) {
+        target.execute_batch(sql)?;
+    }
+    Ok((rows_recovered, incomplete_tables))
+}
+
+/// Insert one profile row; shared by single and batch creation
+fn insert_profile(conn: &Connection, profile: &Profile) -> rusqlite::Result<()> {
+    conn.execute(
+        "INSERT INTO profiles (
+            id, name, user_agent, screen_width, screen_height,
+            webgl_vendor, webgl_renderer, hardware_concurrency,
+            device_memory, platform, timezone, language, default_url,
+            proxy_enabled, proxy_type, proxy_host, proxy_port, proxy_username, proxy_password,
+            created_at, last_used, do_not_track, canvas_noise_level, inner_width,
+            inner_height, custom_headers, last_aged, is_system, proxy_bypass, languages, tags,
+            webrtc_mode, environment, disable_cache
+        ) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15, ?16, ?17, ?18, ?19, ?20, ?21, ?22, ?23, ?24, ?25, ?26, ?27, ?28, ?29, ?30, ?31, ?32, ?33, ?34)",
+        params![
+            profile.id,
+            profile.name,
+            profile.user_agent,
+            profile.screen_width,
+            profile.screen_height,
+            profile.webgl_vendor,
+            profile.webgl_renderer,
+            profile.hardware_concurrency,
+            profile.device_memory,
+            profile.platform,
+            profile.timezone,
+            profile.language,
+            profile.default_url,
+            profile.proxy_enabled,
+            profile.proxy_type,
+            profile.proxy_host,
+            profile.proxy_port,
+            profile.proxy_username,
+            profile.proxy_password,
+            profile.created_at,
+            profile.last_used,
+            profile.do_not_track,
+            profile.canvas_noise_level,
+            profile.inner_width,
+            profile.inner_height,
+            serde_json::to_string(&profile.custom_headers).unwrap_or_default(),
+            profile.last_aged,
+            profile.is_system,
+            serde_json::to_string(&profile.proxy_bypass).unwrap_or_default(),
+            serde_json::to_string(&profile.languages).unwrap_or_default(),
+            serde_json::to_string(&profile.tags).unwrap_or_default(),
+            profile.webrtc_mode,
+            profile.environment,
+            profile.disable_cache,
+        ],
+    )?;
+    Ok(())
+}
+
+/// Whether `id` is a single path component, so it names a directory directly under `profiles_dir`
+fn is_plain_dir_name(id: &str) -> bool {
+    let mut components = Path::new(id).components();
+    matches!((components.next(), components.next()), (Some(Component::Normal(_)), None))
+}
+
+/// Every profile id
+fn profile_ids(conn: &Connection) -> rusqlite::Result<Vec<String>> {
+    let mut stmt = conn.prepare("SELECT id FROM profiles")?;
+    let ids = stmt.query_map([], |row| row.get(0))?.collect();
+    ids
+}
+
+/// Ids lowercased, for matching directory names case-insensitively
+fn lowercase_ids(ids: &[String]) -> HashSet<String> {
+    ids.iter().map(|id| id.to_lowercase()).collect()
+}
+
+/// Whether a directory named `name` belongs to none of the `known` (lowercased) ids
+fn is_orphan_dir(name: &str, known: &HashSet<String>) -> bool {
+    !known.contains(&name.to_lowercase())
+}
+
+/// Directories under `profiles_dir` not named after any id, and ids without a
+/// directory. Names are compared case-insensitively so a directory a
+/// case-insensitive filesystem shares with a profile is never called an orphan.
+fn dir_mismatches(profiles_dir: &Path, ids: &[String]) -> std::io::Result<(Vec<String>, Vec<String>)> {
+    let known = lowercase_ids(ids);
+
+    let mut orphans = Vec::new();
+    for entry in std::fs::read_dir(profiles_dir)? {
+        let entry = entry?;
+        let file_type = entry.file_type()?;
+        // Stray files aren't profile data; non-UTF-8 names can't be profile ids
+        // and are left for the user to look at
+        if !(file_type.is_dir() || file_type.is_symlink()) {
+            continue;
+        }
+        if let Ok(name) = entry.file_name().into_string() {
+            if is_orphan_dir(&name, &known) {
+                orphans.push(name);
+            }
+        }
+    }
+    orphans.sort();
+
+    let missing = ids
+        .iter()
+        .filter(|id| is_plain_dir_name(id) && !profiles_dir.join(id).is_dir())
+        .cloned()
+        .collect();
+
+    Ok((orphans, missing))
+}
+
+/// Check that each id's data directory is a single, non-symlinked path
+/// component directly under `profiles_dir` and doesn't resolve to another
+/// profile's directory. Returns the problems found, keyed by id.
+fn data_dir_issues(profiles_dir: &Path, ids: &[String]) -> HashMap<String, Vec<String>> {
+    let mut issues: HashMap<String, Vec<String>> = HashMap::new();
+    let root = std::fs::canonicalize(profiles_dir).unwrap_or_else(|_| profiles_dir.to_path_buf());
+    let mut by_dir: HashMap<String, Vec<&String>> = HashMap::new();
+
+    for id in ids {
+        let dir = profiles_dir.join(id);
+        let mut report = |issue: String| issues.entry(id.clone()).or_default().push(issue);
+
+        if !is_plain_dir_name(id) {
+            report(format!("Profile id '{}' is not a plain directory name", id));
+        }
+
+        if let Ok(meta) = std::fs::symlink_metadata(&dir) {
+            if meta.file_type().is_symlink() {
+                report(format!("{} is a symlink", dir.display()));
+            }
+        }
+
+        let resolved = std::fs::canonicalize(&dir).ok();
+        if let Some(resolved) = &resolved {
+            if resolved.parent() != Some(root.as_path()) {
+                report(format!("{} resolves outside the profiles directory", dir.display()));
+            }
+        }
+
+        // Lowercased: ids differing only in case share a directory on case-insensitive filesystems
+        let key = resolved.unwrap_or_else(|| root.join(id));
+        by_dir
+            .entry(key.to_string_lossy().to_lowercase())
+            .or_default()
+            .push(id);
+    }
+
+    for group in by_dir.values().filter(|group| group.len() > 1) {
+        for id in group {
+            for other in group.iter().filter(|other| *other != id) {
+                issues
+                    .entry(id.to_string())
+                    .or_default()
+                    .push(format!("Data directory is shared with profile {}", other));
+            }
+        }
+    }
+
+    issues
 }
 
 fn chrono_now() -> String {
@@ -390,3 +1359,356 @@ fn chrono_now() -> String {
         .unwrap();
     format!("{}", duration.as_secs())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_profile_validate() {
+        let mut profile = test_profile("p");
+        assert!(profile.validate().is_ok());
+
+        profile.screen_width = 0;
+        assert!(profile.validate().unwrap_err().starts_with("screen_width"));
+        profile.user_agent = "  ".to_string();
+        assert!(profile.validate().unwrap_err().starts_with("user_agent"));
+        profile.user_agent = "Mozilla/5.0".to_string();
+        profile.screen_width = 1920;
+        profile.languages = vec!["de-DE".to_string(), " ".to_string()];
+        assert!(profile.validate().unwrap_err().starts_with("languages"));
+        profile.languages = Vec::new();
+        assert_eq!(profile.environment, DEFAULT_ENVIRONMENT);
+        profile.environment = String::new();
+        assert!(profile.validate().unwrap_err().starts_with("environment"));
+    }
+
+    #[test]
+    fn test_profile_validate_lengths() {
+        let limits = TextLimits { name: 5, text: 20 };
+        let mut profile = test_profile("p");
+        profile.name = "ééééé".to_string();
+        assert!(profile.validate_lengths(limits).is_ok());
+
+        profile.name.push('x');
+        let err = profile.validate_lengths(limits).unwrap_err();
+        assert_eq!(err, "name is too long (6 characters, limit 5)");
+
+        profile.name = "p".to_string();
+        profile.default_url = format!("https://{}", "a".repeat(12));
+        assert!(profile.validate_lengths(limits).is_ok());
+        profile.default_url.push('a');
+        assert!(profile.validate_lengths(limits).unwrap_err().starts_with("default_url"));
+
+        profile.default_url = "https://a".to_string();
+        profile.tags = vec!["ok".to_string(), "t".repeat(21)];
+        assert!(profile.validate_lengths(limits).unwrap_err().starts_with("tag"));
+    }
+
+    #[test]
+    fn test_describe_dir_error() {
+        let root = std::env::temp_dir().join(format!("identityforge-dirs-{}", std::process::id()));
+        std::fs::create_dir_all(&root).unwrap();
+        let file = root.join("taken");
+        std::fs::write(&file, "").unwrap();
+
+        let err = create_data_dir(&file.join("profile")).unwrap_err().to_string();
+        assert!(err.contains("exists as a file"), "{}", err);
+
+        let full = std::io::Error::from_raw_os_error(DISK_FULL_CODES[0]);
+        assert!(describe_dir_error(&root.join("new"), &full).ends_with("the disk is full"));
+        let denied = std::io::Error::from(std::io::ErrorKind::PermissionDenied);
+        assert!(describe_dir_error(&root.join("new"), &denied).ends_with("permission denied"));
+
+        std::fs::remove_dir_all(&root).ok();
+    }
+
+    #[test]
+    fn test_data_dir_issues() {
+        let root = std::env::temp_dir().join(format!("identityforge-isolation-{}", std::process::id()));
+        std::fs::create_dir_all(root.join("a")).unwrap();
+        let ids: Vec<String> = ["a", "A", "b", "../b"].iter().map(|id| id.to_string()).collect();
+
+        let issues = data_dir_issues(&root, &ids);
+        assert!(!issues.contains_key("b"));
+        assert_eq!(issues["a"], vec!["Data directory is shared with profile A".to_string()]);
+        assert_eq!(issues["A"].len(), 1);
+        assert!(!issues["../b"].is_empty());
+
+        std::fs::remove_dir_all(&root).ok();
+    }
+
+    #[test]
+    fn test_create_profile_failure_leaves_no_dir() {
+        let root = std::env::temp_dir().join(format!("identityforge-create-fail-{}", std::process::id()));
+        std::fs::remove_dir_all(&root).ok();
+        let db = Database::new(&root.join("test.db"), root.join("profiles")).unwrap();
+        let conn = db.conn.lock().unwrap();
+        conn.execute_batch("CREATE TRIGGER refuse BEFORE INSERT ON profiles BEGIN SELECT RAISE(ABORT, 'refused'); END")
+            .unwrap();
+        drop(conn);
+
+        assert!(db.create_profile(&test_profile("a")).is_err());
+        assert!(!root.join("profiles").join("a").exists());
+        // A directory that was already there is left alone
+        std::fs::create_dir_all(root.join("profiles").join("b")).unwrap();
+        assert!(db.create_profile(&test_profile("b")).is_err());
+        assert!(root.join("profiles").join("b").exists());
+
+        drop(db);
+        std::fs::remove_dir_all(&root).ok();
+    }
+
+    #[test]
+    fn test_record_launch_concurrently() {
+        let root = std::env::temp_dir().join(format!("identityforge-launches-{}", std::process::id()));
+        let db = Database::new(&root.join("test.db"), root.join("profiles")).unwrap();
+        let profile = test_profile("p");
+        db.create_profile(&profile).unwrap();
+
+        std::thread::scope(|scope| {
+            for _ in 0..8 {
+                scope.spawn(|| {
+                    for _ in 0..25 {
+                        db.record_launch("p").unwrap();
+                    }
+                });
+            }
+            // A profile edit saved mid-way must not roll the count back
+            scope.spawn(|| db.update_profile(&profile).unwrap());
+        });
+
+        assert_eq!(db.get_profile("p").unwrap().launch_count, 200);
+        drop(db);
+        std::fs::remove_dir_all(&root).ok();
+    }
+
+    #[test]
+    fn test_lease_profile() {
+        let root = std::env::temp_dir().join(format!("identityforge-leases-{}", std::process::id()));
+        let db = Database::new(&root.join("test.db"), root.join("profiles")).unwrap();
+        for (id, tags) in [("a", vec!["shop"]), ("b", vec![]), ("c", vec!["shop"])] {
+            let mut profile = test_profile(id);
+            profile.tags = tags.into_iter().map(String::from).collect();
+            db.create_profile(&profile).unwrap();
+        }
+        let active = vec!["a".to_string()];
+
+        let first = db.lease_profile("w1", Some("shop"), 60, &active).unwrap().unwrap();
+        assert_eq!(first.id, "c");
+        assert_eq!(first.leased_by.as_deref(), Some("w1"));
+        // "a" is open and "c" is taken
+        assert!(db.lease_profile("w2", Some("shop"), 60, &active).unwrap().is_none());
+        assert_eq!(db.lease_profile("w2", None, 60, &active).unwrap().unwrap().id, "b");
+
+        // Only the holder can release
+        assert!(matches!(db.release_profile("c", "w2"), Err(DatabaseError::LeasedByOther(_))));
+        db.release_profile("c", "w1").unwrap();
+        assert_eq!(db.lease_profile("w3", Some("shop"), 60, &active).unwrap().unwrap().id, "c");
+        assert!(matches!(db.release_profile("missing", "w3"), Err(DatabaseError::ProfileNotFound(_))));
+
+        // Saving a stale copy of the profile keeps the lease
+        db.update_profile(&first).unwrap();
+        assert_eq!(db.get_profile("c").unwrap().leased_by.as_deref(), Some("w3"));
+
+        // Expired leases are released before picking
+        let conn = db.conn.lock().unwrap();
+        conn.execute("UPDATE profiles SET leased_at = leased_at - 120", []).unwrap();
+        drop(conn);
+        assert_eq!(db.lease_profile("w4", Some("shop"), 60, &active).unwrap().unwrap().id, "c");
+
+        drop(db);
+        std::fs::remove_dir_all(&root).ok();
+    }
+
+    #[test]
+    fn test_create_profile_once() {
+        let root = std::env::temp_dir().join(format!("identityforge-idempotency-{}", std::process::id()));
+        let db = Database::new(&root.join("test.db"), root.join("profiles")).unwrap();
+
+        let (first, created) = db.create_profile_once(&test_profile("a"), "req-1").unwrap();
+        assert!(created);
+        // A retry with a fresh candidate gets the original back
+        let (retry, created) = db.create_profile_once(&test_profile("b"), "req-1").unwrap();
+        assert!(!created);
+        assert_eq!(retry.id, first.id);
+        assert!(db.get_profile("b").is_err());
+        assert!(!root.join("profiles").join("b").exists());
+
+        // A deleted profile frees its key
+        db.delete_profile("a").unwrap();
+        assert!(db.create_profile_once(&test_profile("c"), "req-1").unwrap().1);
+
+        // Expired keys are swept
+        let conn = db.conn.lock().unwrap();
+        conn.execute("UPDATE idempotency_keys SET created_at = created_at - ?1", [IDEMPOTENCY_KEY_TTL_SECS])
+            .unwrap();
+        drop(conn);
+        assert!(db.create_profile_once(&test_profile("d"), "req-2").unwrap().1);
+        let conn = db.conn.lock().unwrap();
+        let keys: i64 = conn.query_row("SELECT COUNT(*) FROM idempotency_keys", [], |row| row.get(0)).unwrap();
+        assert_eq!(keys, 1);
+
+        // A failed insert takes its new data directory with it
+        conn.execute_batch("CREATE TRIGGER refuse BEFORE INSERT ON profiles BEGIN SELECT RAISE(ABORT, 'refused'); END")
+            .unwrap();
+        drop(conn);
+        assert!(db.create_profile_once(&test_profile("e"), "req-3").is_err());
+        assert!(!root.join("profiles").join("e").exists());
+
+        drop(db);
+        std::fs::remove_dir_all(&root).ok();
+    }
+
+    #[test]
+    fn test_favorites_and_manual_order() {
+        let root = std::env::temp_dir().join(format!("identityforge-order-{}", std::process::id()));
+        let db = Database::new(&root.join("test.db"), root.join("profiles")).unwrap();
+        for (id, created_at) in [("a", "1"), ("b", "2"), ("c", "3")] {
+            let mut profile = test_profile(id);
+            profile.created_at = created_at.to_string();
+            db.create_profile(&profile).unwrap();
+        }
+        let order = |db: &Database| -> Vec<String> {
+            db.get_all_profiles().unwrap().into_iter().map(|p| p.id).collect()
+        };
+        assert_eq!(order(&db), vec!["c", "b", "a"]);
+
+        db.reorder_profiles(&["a".to_string(), "c".to_string(), "b".to_string()]).unwrap();
+        assert_eq!(order(&db), vec!["a", "c", "b"]);
+        assert!(db.toggle_favorite("b").unwrap());
+        assert_eq!(order(&db), vec!["b", "a", "c"]);
+
+        // Saving a stale copy keeps both, and a bad id leaves the order alone
+        db.update_profile(&test_profile("b")).unwrap();
+        assert!(db.reorder_profiles(&["c".to_string(), "missing".to_string()]).is_err());
+        assert_eq!(order(&db), vec!["b", "a", "c"]);
+        assert!(!db.toggle_favorite("b").unwrap());
+        assert!(db.toggle_favorite("missing").is_err());
+
+        drop(db);
+        std::fs::remove_dir_all(&root).ok();
+    }
+
+    #[test]
+    fn test_same_second_profiles_sort_by_id() {
+        let root = std::env::temp_dir().join(format!("identityforge-ties-{}", std::process::id()));
+        let db = Database::new(&root.join("test.db"), root.join("profiles")).unwrap();
+        // Ids out of insertion order, all created in one second
+        let batch: Vec<Profile> = ["m", "a", "z"]
+            .iter()
+            .map(|id| Profile { created_at: "100".to_string(), ..test_profile(id) })
+            .collect();
+        db.create_profiles(&batch).unwrap();
+        let mut older = test_profile("old");
+        older.created_at = "99".to_string();
+        db.create_profile(&older).unwrap();
+
+        for _ in 0..3 {
+            let ids: Vec<String> = db.get_all_profiles().unwrap().into_iter().map(|p| p.id).collect();
+            assert_eq!(ids, vec!["a", "m", "z", "old"]);
+        }
+
+        drop(db);
+        std::fs::remove_dir_all(&root).ok();
+    }
+
+    #[test]
+    fn test_import_config() {
+        let root = std::env::temp_dir().join(format!("identityforge-config-{}", std::process::id()));
+        let db = Database::new(&root.join("test.db"), root.join("profiles")).unwrap();
+        db.set_setting("default_platform", "windows").unwrap();
+
+        let settings: BTreeMap<String, String> = [("default_platform", "linux"), ("lease_ttl_minutes", "5")]
+            .into_iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect();
+        let builtin = builtin_templates().remove(0);
+        let custom = FingerprintTemplate {
+            name: "Custom".to_string(),
+            builtin: false,
+            ..builtin.clone()
+        };
+        let templates = [builtin.clone(), custom];
+
+        let report = db.import_config(&settings, &templates, false).unwrap();
+        assert_eq!(report.settings_imported, 1);
+        assert_eq!(report.settings_skipped, vec!["default_platform"]);
+        assert_eq!(db.get_setting("default_platform").unwrap().as_deref(), Some("windows"));
+        assert_eq!(report.templates_imported, 1);
+        assert_eq!(report.templates_skipped, vec![builtin.name.clone()]);
+        assert!(db.get_template(&builtin.name).unwrap().builtin);
+
+        let report = db.import_config(&settings, &templates, true).unwrap();
+        assert_eq!(report.settings_imported, 2);
+        assert_eq!(db.get_setting("default_platform").unwrap().as_deref(), Some("linux"));
+        // Built-ins stay protected even when overwriting
+        assert_eq!(report.templates_skipped, vec![builtin.name.clone()]);
+        assert_eq!(db.get_all_settings().unwrap(), settings);
+
+        drop(db);
+        std::fs::remove_dir_all(&root).ok();
+    }
+
+    #[test]
+    fn test_dir_mismatches() {
+        let root = std::env::temp_dir().join(format!("identityforge-reconcile-{}", std::process::id()));
+        for dir in ["kept", "orphan", "kept.previous", "Mixed"] {
+            std::fs::create_dir_all(root.join(dir)).unwrap();
+        }
+        std::fs::write(root.join("notes.txt"), "").unwrap();
+        let ids: Vec<String> = ["kept", "mixed", "gone", "../escape"].iter().map(|id| id.to_string()).collect();
+
+        let (orphans, missing) = dir_mismatches(&root, &ids).unwrap();
+        assert_eq!(orphans, vec!["kept.previous".to_string(), "orphan".to_string()]);
+        // Matching ignores case, including for a profile created after listing
+        assert!(!is_orphan_dir("ORPHAN", &lowercase_ids(&["orphan".to_string()])));
+        // "mixed" is only missing where the filesystem is case-sensitive
+        assert!(missing.contains(&"gone".to_string()));
+        assert!(!missing.contains(&"kept".to_string()));
+        assert!(!missing.contains(&"../escape".to_string()));
+
+        std::fs::remove_dir_all(&root).ok();
+    }
+
+    #[test]
+    fn test_repair_rebuilds_damaged_index() {
+        let root = std::env::temp_dir().join(format!("identityforge-repair-{}", std::process::id()));
+        std::fs::remove_dir_all(&root).ok();
+        let path = root.join("identityforge.db");
+        let db = Database::new(&path, root.join("profiles")).unwrap();
+        for i in 0..200 {
+            db.set_setting(&format!("key-{}", i), &"v".repeat(100)).unwrap();
+        }
+        assert!(db.check_integrity().unwrap().ok);
+        let root_page: u64 = {
+            let conn = db.conn.lock().unwrap();
+            conn.execute_batch("CREATE INDEX settings_value ON settings(value)").unwrap();
+            conn.query_row("SELECT rootpage FROM sqlite_master WHERE name = 'settings_value'", [], |row| row.get(0))
+                .unwrap()
+        };
+        drop(db);
+
+        // Scribble over the index, leaving the rows intact
+        let mut bytes = std::fs::read(&path).unwrap();
+        let page_size = u16::from_be_bytes([bytes[16], bytes[17]]) as u64;
+        let start = ((root_page - 1) * page_size) as usize;
+        bytes[start + 8..start + 64].fill(0xAB);
+        std::fs::write(&path, &bytes).unwrap();
+
+        let db = Database::new(&path, root.join("profiles")).unwrap();
+        let report = db.check_integrity().unwrap();
+        assert!(!report.ok);
+        assert!(!report.integrity_issues.is_empty());
+
+        let repair = db.repair().unwrap();
+        assert!(repair.incomplete_tables.is_empty(), "{:?}", repair.incomplete_tables);
+        assert!(repair.rows_recovered >= 200);
+        assert!(Path::new(&repair.backup_path).exists());
+        assert!(db.check_integrity().unwrap().ok);
+        assert_eq!(db.get_setting("key-199").unwrap().map(|v| v.len()), Some(100));
+
+        drop(db);
+        std::fs::remove_dir_all(&root).ok();
+    }
+}
@@ -1,7 +1,14 @@
+use crate::database::{Database, Profile};
+use crate::templates::FingerprintTemplate;
+use chrono::{DateTime, Offset, TimeZone};
+use chrono_tz::Tz;
 use rand::prelude::*;
 use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
 use std::collections::hash_map::DefaultHasher;
+use std::collections::{BTreeMap, HashMap, HashSet};
 use std::hash::{Hash, Hasher};
+use std::sync::Mutex;
 
 /// User agent templates for different platforms
 const USER_AGENTS: &[(&str, &str)] = &[
@@ -29,7 +36,415 @@ const USER_AGENTS: &[(&str, &str)] = &[
     ("Linux x86_64", "Mozilla/5.0 (X11; Ubuntu; Linux x86_64; rv:120.0) Gecko/20100101 Firefox/120.0"),
 ];
 
-/// Common screen resolutions
+/// Browser families whose UA version `age_user_agent` knows how to bump
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum UaBrowser {
+    Chrome,
+    Firefox,
+}
+
+/// Extract the browser family and major version from a UA string
+fn ua_browser_version(user_agent: &str) -> Option<(UaBrowser, u32)> {
+    let major_after = |marker: &str| -> Option<u32> {
+        let start = user_agent.find(marker)? + marker.len();
+        let digits: String = user_agent[start..].chars().take_while(|c| c.is_ascii_digit()).collect();
+        digits.parse().ok()
+    };
+
+    // Edge and Opera also carry a Chrome/ token but version independently
+    if user_agent.contains("Edg/") || user_agent.contains("OPR/") {
+        return None;
+    }
+    if let Some(version) = major_after("Firefox/") {
+        return Some((UaBrowser::Firefox, version));
+    }
+    major_after("Chrome/").map(|version| (UaBrowser::Chrome, version))
+}
+
+/// Chrome major version a UA claims; `None` for Firefox, Edge, Opera and Safari
+pub fn chrome_major_version(user_agent: &str) -> Option<u32> {
+    match ua_browser_version(user_agent)? {
+        (UaBrowser::Chrome, version) => Some(version),
+        _ => None,
+    }
+}
+
+/// Newest Chrome major version in the UA pool
+pub fn newest_chrome_version() -> u32 {
+    USER_AGENTS
+        .iter()
+        .filter_map(|(_, ua)| chrome_major_version(ua))
+        .max()
+        .unwrap_or(0)
+}
+
+/// Bump the Chrome/Firefox major version in `user_agent` to the next version
+/// found in the UA pool, leaving the OS and engine tokens untouched.
+/// Returns `None` if the browser is unrecognized or already at the newest known version.
+pub fn age_user_agent(user_agent: &str) -> Option<String> {
+    let (browser, current) = ua_browser_version(user_agent)?;
+
+    let next = USER_AGENTS
+        .iter()
+        .filter_map(|(_, ua)| ua_browser_version(ua))
+        .filter(|(b, v)| *b == browser && *v > current)
+        .map(|(_, v)| v)
+        .min()?;
+
+    let aged = match browser {
+        UaBrowser::Chrome => user_agent.replace(
+            &format!("Chrome/{}.", current),
+            &format!("Chrome/{}.", next),
+        ),
+        UaBrowser::Firefox => user_agent
+            .replace(&format!("rv:{}.", current), &format!("rv:{}.", next))
+            .replace(&format!("Firefox/{}.", current), &format!("Firefox/{}.", next)),
+    };
+    Some(aged)
+}
+
+/// Lowercase browser name for a UA, as used by templates
+fn ua_browser_name(user_agent: &str) -> &'static str {
+    if user_agent.contains("Firefox/") {
+        "firefox"
+    } else if user_agent.contains("Chrome/") {
+        "chrome"
+    } else {
+        "safari"
+    }
+}
+
+/// `navigator.vendor` and `navigator.productSub` for the browser family a UA
+/// claims. Chromium browsers (Edge and Opera included) say Google, Safari
+/// says Apple and Firefox leaves the vendor empty.
+fn navigator_vendor(user_agent: &str) -> (&'static str, &'static str) {
+    match ua_browser_name(user_agent) {
+        "firefox" => ("", "20100101"),
+        "chrome" => ("Google Inc.", "20030107"),
+        _ => ("Apple Computer, Inc.", "20030107"),
+    }
+}
+
+/// `navigator.buildID` Firefox has reported for every release since 64,
+/// which froze it to stop it being used for fingerprinting
+const FIREFOX_BUILD_ID: &str = "20181001000000";
+
+/// `navigator.oscpu` as Firefox reports it for the spoofed platform, using
+/// the OS version from the UA where it has one. `None` for other browsers,
+/// which don't expose `oscpu` at all.
+fn firefox_oscpu(user_agent: &str, platform: &str) -> Option<String> {
+    if ua_browser_name(user_agent) != "firefox" {
+        return None;
+    }
+    // The UA's system tokens, e.g. "Windows NT 10.0; Win64; x64; rv:121.0"
+    let system = user_agent
+        .split_once('(')
+        .and_then(|(_, rest)| rest.split_once(')'))
+        .map_or("", |(system, _)| system);
+    let tokens: Vec<&str> = system.split("; ").filter(|t| !t.starts_with("rv:")).collect();
+    let token = |prefix: &str| tokens.iter().find(|t| t.starts_with(prefix)).map(|t| t.to_string());
+
+    let oscpu = match platform {
+        "Win32" if tokens.first().is_some_and(|t| t.starts_with("Windows NT")) => tokens.join("; "),
+        "Win32" => "Windows NT 10.0; Win64; x64".to_string(),
+        "MacIntel" => token("Intel Mac OS X").unwrap_or_else(|| "Intel Mac OS X 10.15".to_string()),
+        _ => token("Linux").unwrap_or_else(|| platform.to_string()),
+    };
+    Some(oscpu)
+}
+
+/// One entry of `navigator.userAgentData.brands` or `fullVersionList`
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct UaBrand {
+    pub brand: String,
+    pub version: String,
+}
+
+/// `navigator.userAgentData` as the spoof script exposes it: the low-entropy
+/// `brands`, `mobile` and `platform`, plus what `getHighEntropyValues`
+/// resolves. Field names follow the JS API.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UserAgentData {
+    pub brands: Vec<UaBrand>,
+    pub mobile: bool,
+    pub platform: String,
+    pub architecture: String,
+    pub bitness: String,
+    pub model: String,
+    pub platform_version: String,
+    pub ua_full_version: String,
+    pub full_version_list: Vec<UaBrand>,
+    pub wow64: bool,
+}
+
+/// Version after `marker` in a UA, e.g. `120.0.0.0` after `Chrome/`
+fn ua_version_after(user_agent: &str, marker: &str) -> Option<String> {
+    let start = user_agent.find(marker)? + marker.len();
+    let version: String = user_agent[start..]
+        .chars()
+        .take_while(|c| c.is_ascii_digit() || *c == '.')
+        .collect();
+    Some(version).filter(|v| v.starts_with(|c: char| c.is_ascii_digit()))
+}
+
+/// Chromium's GREASE brand for major version `seed`, e.g. `Not_A Brand` 8 for 120
+fn grease_brand(seed: u32) -> (String, &'static str) {
+    const CHARS: [char; 11] = [' ', '(', ':', '-', '.', '/', ')', ';', '=', '?', '_'];
+    const VERSIONS: [&str; 3] = ["8", "99", "24"];
+    let at = |i: u32| CHARS[(i % CHARS.len() as u32) as usize];
+    (format!("Not{}A{}Brand", at(seed), at(seed + 1)), VERSIONS[(seed % 3) as usize])
+}
+
+/// Where Chromium puts the GREASE, Chromium and browser brands (in that
+/// input order) for major version `seed`
+fn brand_order(seed: u32) -> [usize; 3] {
+    const ORDERS: [[usize; 3]; 6] = [[0, 1, 2], [0, 2, 1], [1, 0, 2], [1, 2, 0], [2, 0, 1], [2, 1, 0]];
+    ORDERS[(seed % 6) as usize]
+}
+
+/// Client hints matching a fingerprint, or `None` for UAs of browsers
+/// without `userAgentData` (Firefox and Safari) or with no readable Chrome
+/// version. Platform details follow the spoofed `navigator.platform`, and
+/// Apple Silicon GPUs make a Mac report `arm`.
+pub fn user_agent_data(fingerprint: &Fingerprint) -> Option<UserAgentData> {
+    let user_agent = &fingerprint.user_agent;
+    if ua_browser_name(user_agent) != "chrome" {
+        return None;
+    }
+    let chrome = ua_version_after(user_agent, "Chrome/")?;
+    // The browser's own brand; Edge and Opera version apart from Chromium
+    let (brand, brand_version) = if let Some(version) = ua_version_after(user_agent, "Edg/") {
+        ("Microsoft Edge", version)
+    } else if let Some(version) = ua_version_after(user_agent, "OPR/") {
+        ("Opera", version)
+    } else {
+        ("Google Chrome", chrome.clone())
+    };
+    let major = |version: &str| version.split('.').next().unwrap_or_default().to_string();
+    // Chromium derives the GREASE brand and the brand order from its major version
+    let seed: u32 = major(&chrome).parse().ok()?;
+    let (grease, grease_version) = grease_brand(seed);
+    let list = |full: bool| {
+        let version = |v: &str| if full { v.to_string() } else { major(v) };
+        let inputs = [
+            UaBrand { brand: grease.clone(), version: version(&format!("{}.0.0.0", grease_version)) },
+            UaBrand { brand: "Chromium".to_string(), version: version(&chrome) },
+            UaBrand { brand: brand.to_string(), version: version(&brand_version) },
+        ];
+        let mut brands = vec![None, None, None];
+        for (brand, slot) in inputs.into_iter().zip(brand_order(seed)) {
+            brands[slot] = Some(brand);
+        }
+        brands.into_iter().flatten().collect::<Vec<_>>()
+    };
+
+    let (platform, platform_version) = match fingerprint.platform.as_str() {
+        "Win32" => ("Windows", "10.0.0".to_string()),
+        "MacIntel" => {
+            let version = user_agent
+                .split_once("Mac OS X ")
+                .map(|(_, rest)| rest.chars().take_while(|c| c.is_ascii_digit() || *c == '_').collect::<String>())
+                .filter(|v| !v.is_empty())
+                .map_or_else(|| "10.15.7".to_string(), |v| v.replace('_', "."));
+            ("macOS", version)
+        }
+        _ => ("Linux", String::new()),
+    };
+    let arm = platform == "macOS" && fingerprint.webgl_renderer.starts_with("Apple M");
+
+    Some(UserAgentData {
+        brands: list(false),
+        mobile: false,
+        platform: platform.to_string(),
+        architecture: if arm { "arm" } else { "x86" }.to_string(),
+        bitness: "64".to_string(),
+        model: String::new(),
+        platform_version,
+        ua_full_version: brand_version.clone(),
+        full_version_list: list(true),
+        wow64: false,
+    })
+}
+
+/// Punctuation keys of a US keyboard; letters and digits are filled in by `keyboard_layout`
+const US_PUNCTUATION_KEYS: &[(&str, &str)] = &[
+    ("Backquote", "`"), ("Minus", "-"), ("Equal", "="), ("BracketLeft", "["), ("BracketRight", "]"),
+    ("Backslash", "\\"), ("Semicolon", ";"), ("Quote", "'"), ("Comma", ","), ("Period", "."), ("Slash", "/"),
+];
+
+/// Keys that differ from US per layout. ISO layouts add `IntlBackslash`.
+const GB_KEYS: &[(&str, &str)] = &[("Backslash", "#"), ("IntlBackslash", "\\")];
+const GB_MAC_KEYS: &[(&str, &str)] = &[("Backquote", "§"), ("IntlBackslash", "`")];
+const DE_KEYS: &[(&str, &str)] = &[
+    ("Backquote", "^"), ("Minus", "ß"), ("Equal", "´"), ("KeyY", "z"), ("KeyZ", "y"), ("BracketLeft", "ü"),
+    ("BracketRight", "+"), ("Backslash", "#"), ("Semicolon", "ö"), ("Quote", "ä"), ("Slash", "-"),
+    ("IntlBackslash", "<"),
+];
+const FR_KEYS: &[(&str, &str)] = &[
+    ("Backquote", "²"), ("Digit1", "&"), ("Digit2", "é"), ("Digit3", "\""), ("Digit4", "'"), ("Digit5", "("),
+    ("Digit6", "-"), ("Digit7", "è"), ("Digit8", "_"), ("Digit9", "ç"), ("Digit0", "à"), ("Minus", ")"),
+    ("KeyQ", "a"), ("KeyW", "z"), ("KeyA", "q"), ("KeyZ", "w"), ("KeyM", ","), ("BracketLeft", "^"),
+    ("BracketRight", "$"), ("Backslash", "*"), ("Semicolon", "m"), ("Quote", "ù"), ("Comma", ";"),
+    ("Period", ":"), ("Slash", "!"), ("IntlBackslash", "<"),
+];
+const FR_MAC_KEYS: &[(&str, &str)] = &[
+    ("Backquote", "@"), ("Digit6", "§"), ("Digit8", "!"), ("Equal", "-"), ("Backslash", "`"), ("Slash", "="),
+];
+const ES_KEYS: &[(&str, &str)] = &[
+    ("Backquote", "º"), ("Minus", "'"), ("Equal", "¡"), ("BracketLeft", "`"), ("BracketRight", "+"),
+    ("Backslash", "ç"), ("Semicolon", "ñ"), ("Quote", "´"), ("Slash", "-"), ("IntlBackslash", "<"),
+];
+const IT_KEYS: &[(&str, &str)] = &[
+    ("Backquote", "\\"), ("Minus", "'"), ("Equal", "ì"), ("BracketLeft", "è"), ("BracketRight", "+"),
+    ("Backslash", "ù"), ("Semicolon", "ò"), ("Quote", "à"), ("Slash", "-"), ("IntlBackslash", "<"),
+];
+const BR_KEYS: &[(&str, &str)] = &[
+    ("Backquote", "'"), ("BracketLeft", "´"), ("BracketRight", "["), ("Backslash", "]"), ("Semicolon", "ç"),
+    ("Quote", "~"), ("Slash", ";"), ("IntlBackslash", "\\"), ("IntlRo", "/"),
+];
+const JP_KEYS: &[(&str, &str)] = &[
+    ("Equal", "^"), ("IntlYen", "¥"), ("BracketLeft", "@"), ("BracketRight", "["), ("Backslash", "]"),
+    ("Quote", ":"), ("IntlRo", "\\"),
+];
+
+/// `navigator.keyboard.getLayoutMap()` entries (key code to the character it
+/// types) for the layout usual with `language`. US for languages typed on
+/// it, such as Chinese and Korean; Mac keyboards differ for British and French.
+fn keyboard_layout(language: &str, platform: &str) -> BTreeMap<String, String> {
+    let mut map = BTreeMap::new();
+    for letter in 'a'..='z' {
+        map.insert(format!("Key{}", letter.to_ascii_uppercase()), letter.to_string());
+    }
+    for digit in 0..10 {
+        map.insert(format!("Digit{}", digit), digit.to_string());
+    }
+    for (code, key) in US_PUNCTUATION_KEYS {
+        map.insert(code.to_string(), key.to_string());
+    }
+
+    let language = language.to_lowercase();
+    let (base, region) = language.split_once(['-', '_']).unwrap_or((&language, ""));
+    let mac = platform == "MacIntel";
+    let layers: &[&[(&str, &str)]] = match (base, region) {
+        ("en", "gb" | "ie") if mac => &[GB_KEYS, GB_MAC_KEYS],
+        ("en", "gb" | "ie") => &[GB_KEYS],
+        ("de", _) => &[DE_KEYS],
+        ("fr", "ca") => &[],
+        ("fr", _) if mac => &[FR_KEYS, FR_MAC_KEYS],
+        ("fr", _) => &[FR_KEYS],
+        ("es", "es" | "") => &[ES_KEYS],
+        ("it", _) => &[IT_KEYS],
+        ("pt", _) => &[BR_KEYS],
+        ("ja", _) => &[JP_KEYS],
+        _ => &[],
+    };
+    for (code, key) in layers.iter().flat_map(|layer| layer.iter()) {
+        map.insert(code.to_string(), key.to_string());
+    }
+    map
+}
+
+/// Display name and major version of the browser a UA claims to be
+fn ua_browser_label(user_agent: &str) -> (&'static str, Option<u32>) {
+    let major_after = |marker: &str| -> Option<u32> {
+        let start = user_agent.find(marker)? + marker.len();
+        let digits: String = user_agent[start..].chars().take_while(|c| c.is_ascii_digit()).collect();
+        digits.parse().ok()
+    };
+
+    // Checked most specific first: Edge and Opera UAs also carry Chrome/ and Safari/
+    for (marker, name) in [("Edg/", "Edge"), ("OPR/", "Opera"), ("Firefox/", "Firefox"), ("Chrome/", "Chrome")] {
+        if user_agent.contains(marker) {
+            return (name, major_after(marker));
+        }
+    }
+    if user_agent.contains("Safari/") {
+        return ("Safari", major_after("Version/"));
+    }
+    ("Unknown", None)
+}
+
+/// Operating system a UA claims, falling back to `navigator.platform`
+fn ua_os_label(user_agent: &str, platform: &str) -> String {
+    if user_agent.contains("Windows NT 10.0") {
+        // Windows 11 still reports NT 10.0
+        "Windows 10/11".to_string()
+    } else if let Some(start) = user_agent.find("Mac OS X ") {
+        let version: String = user_agent[start + "Mac OS X ".len()..]
+            .chars()
+            .take_while(|c| c.is_ascii_digit() || *c == '_' || *c == '.')
+            .map(|c| if c == '_' { '.' } else { c })
+            .collect();
+        format!("macOS {}", version).trim_end().to_string()
+    } else if user_agent.contains("Android") {
+        "Android".to_string()
+    } else if user_agent.contains("iPhone") || user_agent.contains("iPad") {
+        "iOS".to_string()
+    } else if user_agent.contains("Linux") || user_agent.contains("X11") {
+        "Linux".to_string()
+    } else if user_agent.contains("Windows") {
+        "Windows".to_string()
+    } else {
+        platform.to_string()
+    }
+}
+
+/// Country codes that are mostly sold as generic domains, so say nothing about location
+const GENERIC_CCTLDS: &[&str] = &["ai", "cc", "co", "fm", "gg", "io", "ly", "me", "sh", "to", "tv", "ws"];
+
+/// Best guess at a proxy's country from its hostname: a country-code TLD
+/// (`proxy.example.de`) or a leading country label (`us.provider.com`).
+/// Without a GeoIP database, IP hosts and other names give `None`.
+fn guess_proxy_country(host: &str) -> Option<String> {
+    let host = host.trim().trim_end_matches('.').to_lowercase();
+    if host.parse::<std::net::IpAddr>().is_ok() {
+        return None;
+    }
+    let labels: Vec<&str> = host.split('.').collect();
+    if labels.len() < 2 {
+        return None;
+    }
+    let is_country = |label: &str| label.len() == 2 && label.chars().all(|c| c.is_ascii_alphabetic());
+
+    let tld = labels[labels.len() - 1];
+    let code = if is_country(tld) && !GENERIC_CCTLDS.contains(&tld) {
+        tld
+    } else if is_country(labels[0]) {
+        labels[0]
+    } else {
+        return None;
+    };
+    Some(code.to_uppercase())
+}
+
+/// Smallest screen side a profile may report, in CSS pixels
+pub const MIN_SCREEN_DIMENSION: i32 = 320;
+
+/// Largest screen side a profile may report (8K UHD width)
+pub const MAX_SCREEN_DIMENSION: i32 = 7680;
+
+/// Reject screen sizes no desktop reports: a side outside
+/// `MIN_SCREEN_DIMENSION..=MAX_SCREEN_DIMENSION`, or portrait orientation.
+/// The launcher also sizes windows from these, so they must stay sane.
+pub fn validate_screen_size(width: i32, height: i32) -> Result<(), String> {
+    let range = MIN_SCREEN_DIMENSION..=MAX_SCREEN_DIMENSION;
+    if !range.contains(&width) || !range.contains(&height) {
+        return Err(format!(
+            "Screen size {}x{} is out of range: each side must be {} to {}",
+            width, height, MIN_SCREEN_DIMENSION, MAX_SCREEN_DIMENSION
+        ));
+    }
+    if width < height {
+        return Err(format!(
+            "Screen size {}x{} is portrait; desktop profiles need width at least height",
+            width, height
+        ));
+    }
+    Ok(())
+}
+
+/// Every screen resolution any platform uses, as offered to the UI
 const SCREEN_RESOLUTIONS: &[(i32, i32)] = &[
     (1920, 1080),
     (2560, 1440),
@@ -43,6 +458,51 @@ const SCREEN_RESOLUTIONS: &[(i32, i32)] = &[
     (3440, 1440),
     (3840, 2160),
     (1280, 1024),
+    (1280, 800),
+    (1470, 956),
+    (1512, 982),
+    (1728, 1117),
+];
+
+/// Windows screens: mostly laptop panels (often at 125% scaling) and common monitors
+const WINDOWS_RESOLUTIONS: &[(i32, i32)] = &[
+    (1920, 1080),
+    (1366, 768),
+    (1536, 864),
+    (1440, 900),
+    (1600, 900),
+    (1280, 720),
+    (1280, 1024),
+    (1680, 1050),
+    (2560, 1440),
+    (2560, 1080),
+    (3440, 1440),
+    (3840, 2160),
+];
+
+/// Mac screens in CSS pixels: Retina MacBook defaults plus common external displays
+const MAC_RESOLUTIONS: &[(i32, i32)] = &[
+    (1440, 900),
+    (1280, 800),
+    (1470, 956),
+    (1512, 982),
+    (1728, 1117),
+    (1680, 1050),
+    (1920, 1080),
+    (2560, 1440),
+];
+
+/// Linux desktop screens
+const LINUX_RESOLUTIONS: &[(i32, i32)] = &[
+    (1920, 1080),
+    (1366, 768),
+    (1600, 900),
+    (1680, 1050),
+    (1280, 1024),
+    (2560, 1440),
+    (2560, 1080),
+    (3440, 1440),
+    (3840, 2160),
 ];
 
 /// WebGL vendor/renderer combinations
@@ -67,23 +527,98 @@ const WEBGL_CONFIGS: &[(&str, &str)] = &[
     ("Google Inc. (Intel)", "ANGLE (Intel, Intel(R) UHD Graphics 630 Direct3D11 vs_5_0 ps_5_0, D3D11)"),
 ];
 
-/// Timezones with their UTC offsets
-const TIMEZONES: &[(&str, i32)] = &[
-    ("America/New_York", 300),
-    ("America/Los_Angeles", 480),
-    ("America/Chicago", 360),
-    ("America/Denver", 420),
-    ("Europe/London", 0),
-    ("Europe/Paris", -60),
-    ("Europe/Berlin", -60),
-    ("Asia/Tokyo", -540),
-    ("Asia/Shanghai", -480),
-    ("Asia/Singapore", -480),
-    ("Australia/Sydney", -660),
-    ("America/Toronto", 300),
-    ("America/Sao_Paulo", 180),
+/// IANA timezones the generator picks from, uniformly. The smaller zones in
+/// `TIMEZONE_COUNTRIES` are only reached through `spread_timezone`.
+const TIMEZONES: &[&str] = &[
+    "America/New_York",
+    "America/Los_Angeles",
+    "America/Chicago",
+    "America/Denver",
+    "Europe/London",
+    "Europe/Paris",
+    "Europe/Berlin",
+    "Asia/Tokyo",
+    "Asia/Shanghai",
+    "Asia/Singapore",
+    "Australia/Sydney",
+    "America/Toronto",
+    "America/Sao_Paulo",
 ];
 
+/// Country (ISO 3166-1 alpha-2) of every timezone the generator knows, with
+/// a rough share of the country's population living in it (percent)
+const TIMEZONE_COUNTRIES: &[(&str, &str, u32)] = &[
+    ("America/New_York", "US", 47),
+    ("America/Chicago", "US", 29),
+    ("America/Los_Angeles", "US", 15),
+    ("America/Denver", "US", 5),
+    ("America/Phoenix", "US", 2),
+    ("America/Anchorage", "US", 1),
+    ("Pacific/Honolulu", "US", 1),
+    ("Europe/London", "GB", 100),
+    ("Europe/Paris", "FR", 100),
+    ("Europe/Berlin", "DE", 100),
+    ("Asia/Tokyo", "JP", 100),
+    ("Asia/Shanghai", "CN", 100),
+    ("Asia/Singapore", "SG", 100),
+    ("Australia/Sydney", "AU", 32),
+    ("Australia/Melbourne", "AU", 26),
+    ("Australia/Brisbane", "AU", 21),
+    ("Australia/Perth", "AU", 11),
+    ("Australia/Adelaide", "AU", 7),
+    ("America/Toronto", "CA", 60),
+    ("America/Vancouver", "CA", 14),
+    ("America/Edmonton", "CA", 12),
+    ("America/Winnipeg", "CA", 7),
+    ("America/Halifax", "CA", 6),
+    ("America/St_Johns", "CA", 1),
+    ("America/Sao_Paulo", "BR", 80),
+    ("America/Fortaleza", "BR", 12),
+    ("America/Manaus", "BR", 8),
+];
+
+/// Every timezone the generator knows: `TIMEZONES`, then the zones only
+/// `spread_timezone` picks
+fn known_timezones() -> impl Iterator<Item = &'static str> {
+    TIMEZONES.iter().copied().chain(
+        TIMEZONE_COUNTRIES
+            .iter()
+            .map(|(tz, _, _)| *tz)
+            .filter(|tz| !TIMEZONES.contains(tz)),
+    )
+}
+
+/// Country a generator timezone is in
+pub fn timezone_country(timezone: &str) -> Option<&'static str> {
+    TIMEZONE_COUNTRIES
+        .iter()
+        .find(|(tz, _, _)| *tz == timezone)
+        .map(|(_, code, _)| *code)
+}
+
+/// Whether the generator knows more than one timezone in `country`
+pub fn has_timezone_choice(country: &str) -> bool {
+    country_timezones(country).len() > 1
+}
+
+/// Generator timezones in `country` with their population weights
+fn country_timezones(country: &str) -> Vec<(&'static str, u32)> {
+    TIMEZONE_COUNTRIES
+        .iter()
+        .filter(|(_, code, _)| code.eq_ignore_ascii_case(country))
+        .map(|(tz, _, weight)| (*tz, *weight))
+        .collect()
+}
+
+/// Region subtag of a language tag, e.g. `JP` for `ja-JP`
+pub fn language_region(language: &str) -> Option<String> {
+    language
+        .split('-')
+        .skip(1)
+        .find(|subtag| subtag.len() == 2 && subtag.chars().all(|c| c.is_ascii_alphabetic()))
+        .map(|subtag| subtag.to_ascii_uppercase())
+}
+
 /// Languages
 const LANGUAGES: &[&str] = &[
     "en-US",
@@ -100,12 +635,111 @@ const LANGUAGES: &[&str] = &[
     "ko-KR",
 ];
 
+/// Ordered `navigator.languages` for a primary language: the primary first,
+/// then the rest of `languages` in order without repeats. An empty list (rows
+/// stored before languages were tracked) gives the primary plus its base language.
+pub fn navigator_languages(primary: &str, languages: &[String]) -> Vec<String> {
+    let mut list = vec![primary.to_string()];
+    if languages.is_empty() {
+        let base = primary.split('-').next().unwrap_or(primary);
+        if base != primary {
+            list.push(base.to_string());
+        }
+    }
+    for language in languages {
+        if !list.contains(language) {
+            list.push(language.clone());
+        }
+    }
+    list
+}
+
 /// Hardware concurrency options (CPU cores)
 const HARDWARE_CONCURRENCY: &[i32] = &[2, 4, 6, 8, 10, 12, 16];
 
 /// Device memory options (GB)
 const DEVICE_MEMORY: &[i32] = &[2, 4, 8, 16, 32];
 
+/// Canvas noise levels, mapped to (pixel modulus, amplitude) in `canvas_noise_params`
+pub const CANVAS_NOISE_LEVELS: &[&str] = &["low", "medium", "high"];
+
+/// Canvas noise level used when none is chosen
+pub const DEFAULT_CANVAS_NOISE_LEVEL: &str = "medium";
+
+/// WebRTC handling: block it outright, allow it through TURN relays only
+/// (no local or real public IP in ICE candidates), or leave it untouched
+pub const WEBRTC_MODES: &[&str] = &["disabled", "relay", "enabled"];
+
+/// WebRTC mode when neither the profile nor `default_webrtc_mode` sets one
+pub const DEFAULT_WEBRTC_MODE: &str = "disabled";
+
+/// Setting key for the WebRTC mode of profiles that don't pick their own
+pub const DEFAULT_WEBRTC_MODE_SETTING: &str = "default_webrtc_mode";
+
+/// Setting key: add seeded noise to WebGL pixel readback (on unless `false`).
+/// Costs a pass over every `readPixels` result, plus a copy through a 2D
+/// canvas whenever a WebGL canvas is exported.
+/// The noise is applied to whatever the host GPU rendered, so it keeps a
+/// profile's hash stable on one machine, not across machines.
+pub const WEBGL_NOISE_SETTING: &str = "webgl_noise";
+
+/// Setting key: when `true`, a generated timezone is redrawn among the zones
+/// of its country by population, so a fleet in one country doesn't all sit
+/// in the country's largest zone
+pub const SPREAD_TIMEZONES_SETTING: &str = "spread_timezones";
+
+/// Setting key: a whole number that makes generation reproducible; empty
+/// leaves it random. See `GenerationSeed` for what is guaranteed.
+pub const GENERATION_SEED_SETTING: &str = "generation_seed";
+
+/// The pinned seed, if `GENERATION_SEED_SETTING` holds one
+fn pinned_generation_seed(db: &Database) -> Option<u64> {
+    db.get_setting(GENERATION_SEED_SETTING)
+        .ok()
+        .flatten()
+        .and_then(|value| value.trim().parse().ok())
+}
+
+/// Session state behind `GENERATION_SEED_SETTING`. With a seed pinned, one
+/// stream seeded from it hands each `FingerprintGenerator` built through
+/// `from_settings` the next seed in turn, so the same commands run in the
+/// same order after the seed is set (or the app starts) produce the same
+/// fingerprints; a bulk command draws once and generates all its profiles
+/// from that. Previews take the next seed without consuming it, so
+/// `preview_fingerprint` or `dry_run_bulk_create` show what the next real
+/// command will generate. Profile ids, and so noise seeds, stay random.
+#[derive(Default)]
+pub struct GenerationSeed {
+    stream: Mutex<Option<(u64, StdRng)>>,
+}
+
+impl GenerationSeed {
+    /// Seed for the next generator, or `None` when no seed is pinned.
+    /// `advance` is false for previews.
+    fn next(&self, db: &Database, advance: bool) -> Option<u64> {
+        let pinned = pinned_generation_seed(db)?;
+        let mut stream = self.stream.lock().unwrap_or_else(|e| e.into_inner());
+        let rng = match &mut *stream {
+            Some((seed, rng)) if *seed == pinned => rng,
+            stream => &mut stream.insert((pinned, StdRng::seed_from_u64(pinned))).1,
+        };
+        if advance {
+            Some(rng.gen())
+        } else {
+            Some(rng.clone().gen())
+        }
+    }
+
+    /// Start the sequence over, e.g. when the setting is written again
+    pub fn restart(&self) {
+        *self.stream.lock().unwrap_or_else(|e| e.into_inner()) = None;
+    }
+}
+
+/// Generation of the protections in `generate_spoof_script`. Bump it whenever
+/// a protection is added or changes behaviour so automation can detect it.
+pub const SPOOF_ENGINE_VERSION: u32 = 19;
+
 /// Common fonts by platform
 const WINDOWS_FONTS: &[&str] = &[
     "Arial", "Arial Black", "Calibri", "Cambria", "Cambria Math", "Comic Sans MS",
@@ -146,6 +780,19 @@ pub struct Fingerprint {
     pub device_memory: i32,
     pub timezone: String,
     pub language: String,
+    /// Full `navigator.languages` list, starting with `language`
+    #[serde(default)]
+    pub languages: Vec<String>,
+    /// Pinned Do Not Track signal; `None` derives it from the profile seed
+    pub do_not_track: Option<bool>,
+    pub canvas_noise_level: String,
+    /// Pinned viewport size; `None` derives it from the profile seed
+    pub inner_width: Option<i32>,
+    pub inner_height: Option<i32>,
+    pub custom_headers: BTreeMap<String, String>,
+    /// One of `WEBRTC_MODES`; `None` falls back to `DEFAULT_WEBRTC_MODE`
+    #[serde(default)]
+    pub webrtc_mode: Option<String>,
     pub default_url: String,
     // Proxy settings
     pub proxy_enabled: bool,
@@ -154,117 +801,824 @@ pub struct Fingerprint {
     pub proxy_port: i32,
     pub proxy_username: Option<String>,
     pub proxy_password: Option<String>,
+    /// Seeded noise on WebGL pixel readback; off via `WEBGL_NOISE_SETTING`
+    #[serde(default = "default_webgl_noise")]
+    pub webgl_noise: bool,
 }
 
-/// Fingerprint generator with configurable options
-pub struct FingerprintGenerator {
-    rng: ThreadRng,
+fn default_webgl_noise() -> bool {
+    true
 }
 
-impl FingerprintGenerator {
-    pub fn new() -> Self {
-        FingerprintGenerator {
-            rng: thread_rng(),
+impl Fingerprint {
+    /// Stable SHA-256 over the identifying fields (proxy and start URL excluded),
+    /// so two profiles presenting the same identity produce the same hash
+    pub fn identity_hash(&self) -> String {
+        let mut hasher = Sha256::new();
+        let fields = [
+            self.user_agent.clone(),
+            self.platform.clone(),
+            self.screen_width.to_string(),
+            self.screen_height.to_string(),
+            self.webgl_vendor.clone(),
+            self.webgl_renderer.clone(),
+            self.hardware_concurrency.to_string(),
+            self.device_memory.to_string(),
+            self.timezone.clone(),
+            self.language.clone(),
+            navigator_languages(&self.language, &self.languages).join(","),
+        ];
+        for field in &fields {
+            hasher.update(field.as_bytes());
+            hasher.update([0u8]); // separator so adjacent fields can't run together
         }
+        hex::encode(hasher.finalize())
     }
 
-    /// Generate a completely random fingerprint
-    pub fn generate(&mut self) -> Fingerprint {
-        let (platform, user_agent) = USER_AGENTS[self.rng.gen_range(0..USER_AGENTS.len())];
-        let (width, height) = SCREEN_RESOLUTIONS[self.rng.gen_range(0..SCREEN_RESOLUTIONS.len())];
-        let (vendor, renderer) = WEBGL_CONFIGS[self.rng.gen_range(0..WEBGL_CONFIGS.len())];
-        let hardware_concurrency = HARDWARE_CONCURRENCY[self.rng.gen_range(0..HARDWARE_CONCURRENCY.len())];
-        let device_memory = DEVICE_MEMORY[self.rng.gen_range(0..DEVICE_MEMORY.len())];
-        let (timezone, _) = TIMEZONES[self.rng.gen_range(0..TIMEZONES.len())];
-        let language = LANGUAGES[self.rng.gen_range(0..LANGUAGES.len())];
+    /// Attribute combinations no real browser reports, e.g. a Windows UA on a
+    /// Mac platform or an Apple GPU outside macOS
+    pub fn coherence_issues(&self) -> Vec<String> {
+        let mut issues = Vec::new();
+        let ua_os = if self.user_agent.contains("Windows") {
+            "Win32"
+        } else if self.user_agent.contains("Macintosh") {
+            "MacIntel"
+        } else if self.user_agent.contains("Linux") {
+            "Linux x86_64"
+        } else {
+            ""
+        };
 
-        Fingerprint {
-            user_agent: user_agent.to_string(),
-            platform: platform.to_string(),
-            screen_width: width,
-            screen_height: height,
-            webgl_vendor: vendor.to_string(),
-            webgl_renderer: renderer.to_string(),
-            hardware_concurrency,
-            device_memory,
-            timezone: timezone.to_string(),
-            language: language.to_string(),
-            default_url: "https://www.google.com".to_string(),
-            proxy_enabled: false,
-            proxy_type: "http".to_string(),
-            proxy_host: String::new(),
-            proxy_port: 0,
-            proxy_username: None,
-            proxy_password: None,
+        if !ua_os.is_empty() && ua_os != self.platform {
+            issues.push(format!(
+                "User agent is for {} but platform is {}",
+                ua_os, self.platform
+            ));
+        }
+        if ua_browser_name(&self.user_agent) == "safari" && self.platform != "MacIntel" {
+            issues.push("Safari user agent on a non-Mac platform".to_string());
+        }
+        if self.webgl_renderer.starts_with("Apple") && self.platform != "MacIntel" {
+            issues.push(format!("{} GPU on a non-Mac platform", self.webgl_renderer));
+        }
+        if self.webgl_renderer.contains("Direct3D") && self.platform != "Win32" {
+            issues.push("Direct3D renderer on a non-Windows platform".to_string());
         }
+        issues
     }
 
-    /// Generate a fingerprint for a specific platform
-    pub fn generate_for_platform(&mut self, target_platform: &str) -> Fingerprint {
-        // Map friendly names to internal platform identifiers
-        let search_term = match target_platform.to_lowercase().as_str() {
-            "windows" => "win32",
-            "macos" | "mac" => "macintel",
-            "linux" => "linux",
-            _ => target_platform,
-        };
-
-        let platform_agents: Vec<_> = USER_AGENTS
-            .iter()
-            .filter(|(p, _)| p.to_lowercase().contains(&search_term.to_lowercase()))
-            .collect();
+    /// Timezone and language that don't fit traffic exiting from `country`,
+    /// e.g. `Asia/Tokyo` and `ja-JP` behind a US proxy. Timezones outside the
+    /// generator's list and languages without a region aren't judged, nor is
+    /// `en-US`, which people everywhere leave their browsers set to.
+    pub fn location_issues(&self, country: &str) -> Vec<String> {
+        let mut issues = Vec::new();
+        if let Some(code) = timezone_country(&self.timezone).filter(|code| !code.eq_ignore_ascii_case(country)) {
+            issues.push(format!(
+                "Proxy exits in {} but timezone {} is in {}",
+                country, self.timezone, code
+            ));
+        }
+        let region = language_region(&self.language).filter(|_| !self.language.eq_ignore_ascii_case("en-US"));
+        if let Some(region) = region.filter(|r| !r.eq_ignore_ascii_case(country)) {
+            issues.push(format!(
+                "Proxy exits in {} but language {} is for {}",
+                country, self.language, region
+            ));
+        }
+        issues
+    }
 
-        let (platform, user_agent) = if platform_agents.is_empty() {
-            USER_AGENTS[self.rng.gen_range(0..USER_AGENTS.len())]
-        } else {
-            *platform_agents[self.rng.gen_range(0..platform_agents.len())]
+    /// Compare the identifying fields (plus proxy host) against another fingerprint
+    pub fn diff(&self, other: &Fingerprint) -> Vec<FieldDiff> {
+        let proxy_host = |fp: &Fingerprint| {
+            if fp.proxy_enabled {
+                fp.proxy_host.clone()
+            } else {
+                String::new()
+            }
+        };
+        let field = |name: &str, a: String, b: String| FieldDiff {
+            field: name.to_string(),
+            matches: a == b,
+            a,
+            b,
         };
 
-        let (width, height) = SCREEN_RESOLUTIONS[self.rng.gen_range(0..SCREEN_RESOLUTIONS.len())];
-        let (vendor, renderer) = WEBGL_CONFIGS[self.rng.gen_range(0..WEBGL_CONFIGS.len())];
-        let hardware_concurrency = HARDWARE_CONCURRENCY[self.rng.gen_range(0..HARDWARE_CONCURRENCY.len())];
-        let device_memory = DEVICE_MEMORY[self.rng.gen_range(0..DEVICE_MEMORY.len())];
-        let (timezone, _) = TIMEZONES[self.rng.gen_range(0..TIMEZONES.len())];
-        let language = LANGUAGES[self.rng.gen_range(0..LANGUAGES.len())];
+        vec![
+            field("user_agent", self.user_agent.clone(), other.user_agent.clone()),
+            field("platform", self.platform.clone(), other.platform.clone()),
+            field(
+                "screen",
+                format!("{}x{}", self.screen_width, self.screen_height),
+                format!("{}x{}", other.screen_width, other.screen_height),
+            ),
+            field("webgl_vendor", self.webgl_vendor.clone(), other.webgl_vendor.clone()),
+            field("webgl_renderer", self.webgl_renderer.clone(), other.webgl_renderer.clone()),
+            field(
+                "hardware_concurrency",
+                self.hardware_concurrency.to_string(),
+                other.hardware_concurrency.to_string(),
+            ),
+            field("device_memory", self.device_memory.to_string(), other.device_memory.to_string()),
+            field("timezone", self.timezone.clone(), other.timezone.clone()),
+            field("language", self.language.clone(), other.language.clone()),
+            field(
+                "languages",
+                navigator_languages(&self.language, &self.languages).join(","),
+                navigator_languages(&other.language, &other.languages).join(","),
+            ),
+            field("proxy_host", proxy_host(self), proxy_host(other)),
+        ]
+    }
 
-        Fingerprint {
-            user_agent: user_agent.to_string(),
-            platform: platform.to_string(),
-            screen_width: width,
-            screen_height: height,
-            webgl_vendor: vendor.to_string(),
-            webgl_renderer: renderer.to_string(),
-            hardware_concurrency,
-            device_memory,
-            timezone: timezone.to_string(),
-            language: language.to_string(),
-            default_url: "https://www.google.com".to_string(),
-            proxy_enabled: false,
-            proxy_type: "http".to_string(),
-            proxy_host: String::new(),
-            proxy_port: 0,
-            proxy_username: None,
-            proxy_password: None,
+    /// Summarize what a fingerprinting service would observe at `now_secs`
+    pub fn describe(&self, now_secs: i64) -> IdentityDescription {
+        let (browser, browser_version) = ua_browser_label(&self.user_agent);
+        // Minutes west of UTC, as getTimezoneOffset reports it
+        let offset = timezone_offset_at(&self.timezone, now_secs);
+        let east = -offset;
+        let utc_offset = format!(
+            "UTC{}{:02}:{:02}",
+            if east < 0 { '-' } else { '+' },
+            east.abs() / 60,
+            east.abs() % 60
+        );
+        let proxy_host = Some(self.proxy_host.trim())
+            .filter(|host| self.proxy_enabled && !host.is_empty())
+            .map(|host| host.to_string());
+
+        IdentityDescription {
+            browser: browser.to_string(),
+            browser_version,
+            os: ua_os_label(&self.user_agent, &self.platform),
+            gpu: self.webgl_renderer.clone(),
+            screen: format!("{}x{}", self.screen_width, self.screen_height),
+            timezone: self.timezone.clone(),
+            utc_offset,
+            languages: navigator_languages(&self.language, &self.languages),
+            hardware_concurrency: self.hardware_concurrency,
+            device_memory: exposed_device_memory(self.device_memory),
+            proxy_country: proxy_host.as_deref().and_then(guess_proxy_country),
+            proxy_host,
         }
     }
 }
 
-impl Default for FingerprintGenerator {
-    fn default() -> Self {
-        Self::new()
-    }
-}
+/// Most times a diverse batch regenerates one candidate before taking it anyway
+pub const DIVERSITY_RETRY_LIMIT: u32 = 20;
 
-/// Generate a persistent noise seed from profile ID
-fn generate_persistent_seed(profile_id: &str) -> u64 {
-    let mut hasher = DefaultHasher::new();
-    profile_id.hash(&mut hasher);
-    hasher.finish()
+/// A (platform, resolution, renderer) combination may fill at most 1 in
+/// this many profiles of a diverse batch
+const DIVERSITY_SHARE: usize = 10;
+
+/// Tracks how often each (platform, resolution, WebGL renderer) combination
+/// has appeared in a batch, so uniform sampling can't cluster on a few of them
+pub struct DiversityTracker {
+    counts: HashMap<(String, i32, i32, String), usize>,
+    max_repeats: usize,
 }
 
-/// Get fonts list for platform
-fn get_fonts_for_platform(platform: &str) -> Vec<&'static str> {
-    if platform.contains("Win") {
+impl DiversityTracker {
+    pub fn new(batch_size: usize) -> Self {
+        DiversityTracker {
+            counts: HashMap::new(),
+            max_repeats: batch_size.div_ceil(DIVERSITY_SHARE).max(1),
+        }
+    }
+
+    fn key(fingerprint: &Fingerprint) -> (String, i32, i32, String) {
+        (
+            fingerprint.platform.clone(),
+            fingerprint.screen_width,
+            fingerprint.screen_height,
+            fingerprint.webgl_renderer.clone(),
+        )
+    }
+
+    /// Whether the batch can take `fingerprint` without its combination
+    /// appearing too often
+    pub fn accepts(&self, fingerprint: &Fingerprint) -> bool {
+        self.counts.get(&Self::key(fingerprint)).copied().unwrap_or(0) < self.max_repeats
+    }
+
+    pub fn record(&mut self, fingerprint: &Fingerprint) {
+        *self.counts.entry(Self::key(fingerprint)).or_default() += 1;
+    }
+}
+
+/// Split a blocklist of `identity_hash` values into the normalized hashes
+/// and the entries that aren't one. Case and surrounding whitespace are
+/// ignored; blank entries are skipped.
+pub fn parse_hash_blocklist(entries: &[String]) -> (HashSet<String>, Vec<String>) {
+    let mut hashes = HashSet::new();
+    let mut invalid = Vec::new();
+    for entry in entries {
+        let hash = entry.trim().to_ascii_lowercase();
+        if hash.is_empty() {
+            continue;
+        }
+        if hash.len() == 64 && hash.chars().all(|c| c.is_ascii_hexdigit()) {
+            hashes.insert(hash);
+        } else {
+            invalid.push(entry.clone());
+        }
+    }
+    (hashes, invalid)
+}
+
+/// One field compared between two fingerprints
+#[derive(Debug, Clone, Serialize)]
+pub struct FieldDiff {
+    pub field: String,
+    pub a: String,
+    pub b: String,
+    pub matches: bool,
+}
+
+/// The signals a fingerprinting service sees, condensed for a person to read
+#[derive(Debug, Clone, Serialize)]
+pub struct IdentityDescription {
+    /// Browser family claimed by the user agent, e.g. "Chrome" or "Safari"
+    pub browser: String,
+    pub browser_version: Option<u32>,
+    /// Operating system claimed by the user agent, e.g. "macOS 10.15.7"
+    pub os: String,
+    /// WebGL renderer string
+    pub gpu: String,
+    pub screen: String,
+    pub timezone: String,
+    /// Offset currently in effect for `timezone`, e.g. "UTC+02:00"
+    pub utc_offset: String,
+    /// `navigator.languages`, primary locale first
+    pub languages: Vec<String>,
+    pub hardware_concurrency: i32,
+    /// `navigator.deviceMemory` as exposed (capped at 8)
+    pub device_memory: f64,
+    /// Proxy host when the proxy is enabled
+    pub proxy_host: Option<String>,
+    /// Country guessed from the proxy hostname; `None` if it gives no hint
+    pub proxy_country: Option<String>,
+}
+
+impl From<&Profile> for Fingerprint {
+    fn from(profile: &Profile) -> Self {
+        Fingerprint {
+            user_agent: profile.user_agent.clone(),
+            platform: profile.platform.clone(),
+            screen_width: profile.screen_width,
+            screen_height: profile.screen_height,
+            webgl_vendor: profile.webgl_vendor.clone(),
+            webgl_renderer: profile.webgl_renderer.clone(),
+            hardware_concurrency: profile.hardware_concurrency,
+            device_memory: profile.device_memory,
+            timezone: profile.timezone.clone(),
+            language: profile.language.clone(),
+            languages: navigator_languages(&profile.language, &profile.languages),
+            do_not_track: profile.do_not_track,
+            canvas_noise_level: profile.canvas_noise_level.clone(),
+            inner_width: profile.inner_width,
+            inner_height: profile.inner_height,
+            custom_headers: profile.custom_headers.clone(),
+            webrtc_mode: profile.webrtc_mode.clone(),
+            default_url: profile.default_url.clone(),
+            proxy_enabled: profile.proxy_enabled,
+            proxy_type: profile.proxy_type.clone(),
+            proxy_host: profile.proxy_host.clone(),
+            proxy_port: profile.proxy_port,
+            proxy_username: profile.proxy_username.clone(),
+            proxy_password: profile.proxy_password.clone(),
+            webgl_noise: true,
+        }
+    }
+}
+
+/// Request headers asking servers and caches for a fresh response, added
+/// for profiles with `disable_cache`
+const NO_CACHE_HEADERS: [(&str, &str); 2] = [("Cache-Control", "no-cache"), ("Pragma", "no-cache")];
+
+/// The fingerprint a profile is launched with: its stored fields, with
+/// app-wide settings filled in where the profile doesn't choose
+pub fn launch_fingerprint(db: &Database, profile: &Profile) -> Fingerprint {
+    let mut fingerprint = Fingerprint::from(profile);
+    if fingerprint.webrtc_mode.is_none() {
+        fingerprint.webrtc_mode = db
+            .get_setting(DEFAULT_WEBRTC_MODE_SETTING)
+            .ok()
+            .flatten()
+            .filter(|mode| WEBRTC_MODES.contains(&mode.as_str()));
+    }
+    fingerprint.webgl_noise = db.get_setting_or(WEBGL_NOISE_SETTING, true);
+    if profile.disable_cache {
+        // Headers the profile sets itself win
+        for (name, value) in NO_CACHE_HEADERS {
+            let pinned = fingerprint.custom_headers.keys().any(|key| key.eq_ignore_ascii_case(name));
+            if !pinned {
+                fingerprint.custom_headers.insert(name.to_string(), value.to_string());
+            }
+        }
+    }
+    fingerprint
+}
+
+/// Screen size offered by the generator
+#[derive(Debug, Clone, Serialize)]
+pub struct ScreenResolution {
+    pub width: i32,
+    pub height: i32,
+}
+
+/// WebGL vendor/renderer pair offered by the generator
+#[derive(Debug, Clone, Serialize)]
+pub struct WebglConfig {
+    pub vendor: String,
+    pub renderer: String,
+}
+
+/// Every value the generator can pick from, for building UI choices
+#[derive(Debug, Clone, Serialize)]
+pub struct FingerprintOptions {
+    /// `navigator.platform` values; each is accepted by `generate_for_platform`
+    pub platforms: Vec<String>,
+    pub timezones: Vec<String>,
+    pub languages: Vec<String>,
+    pub resolutions: Vec<ScreenResolution>,
+    pub webgl_configs: Vec<WebglConfig>,
+    pub hardware_concurrency: Vec<i32>,
+    pub device_memory: Vec<i32>,
+    pub canvas_noise_levels: Vec<String>,
+}
+
+/// Read the generator's attribute pools
+pub fn fingerprint_options() -> FingerprintOptions {
+    let mut platforms: Vec<String> = Vec::new();
+    for (platform, _) in USER_AGENTS {
+        if !platforms.iter().any(|p| p == platform) {
+            platforms.push(platform.to_string());
+        }
+    }
+
+    FingerprintOptions {
+        platforms,
+        timezones: known_timezones().map(str::to_string).collect(),
+        languages: LANGUAGES.iter().map(|l| l.to_string()).collect(),
+        resolutions: SCREEN_RESOLUTIONS
+            .iter()
+            .map(|&(width, height)| ScreenResolution { width, height })
+            .collect(),
+        webgl_configs: WEBGL_CONFIGS
+            .iter()
+            .map(|(vendor, renderer)| WebglConfig {
+                vendor: vendor.to_string(),
+                renderer: renderer.to_string(),
+            })
+            .collect(),
+        hardware_concurrency: HARDWARE_CONCURRENCY.to_vec(),
+        device_memory: DEVICE_MEMORY.to_vec(),
+        canvas_noise_levels: CANVAS_NOISE_LEVELS.iter().map(|l| l.to_string()).collect(),
+    }
+}
+
+/// Whether `generate_for_platform` targets `platform` rather than falling back
+/// to a random one: a friendly name or a `navigator.platform` value
+pub fn is_known_platform(platform: &str) -> bool {
+    let platform = platform.trim().to_lowercase();
+    matches!(platform.as_str(), "windows" | "macos" | "mac" | "linux")
+        || USER_AGENTS.iter().any(|(p, _)| p.to_lowercase() == platform)
+}
+
+/// Fingerprint generator with configurable options
+pub struct FingerprintGenerator {
+    rng: StdRng,
+    spread_timezones: bool,
+}
+
+impl FingerprintGenerator {
+    pub fn new() -> Self {
+        FingerprintGenerator {
+            rng: StdRng::from_entropy(),
+            spread_timezones: false,
+        }
+    }
+
+    /// Generator configured by the app settings (`SPREAD_TIMEZONES_SETTING`),
+    /// taking the session's next seed when `GENERATION_SEED_SETTING` is set
+    pub fn from_settings(db: &Database, seed: &GenerationSeed) -> Self {
+        Self::configured(db, seed.next(db, true))
+    }
+
+    /// Like `from_settings`, but leaves the seed sequence where it is
+    pub fn for_preview(db: &Database, seed: &GenerationSeed) -> Self {
+        Self::configured(db, seed.next(db, false))
+    }
+
+    fn configured(db: &Database, seed: Option<u64>) -> Self {
+        FingerprintGenerator {
+            rng: seed.map_or_else(StdRng::from_entropy, StdRng::seed_from_u64),
+            spread_timezones: db.get_setting_or(SPREAD_TIMEZONES_SETTING, false),
+        }
+    }
+
+    /// Random generator timezone, spread within its country when enabled
+    fn pick_timezone(&mut self) -> &'static str {
+        let timezone = TIMEZONES[self.rng.gen_range(0..TIMEZONES.len())];
+        if self.spread_timezones {
+            self.spread_timezone(timezone)
+        } else {
+            timezone
+        }
+    }
+
+    /// A zone in the same country as `timezone`, drawn by population.
+    /// Timezones outside the generator's list are returned unchanged.
+    pub fn spread_timezone<'a>(&mut self, timezone: &'a str) -> &'a str {
+        let Some(country) = timezone_country(timezone) else {
+            return timezone;
+        };
+        let zones = country_timezones(country);
+        let total: u32 = zones.iter().map(|(_, weight)| weight).sum();
+        let mut pick = self.rng.gen_range(0..total);
+        for (zone, weight) in zones {
+            if pick < weight {
+                return zone;
+            }
+            pick -= weight;
+        }
+        timezone
+    }
+
+    /// `count` zones in `country` in proportion to population, shuffled.
+    /// Each slot goes to the zone furthest below its share (Sainte-Laguë), so
+    /// small fleets still cover the largest zones instead of clustering by
+    /// chance. Empty for a country with no generator timezones.
+    pub fn spread_timezones(&mut self, country: &str, count: usize) -> Vec<&'static str> {
+        let zones = country_timezones(country);
+        let mut assigned = vec![0u32; zones.len()];
+        let mut spread = Vec::with_capacity(count);
+        if zones.is_empty() {
+            return spread;
+        }
+        for _ in 0..count {
+            let quotient = |i: usize| zones[i].1 as f64 / (2 * assigned[i] + 1) as f64;
+            let next = (0..zones.len())
+                .max_by(|&a, &b| quotient(a).total_cmp(&quotient(b)).then(b.cmp(&a)))
+                .unwrap_or(0);
+            assigned[next] += 1;
+            spread.push(zones[next].0);
+        }
+        spread.shuffle(&mut self.rng);
+        spread
+    }
+
+    /// Generate a completely random fingerprint
+    pub fn generate(&mut self) -> Fingerprint {
+        let (platform, user_agent) = USER_AGENTS[self.rng.gen_range(0..USER_AGENTS.len())];
+        let resolutions = resolutions_for_platform(platform);
+        let (width, height) = resolutions[self.rng.gen_range(0..resolutions.len())];
+        let (vendor, renderer) = WEBGL_CONFIGS[self.rng.gen_range(0..WEBGL_CONFIGS.len())];
+        let hardware_concurrency = HARDWARE_CONCURRENCY[self.rng.gen_range(0..HARDWARE_CONCURRENCY.len())];
+        let device_memory = DEVICE_MEMORY[self.rng.gen_range(0..DEVICE_MEMORY.len())];
+        let timezone = self.pick_timezone();
+        let language = LANGUAGES[self.rng.gen_range(0..LANGUAGES.len())];
+        let languages = self.language_list(language);
+
+        Fingerprint {
+            user_agent: user_agent.to_string(),
+            platform: platform.to_string(),
+            screen_width: width,
+            screen_height: height,
+            webgl_vendor: vendor.to_string(),
+            webgl_renderer: renderer.to_string(),
+            hardware_concurrency,
+            device_memory,
+            timezone: timezone.to_string(),
+            language: language.to_string(),
+            languages,
+            do_not_track: None,
+            canvas_noise_level: DEFAULT_CANVAS_NOISE_LEVEL.to_string(),
+            inner_width: None,
+            inner_height: None,
+            custom_headers: BTreeMap::new(),
+            webrtc_mode: None,
+            default_url: "https://www.google.com".to_string(),
+            proxy_enabled: false,
+            proxy_type: "http".to_string(),
+            proxy_host: String::new(),
+            proxy_port: 0,
+            proxy_username: None,
+            proxy_password: None,
+            webgl_noise: true,
+        }
+    }
+
+    /// Realistic `navigator.languages` for a primary language: region then base
+    /// language, and for non-English locales often an English fallback
+    fn language_list(&mut self, primary: &str) -> Vec<String> {
+        let mut list = navigator_languages(primary, &[]);
+        if !primary.starts_with("en") && self.rng.gen_bool(0.5) {
+            list.push("en-US".to_string());
+            list.push("en".to_string());
+        }
+        list
+    }
+
+    /// Generate a fingerprint for a specific platform
+    pub fn generate_for_platform(&mut self, target_platform: &str) -> Fingerprint {
+        // Map friendly names to internal platform identifiers
+        let search_term = match target_platform.to_lowercase().as_str() {
+            "windows" => "win32",
+            "macos" | "mac" => "macintel",
+            "linux" => "linux",
+            _ => target_platform,
+        };
+
+        let platform_agents: Vec<_> = USER_AGENTS
+            .iter()
+            .filter(|(p, _)| p.to_lowercase().contains(&search_term.to_lowercase()))
+            .collect();
+
+        let (platform, user_agent) = if platform_agents.is_empty() {
+            USER_AGENTS[self.rng.gen_range(0..USER_AGENTS.len())]
+        } else {
+            *platform_agents[self.rng.gen_range(0..platform_agents.len())]
+        };
+
+        let resolutions = resolutions_for_platform(platform);
+        let (width, height) = resolutions[self.rng.gen_range(0..resolutions.len())];
+        let (vendor, renderer) = WEBGL_CONFIGS[self.rng.gen_range(0..WEBGL_CONFIGS.len())];
+        let hardware_concurrency = HARDWARE_CONCURRENCY[self.rng.gen_range(0..HARDWARE_CONCURRENCY.len())];
+        let device_memory = DEVICE_MEMORY[self.rng.gen_range(0..DEVICE_MEMORY.len())];
+        let timezone = self.pick_timezone();
+        let language = LANGUAGES[self.rng.gen_range(0..LANGUAGES.len())];
+        let languages = self.language_list(language);
+
+        Fingerprint {
+            user_agent: user_agent.to_string(),
+            platform: platform.to_string(),
+            screen_width: width,
+            screen_height: height,
+            webgl_vendor: vendor.to_string(),
+            webgl_renderer: renderer.to_string(),
+            hardware_concurrency,
+            device_memory,
+            timezone: timezone.to_string(),
+            language: language.to_string(),
+            languages,
+            do_not_track: None,
+            canvas_noise_level: DEFAULT_CANVAS_NOISE_LEVEL.to_string(),
+            inner_width: None,
+            inner_height: None,
+            custom_headers: BTreeMap::new(),
+            webrtc_mode: None,
+            default_url: "https://www.google.com".to_string(),
+            proxy_enabled: false,
+            proxy_type: "http".to_string(),
+            proxy_host: String::new(),
+            proxy_port: 0,
+            proxy_username: None,
+            proxy_password: None,
+            webgl_noise: true,
+        }
+    }
+
+    /// Generate a fingerprint within a template's constraints. `platform` is
+    /// used only when the template doesn't pin one.
+    pub fn generate_from_template(
+        &mut self,
+        template: &FingerprintTemplate,
+        platform: Option<&str>,
+    ) -> Fingerprint {
+        let mut fingerprint = match template.platform.as_deref().or(platform) {
+            Some(platform) => self.generate_for_platform(platform),
+            None => self.generate(),
+        };
+
+        if let Some(browser) = template.browser.as_deref() {
+            let agents: Vec<&str> = USER_AGENTS
+                .iter()
+                .filter(|(p, ua)| *p == fingerprint.platform && ua_browser_name(ua) == browser)
+                .map(|(_, ua)| *ua)
+                .collect();
+            if let Some(ua) = agents.choose(&mut self.rng) {
+                fingerprint.user_agent = ua.to_string();
+            }
+        }
+        if let Some(timezone) = template.timezones.choose(&mut self.rng) {
+            fingerprint.timezone = timezone.clone();
+        }
+        if let Some(language) = template.languages.choose(&mut self.rng) {
+            fingerprint.language = language.clone();
+            fingerprint.languages = self.language_list(language);
+        }
+
+        // Prefer sizes plausible for the platform; fall back to any size the template allows
+        let allowed = |candidates: &'static [(i32, i32)]| -> Vec<&'static (i32, i32)> {
+            candidates
+                .iter()
+                .filter(|(w, h)| template.allows_resolution(*w, *h))
+                .collect()
+        };
+        let mut resolutions = allowed(resolutions_for_platform(&fingerprint.platform));
+        if resolutions.is_empty() {
+            resolutions = allowed(SCREEN_RESOLUTIONS);
+        }
+        if let Some((width, height)) = resolutions.choose(&mut self.rng) {
+            fingerprint.screen_width = *width;
+            fingerprint.screen_height = *height;
+        }
+
+        fingerprint
+    }
+}
+
+impl Default for FingerprintGenerator {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Generate a persistent noise seed from profile ID
+fn generate_persistent_seed(profile_id: &str) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    profile_id.hash(&mut hasher);
+    hasher.finish()
+}
+
+/// Resolve the Do Not Track signal, deriving it from the seed when not pinned.
+/// Roughly one in five unpinned profiles opts in, similar to real-world usage.
+fn resolve_do_not_track(pinned: Option<bool>, persistent_seed: u64) -> bool {
+    pinned.unwrap_or((persistent_seed >> 30) % 100 < 20)
+}
+
+/// Pixel modulus (roughly one in N pixels noised) and amplitude for a canvas noise level.
+/// Unknown levels fall back to medium, the historical ±1 on ~1% of pixels.
+fn canvas_noise_params(level: &str) -> (u32, u32) {
+    match level {
+        "low" => (211, 1),
+        "high" => (31, 2),
+        _ => (97, 1),
+    }
+}
+
+/// Spoofed `window.innerWidth`/`innerHeight`. Unless pinned, these subtract a
+/// seed-derived scrollbar width and browser-chrome height from the screen size,
+/// so the viewport isn't a fixed formula of `screen_width` across profiles.
+fn viewport_size(fingerprint: &Fingerprint, persistent_seed: u64) -> (i32, i32) {
+    let viewport_seed = persistent_seed >> 44;
+    let scrollbar = (viewport_seed % 18) as i32;
+    let chrome_height = 70 + ((viewport_seed >> 5) % 70) as i32;
+    let taskbar = 40;
+
+    let width = fingerprint
+        .inner_width
+        .unwrap_or(fingerprint.screen_width - scrollbar);
+    let height = fingerprint
+        .inner_height
+        .unwrap_or(fingerprint.screen_height - taskbar - chrome_height);
+    (width.max(320), height.max(240))
+}
+
+/// The viewport the spoof script reports for a profile
+pub fn profile_viewport(fingerprint: &Fingerprint, profile_id: &str) -> (i32, i32) {
+    viewport_size(fingerprint, generate_persistent_seed(profile_id))
+}
+
+/// WebGL `MAX_TEXTURE_SIZE` and `MAX_VERTEX_ATTRIBS` for a renderer.
+/// Texture limits vary by GPU family and driver, so families that report
+/// more than one value pick between them by seed. Chrome caps vertex
+/// attributes at 16 on every GPU, so that stays constant.
+fn webgl_limits(webgl_renderer: &str, persistent_seed: u64) -> (u32, u32) {
+    let candidates: &[u32] = if webgl_renderer.starts_with("ANGLE") {
+        // D3D11 feature level 11 is fixed at 16384 regardless of vendor
+        &[16384]
+    } else if webgl_renderer.contains("NVIDIA") {
+        &[16384, 32768, 32768]
+    } else if webgl_renderer.contains("AMD") {
+        &[16384, 16384, 32768]
+    } else {
+        // Intel and Apple silicon
+        &[16384]
+    };
+    let max_texture_size = candidates[((persistent_seed >> 50) % candidates.len() as u64) as usize];
+    (max_texture_size, 16)
+}
+
+/// WebGL2 `getParameter` enums that WebGL1 doesn't have, in the order of
+/// the limits in `webgl2_limits`
+const WEBGL2_LIMIT_PARAMS: [u32; 27] = [
+    0x8073, // MAX_3D_TEXTURE_SIZE
+    0x88FF, // MAX_ARRAY_TEXTURE_LAYERS
+    0x8CDF, // MAX_COLOR_ATTACHMENTS
+    0x8824, // MAX_DRAW_BUFFERS
+    0x8D57, // MAX_SAMPLES
+    0x8A2F, // MAX_UNIFORM_BUFFER_BINDINGS
+    0x8A30, // MAX_UNIFORM_BLOCK_SIZE
+    0x8A2B, // MAX_VERTEX_UNIFORM_BLOCKS
+    0x8A2D, // MAX_FRAGMENT_UNIFORM_BLOCKS
+    0x8A2E, // MAX_COMBINED_UNIFORM_BLOCKS
+    0x8C8B, // MAX_TRANSFORM_FEEDBACK_SEPARATE_ATTRIBS
+    0x8C8A, // MAX_TRANSFORM_FEEDBACK_INTERLEAVED_COMPONENTS
+    0x8B4B, // MAX_VARYING_COMPONENTS
+    0x9122, // MAX_VERTEX_OUTPUT_COMPONENTS
+    0x9125, // MAX_FRAGMENT_INPUT_COMPONENTS
+    0x8D6B, // MAX_ELEMENT_INDEX
+    0x8B4A, // MAX_VERTEX_UNIFORM_COMPONENTS
+    0x8B49, // MAX_FRAGMENT_UNIFORM_COMPONENTS
+    0x8A31, // MAX_COMBINED_VERTEX_UNIFORM_COMPONENTS
+    0x8A33, // MAX_COMBINED_FRAGMENT_UNIFORM_COMPONENTS
+    0x8A34, // UNIFORM_BUFFER_OFFSET_ALIGNMENT
+    0x80E8, // MAX_ELEMENTS_VERTICES
+    0x80E9, // MAX_ELEMENTS_INDICES
+    0x8905, // MAX_PROGRAM_TEXEL_OFFSET
+    0x8904, // MIN_PROGRAM_TEXEL_OFFSET
+    0x84FD, // MAX_TEXTURE_LOD_BIAS
+    0x9111, // MAX_SERVER_WAIT_TIMEOUT
+];
+
+/// WebGL2-only limits for a renderer, keyed by GL enum. ANGLE on D3D11 and
+/// Apple GPUs report fixed values; native drivers vary, so the sample count
+/// is picked by seed there. 3D textures never exceed `max_texture_size`.
+/// Combined uniform components are the uniform blocks' worth plus the
+/// default block's, as drivers compute them.
+fn webgl2_limits(webgl_renderer: &str, max_texture_size: u32, persistent_seed: u64) -> BTreeMap<u32, i64> {
+    let pick = |candidates: &[i64]| candidates[((persistent_seed >> 54) % candidates.len() as u64) as usize];
+    let limits: [i64; 27] = if webgl_renderer.starts_with("ANGLE") {
+        [
+            2048, 2048, 8, 8, 8, 24, 65536, 12, 12, 24, 4, 64, 120, 120, 120, 4294967294,
+            16384, 4096, 212992, 200704, 256, 2147483647, 2147483647, 7, -8, 15, 0,
+        ]
+    } else if webgl_renderer.starts_with("Apple") {
+        [
+            2048, 2048, 8, 8, 4, 24, 16384, 12, 12, 24, 4, 64, 60, 64, 60, 4294967295,
+            4096, 4096, 53248, 53248, 256, 2147483647, 2147483647, 7, -8, 16, 0,
+        ]
+    } else if webgl_renderer.contains("NVIDIA") {
+        let samples = pick(&[32, 32, 16]);
+        [
+            16384, 2048, 8, 8, samples, 72, 65536, 14, 14, 70, 4, 128, 124, 128, 128, 4294967295,
+            4096, 4096, 233472, 233472, 256, 1048576, 1048576, 7, -8, 15, 0,
+        ]
+    } else if webgl_renderer.contains("AMD") {
+        let samples = pick(&[8, 8, 16]);
+        [
+            8192, 2048, 8, 8, samples, 72, 65536, 14, 14, 70, 4, 128, 124, 128, 128, 4294967295,
+            16384, 16384, 245760, 245760, 256, 3000, 3000, 7, -8, 15, 0,
+        ]
+    } else {
+        // Intel
+        let samples = pick(&[16, 8]);
+        [
+            2048, 2048, 8, 8, samples, 84, 65536, 14, 14, 70, 4, 128, 124, 128, 128, 4294967295,
+            16384, 16384, 245760, 245760, 32, 3000, 3000, 7, -8, 15, 0,
+        ]
+    };
+    let mut limits: BTreeMap<u32, i64> = WEBGL2_LIMIT_PARAMS.into_iter().zip(limits).collect();
+    limits.entry(0x8073).and_modify(|size| *size = (*size).min(max_texture_size as i64));
+    limits
+}
+
+/// Values `navigator.deviceMemory` may report under the Device Memory spec
+const SPEC_DEVICE_MEMORY: &[f64] = &[0.25, 0.5, 1.0, 2.0, 4.0, 8.0];
+
+/// `navigator.deviceMemory` for a device with `device_memory` GB: the largest
+/// spec value not above it, so 16 and 32 GB machines report 8 like real browsers
+fn exposed_device_memory(device_memory: i32) -> f64 {
+    SPEC_DEVICE_MEMORY
+        .iter()
+        .copied()
+        .rev()
+        .find(|&gb| gb <= device_memory as f64)
+        .unwrap_or(SPEC_DEVICE_MEMORY[0])
+}
+
+/// Chrome `performance.memory` values (limit, total, used) in bytes.
+/// The limit follows Chrome's heap caps for the device class; total/used are seed-stable.
+fn js_heap_sizes(device_memory: i32, persistent_seed: u64) -> (u64, u64, u64) {
+    let limit: u64 = match device_memory {
+        m if m >= 4 => 4_294_705_152,
+        m if m >= 2 => 2_172_649_472,
+        _ => 1_136_000_000,
+    };
+    let memory_seed = persistent_seed >> 40;
+    let total = (12 + memory_seed % 28) * 1_000_000 + (memory_seed % 997) * 1_024;
+    let used = total * (70 + (memory_seed >> 8) % 25) / 100;
+    (limit, total, used)
+}
+
+/// Screen resolutions plausible for a `navigator.platform` value
+fn resolutions_for_platform(platform: &str) -> &'static [(i32, i32)] {
+    if platform.contains("Win") {
+        WINDOWS_RESOLUTIONS
+    } else if platform.contains("Mac") {
+        MAC_RESOLUTIONS
+    } else {
+        LINUX_RESOLUTIONS
+    }
+}
+
+/// Get fonts list for platform
+fn get_fonts_for_platform(platform: &str) -> Vec<&'static str> {
+    if platform.contains("Win") {
         WINDOWS_FONTS.to_vec()
     } else if platform.contains("Mac") {
         MAC_FONTS.to_vec()
@@ -273,27 +1627,206 @@ fn get_fonts_for_platform(platform: &str) -> Vec<&'static str> {
     }
 }
 
-/// Get timezone offset
-fn get_timezone_offset(timezone: &str) -> i32 {
-    TIMEZONES.iter()
-        .find(|(tz, _)| *tz == timezone)
-        .map(|(_, offset)| *offset)
+/// Instants (Unix seconds, 1970 to 2100) the spoof script gets precomputed offsets for
+const TZ_TRANSITION_RANGE: (i64, i64) = (0, 4_102_444_800);
+
+/// Offset (minutes west of UTC) of `tz` at a Unix timestamp
+fn tz_offset_at(tz: Tz, unix_secs: i64) -> i32 {
+    DateTime::from_timestamp(unix_secs, 0)
+        .map(|utc| -tz.offset_from_utc_datetime(&utc.naive_utc()).fix().local_minus_utc() / 60)
         .unwrap_or(0)
 }
 
+/// Offset (minutes west of UTC, as `getTimezoneOffset` reports it) in effect
+/// at a Unix timestamp, from the tz database. Unknown zones are treated as UTC.
+pub fn timezone_offset_at(timezone: &str, unix_secs: i64) -> i32 {
+    timezone.parse::<Tz>().map(|tz| tz_offset_at(tz, unix_secs)).unwrap_or(0)
+}
+
+/// Offset at the start of `TZ_TRANSITION_RANGE`, then every change within it
+/// as (Unix seconds, new offset)
+fn timezone_transitions(timezone: &str) -> (i32, Vec<(i64, i32)>) {
+    let (start, end) = TZ_TRANSITION_RANGE;
+    let Ok(tz) = timezone.parse::<Tz>() else {
+        return (0, Vec::new());
+    };
+    let initial = tz_offset_at(tz, start);
+    let mut transitions = Vec::new();
+    let (mut last_time, mut last_offset) = (start, initial);
+    // Offsets hold for months at a time: step a day, then bisect each change to the second
+    while last_time < end {
+        let next = (last_time + 86400).min(end);
+        if tz_offset_at(tz, next) == last_offset {
+            last_time = next;
+            continue;
+        }
+        let (mut low, mut high) = (last_time, next);
+        while high - low > 1 {
+            let mid = low + (high - low) / 2;
+            if tz_offset_at(tz, mid) == last_offset {
+                low = mid;
+            } else {
+                high = mid;
+            }
+        }
+        last_offset = tz_offset_at(tz, high);
+        last_time = high;
+        transitions.push((high, last_offset));
+    }
+    (initial, transitions)
+}
+
+/// Mixed into the persistent seed for `property_overrides`, so structure
+/// choices don't correlate with the noise seeds
+const STRUCTURE_SEED_SALT: u64 = 0x5f3c_9a1d_2b7e_4c60;
+
+/// `Object.defineProperty` overrides with constant getters on `target`, one
+/// per (property, returned JS expression). Order, descriptor key order, quote
+/// style and getter spacing come from `rng`, so profiles don't all share one
+/// recognisable footprint; none of it changes what the page reads.
+fn property_overrides(target: &str, properties: &[(&str, String)], rng: &mut StdRng) -> String {
+    let mut order: Vec<&(&str, String)> = properties.iter().collect();
+    order.shuffle(rng);
+    let function = if rng.gen_bool(0.5) { "function()" } else { "function ()" };
+    let quote = if rng.gen_bool(0.5) { '\'' } else { '"' };
+
+    let mut out = String::new();
+    for (name, value) in order {
+        let getter = format!("get: {} {{ return {}; }}", function, value);
+        let (first, second) = if rng.gen_bool(0.5) {
+            (getter.as_str(), "configurable: true")
+        } else {
+            ("configurable: true", getter.as_str())
+        };
+        out.push_str(&format!(
+            "    Object.defineProperty({}, {q}{}{q}, {{\n        {},\n        {}\n    }});\n    \n",
+            target,
+            name,
+            first,
+            second,
+            q = quote
+        ));
+    }
+    out
+}
+
+/// Escape `value` for use inside a single-quoted JS string literal
+fn js_single_quoted(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for c in value.chars() {
+        match c {
+            '\\' => escaped.push_str("\\\\"),
+            '\'' => escaped.push_str("\\'"),
+            '\n' => escaped.push_str("\\n"),
+            '\r' => escaped.push_str("\\r"),
+            '\u{2028}' => escaped.push_str("\\u2028"),
+            '\u{2029}' => escaped.push_str("\\u2029"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
 /// Generate the JavaScript injection script for fingerprint spoofing
 /// Now takes profile_id for persistent noise
 pub fn generate_spoof_script(fingerprint: &Fingerprint, profile_id: &str) -> String {
     let persistent_seed = generate_persistent_seed(profile_id);
     let canvas_seed = (persistent_seed % 1000) as i32;
+    let (canvas_noise_modulus, canvas_noise_amplitude) =
+        canvas_noise_params(&fingerprint.canvas_noise_level);
+    let webrtc_mode = fingerprint
+        .webrtc_mode
+        .as_deref()
+        .filter(|mode| WEBRTC_MODES.contains(mode))
+        .unwrap_or(DEFAULT_WEBRTC_MODE);
     let audio_seed = ((persistent_seed >> 10) % 1000) as i32;
     let font_seed = ((persistent_seed >> 20) % 1000) as i32;
+    let webgl_seed = ((persistent_seed >> 34) % 1000) as i32;
     
     let fonts = get_fonts_for_platform(&fingerprint.platform);
     let fonts_json: Vec<String> = fonts.iter().map(|f| format!("\"{}\"", f)).collect();
     let fonts_array = fonts_json.join(", ");
     
-    let tz_offset = get_timezone_offset(&fingerprint.timezone);
+    let (tz_initial_offset, tz_transitions) = timezone_transitions(&fingerprint.timezone);
+    let tz_transitions: Vec<(i64, i32)> =
+        tz_transitions.into_iter().map(|(time, offset)| (time * 1000, offset)).collect();
+    let tz_transitions_json = serde_json::to_string(&tz_transitions).unwrap_or_else(|_| "[]".to_string());
+
+    let (inner_width, inner_height) = viewport_size(fingerprint, persistent_seed);
+
+    let (webgl_max_texture_size, webgl_max_vertex_attribs) =
+        webgl_limits(&fingerprint.webgl_renderer, persistent_seed);
+    let webgl2_parameters = webgl2_limits(&fingerprint.webgl_renderer, webgl_max_texture_size, persistent_seed);
+    // WebGL1 reports the default block's uniform components as vec4s
+    let webgl_max_vertex_uniform_vectors = webgl2_parameters[&0x8B4A] / 4;
+    let webgl_max_fragment_uniform_vectors = webgl2_parameters[&0x8B49] / 4;
+    let webgl2_parameters_json = serde_json::to_string(&webgl2_parameters).unwrap_or_else(|_| "{}".to_string());
+
+    let (js_heap_limit, js_heap_total, js_heap_used) =
+        js_heap_sizes(fingerprint.device_memory, persistent_seed);
+
+    let custom_headers_json =
+        serde_json::to_string(&fingerprint.custom_headers).unwrap_or_else(|_| "{}".to_string());
+    let languages_json =
+        serde_json::to_string(&navigator_languages(&fingerprint.language, &fingerprint.languages))
+            .unwrap_or_else(|_| "[]".to_string());
+
+    let do_not_track = resolve_do_not_track(fingerprint.do_not_track, persistent_seed);
+    let dnt_value = if do_not_track { "'1'" } else { "null" };
+
+    let ua_family = ua_browser_name(&fingerprint.user_agent);
+    let ua_data_json = serde_json::to_string(&user_agent_data(fingerprint)).unwrap_or_else(|_| "null".to_string());
+    let oscpu_json = serde_json::to_string(&firefox_oscpu(&fingerprint.user_agent, &fingerprint.platform))
+        .unwrap_or_else(|_| "null".to_string());
+    let keyboard_layout_json = serde_json::to_string(&keyboard_layout(&fingerprint.language, &fingerprint.platform))
+        .unwrap_or_else(|_| "{}".to_string());
+
+    // Overrides that don't depend on each other; their order and formatting
+    // vary by profile
+    let mut structure_rng = StdRng::seed_from_u64(persistent_seed ^ STRUCTURE_SEED_SALT);
+    let user_agent = format!("'{}'", js_single_quoted(&fingerprint.user_agent));
+    let mut navigator_properties = vec![
+        ("userAgent", user_agent.clone()),
+        ("platform", format!("'{}'", js_single_quoted(&fingerprint.platform))),
+        ("hardwareConcurrency", fingerprint.hardware_concurrency.to_string()),
+        ("deviceMemory", exposed_device_memory(fingerprint.device_memory).to_string()),
+        ("language", format!("'{}'", js_single_quoted(&fingerprint.language))),
+        ("languages", "NAVIGATOR_LANGUAGES".to_string()),
+        ("webdriver", "false".to_string()),
+        ("appVersion", format!("{}.substring(8)", user_agent)),
+        ("vendor", format!("'{}'", navigator_vendor(&fingerprint.user_agent).0)),
+        ("productSub", format!("'{}'", navigator_vendor(&fingerprint.user_agent).1)),
+        ("maxTouchPoints", "0".to_string()),
+        ("doNotTrack", "DO_NOT_TRACK".to_string()),
+    ];
+    // Of the browsers a UA can claim, only Firefox ships GPC
+    if ua_browser_name(&fingerprint.user_agent) == "firefox" {
+        navigator_properties.push(("globalPrivacyControl", "DO_NOT_TRACK === '1'".to_string()));
+    }
+    let navigator_overrides = property_overrides("navigator", &navigator_properties, &mut structure_rng);
+    let screen_overrides = property_overrides(
+        "screen",
+        &[
+            ("width", "SPOOF_WIDTH".to_string()),
+            ("height", "SPOOF_HEIGHT".to_string()),
+            ("availWidth", "SPOOF_WIDTH".to_string()),
+            ("availHeight", "SPOOF_HEIGHT - 40".to_string()),
+            ("colorDepth", "24".to_string()),
+            ("pixelDepth", "24".to_string()),
+        ],
+        &mut structure_rng,
+    );
+    let window_overrides = property_overrides(
+        "window",
+        &[
+            ("innerWidth", "SPOOF_INNER_WIDTH".to_string()),
+            ("innerHeight", "SPOOF_INNER_HEIGHT".to_string()),
+            ("outerWidth", "SPOOF_WIDTH".to_string()),
+            ("outerHeight", "SPOOF_HEIGHT".to_string()),
+            ("devicePixelRatio", "1".to_string()),
+        ],
+        &mut structure_rng,
+    );
 
     format!(r#"
 (function() {{
@@ -311,50 +1844,75 @@ pub fn generate_spoof_script(fingerprint: &Fingerprint, profile_id: &str) -> Str
     // NAVIGATOR SPOOFING
     // ============================================
     
-    Object.defineProperty(navigator, 'userAgent', {{
-        get: function() {{ return '{user_agent}'; }},
-        configurable: true
-    }});
-    
-    Object.defineProperty(navigator, 'platform', {{
-        get: function() {{ return '{platform}'; }},
-        configurable: true
-    }});
-    
-    Object.defineProperty(navigator, 'hardwareConcurrency', {{
-        get: function() {{ return {hardware_concurrency}; }},
-        configurable: true
-    }});
-    
-    Object.defineProperty(navigator, 'deviceMemory', {{
-        get: function() {{ return {device_memory}; }},
-        configurable: true
-    }});
-    
-    Object.defineProperty(navigator, 'language', {{
-        get: function() {{ return '{language}'; }},
-        configurable: true
-    }});
-    
-    Object.defineProperty(navigator, 'languages', {{
-        get: function() {{ return ['{language}', '{language}'.split('-')[0]]; }},
-        configurable: true
-    }});
-    
-    Object.defineProperty(navigator, 'appVersion', {{
-        get: function() {{ return '{user_agent}'.substring(8); }},
-        configurable: true
-    }});
-    
-    Object.defineProperty(navigator, 'vendor', {{
-        get: function() {{ return 'Google Inc.'; }},
-        configurable: true
-    }});
+    // Same frozen array on every read, as in real browsers
+    const NAVIGATOR_LANGUAGES = Object.freeze({languages_json});
+    
+    // Browser family the UA claims: 'chrome', 'firefox' or 'safari'
+    const UA_FAMILY = '{ua_family}';
+    
+    // Privacy signals - GPC (Firefox only) follows DNT so the pair never contradicts itself
+    const DO_NOT_TRACK = {dnt_value};
+    
+    // Constant getters, in a per-profile order. webdriver is pinned so an
+    // automation-enabled runtime can't leak through.
+{navigator_overrides}
+    // Only Firefox exposes oscpu, and it names the OS, so it must follow the
+    // spoofed platform. buildID is Firefox-only too.
+    if (UA_FAMILY === 'firefox') {{
+        const OSCPU = {oscpu_json};
+        Object.defineProperty(navigator, 'oscpu', {{
+            get: function() {{ return OSCPU; }},
+            configurable: true
+        }});
+        Object.defineProperty(navigator, 'buildID', {{
+            get: function() {{ return '{firefox_build_id}'; }},
+            configurable: true
+        }});
+    }}
     
-    Object.defineProperty(navigator, 'maxTouchPoints', {{
-        get: function() {{ return 0; }},
-        configurable: true
-    }});
+    // Client hints follow the UA (see user_agent_data on the Rust side):
+    // Chromium UAs get matching brands and high-entropy values, and other
+    // browsers have no userAgentData at all
+    const UA_DATA = {ua_data_json};
+    if (UA_DATA === null) {{
+        if (typeof Navigator !== 'undefined') {{
+            delete Navigator.prototype.userAgentData;
+        }}
+    }} else {{
+        const UA_BRANDS = Object.freeze(UA_DATA.brands);
+        const lowEntropyValues = function() {{
+            return {{ brands: UA_BRANDS.slice(), mobile: UA_DATA.mobile, platform: UA_DATA.platform }};
+        }};
+        const uaDataMembers = {{
+            getHighEntropyValues: function(hints) {{
+                const values = lowEntropyValues();
+                Array.prototype.forEach.call(hints || [], function(hint) {{
+                    if (hint !== 'brands' && Object.prototype.hasOwnProperty.call(UA_DATA, hint)) {{
+                        values[hint] = JSON.parse(JSON.stringify(UA_DATA[hint]));
+                    }}
+                }});
+                return Promise.resolve(values);
+            }},
+            toJSON: function() {{
+                return lowEntropyValues();
+            }}
+        }};
+        // Patch the real NavigatorUAData where the webview has one, so the
+        // object keeps its native identity; otherwise supply one
+        const uaDataTarget = typeof NavigatorUAData !== 'undefined' ? NavigatorUAData.prototype : {{}};
+        Object.defineProperty(uaDataTarget, 'brands', {{ get: function() {{ return UA_BRANDS; }}, configurable: true }});
+        Object.defineProperty(uaDataTarget, 'mobile', {{ get: function() {{ return UA_DATA.mobile; }}, configurable: true }});
+        Object.defineProperty(uaDataTarget, 'platform', {{ get: function() {{ return UA_DATA.platform; }}, configurable: true }});
+        Object.keys(uaDataMembers).forEach(function(name) {{
+            Object.defineProperty(uaDataTarget, name, {{ value: uaDataMembers[name], writable: true, configurable: true }});
+        }});
+        if (typeof NavigatorUAData === 'undefined') {{
+            Object.defineProperty(navigator, 'userAgentData', {{
+                get: function() {{ return uaDataTarget; }},
+                configurable: true
+            }});
+        }}
+    }}
     
     // ============================================
     // SCREEN SPOOFING (with media query protection)
@@ -363,63 +1921,38 @@ pub fn generate_spoof_script(fingerprint: &Fingerprint, profile_id: &str) -> Str
     const SPOOF_WIDTH = {screen_width};
     const SPOOF_HEIGHT = {screen_height};
     
-    Object.defineProperty(screen, 'width', {{
-        get: function() {{ return SPOOF_WIDTH; }},
-        configurable: true
-    }});
-    
-    Object.defineProperty(screen, 'height', {{
-        get: function() {{ return SPOOF_HEIGHT; }},
-        configurable: true
-    }});
-    
-    Object.defineProperty(screen, 'availWidth', {{
-        get: function() {{ return SPOOF_WIDTH; }},
-        configurable: true
-    }});
-    
-    Object.defineProperty(screen, 'availHeight', {{
-        get: function() {{ return SPOOF_HEIGHT - 40; }},
-        configurable: true
-    }});
-    
-    Object.defineProperty(screen, 'colorDepth', {{
-        get: function() {{ return 24; }},
-        configurable: true
-    }});
-    
-    Object.defineProperty(screen, 'pixelDepth', {{
-        get: function() {{ return 24; }},
-        configurable: true
-    }});
-    
-    // Window dimensions to match screen
-    Object.defineProperty(window, 'innerWidth', {{
-        get: function() {{ return SPOOF_WIDTH; }},
-        configurable: true
-    }});
-    
-    Object.defineProperty(window, 'innerHeight', {{
-        get: function() {{ return SPOOF_HEIGHT - 140; }},
-        configurable: true
-    }});
-    
-    Object.defineProperty(window, 'outerWidth', {{
-        get: function() {{ return SPOOF_WIDTH; }},
-        configurable: true
-    }});
-    
-    Object.defineProperty(window, 'outerHeight', {{
-        get: function() {{ return SPOOF_HEIGHT; }},
-        configurable: true
-    }});
+{screen_overrides}    
+    // Orientation follows the spoofed screen shape, not the real display
+    const SPOOF_ORIENTATION = SPOOF_WIDTH >= SPOOF_HEIGHT ? 'landscape-primary' : 'portrait-primary';
+    if (typeof ScreenOrientation !== 'undefined') {{
+        Object.defineProperty(ScreenOrientation.prototype, 'type', {{
+            get: function() {{ return SPOOF_ORIENTATION; }},
+            configurable: true
+        }});
+        Object.defineProperty(ScreenOrientation.prototype, 'angle', {{
+            get: function() {{ return 0; }},
+            configurable: true
+        }});
+        // A real rotation would contradict the spoofed values, so change listeners never fire
+        Object.defineProperty(ScreenOrientation.prototype, 'onchange', {{
+            get: function() {{ return null; }},
+            set: function() {{}},
+            configurable: true
+        }});
+        const originalOrientationAddListener = EventTarget.prototype.addEventListener;
+        ScreenOrientation.prototype.addEventListener = function(type, listener, options) {{
+            if (type === 'change') {{
+                return;
+            }}
+            return originalOrientationAddListener.call(this, type, listener, options);
+        }};
+    }}
     
-    // Device pixel ratio
-    Object.defineProperty(window, 'devicePixelRatio', {{
-        get: function() {{ return 1; }},
-        configurable: true
-    }});
+    // Viewport inside the screen, offset per profile rather than by a fixed formula
+    const SPOOF_INNER_WIDTH = {inner_width};
+    const SPOOF_INNER_HEIGHT = {inner_height};
     
+{window_overrides}    
     // ============================================
     // MEDIA QUERY SPOOFING
     // ============================================
@@ -481,13 +2014,21 @@ pub fn generate_spoof_script(fingerprint: &Fingerprint, profile_id: &str) -> Str
             if (param === 37446) {{
                 return '{webgl_renderer}';
             }}
-            // MAX_TEXTURE_SIZE - randomize slightly
+            // MAX_TEXTURE_SIZE
             if (param === 3379) {{
-                return 16384;
+                return {webgl_max_texture_size};
             }}
             // MAX_VERTEX_ATTRIBS
             if (param === 34921) {{
-                return 16;
+                return {webgl_max_vertex_attribs};
+            }}
+            // MAX_VERTEX_UNIFORM_VECTORS
+            if (param === 36347) {{
+                return {webgl_max_vertex_uniform_vectors};
+            }}
+            // MAX_FRAGMENT_UNIFORM_VECTORS
+            if (param === 36349) {{
+                return {webgl_max_fragment_uniform_vectors};
             }}
             
             return Reflect.apply(target, thisArg, args);
@@ -497,11 +2038,62 @@ pub fn generate_spoof_script(fingerprint: &Fingerprint, profile_id: &str) -> Str
     const originalGetParameter = WebGLRenderingContext.prototype.getParameter;
     WebGLRenderingContext.prototype.getParameter = new Proxy(originalGetParameter, getParameterProxyHandler);
     
+    // WebGL2 adds limits WebGL1 can't query; they follow the renderer too
+    const WEBGL2_PARAMETERS = {webgl2_parameters_json};
+    const getParameter2ProxyHandler = {{
+        apply: function(target, thisArg, args) {{
+            if (Object.prototype.hasOwnProperty.call(WEBGL2_PARAMETERS, args[0])) {{
+                return WEBGL2_PARAMETERS[args[0]];
+            }}
+            return getParameterProxyHandler.apply(target, thisArg, args);
+        }}
+    }};
+    
     if (typeof WebGL2RenderingContext !== 'undefined') {{
         const originalGetParameter2 = WebGL2RenderingContext.prototype.getParameter;
-        WebGL2RenderingContext.prototype.getParameter = new Proxy(originalGetParameter2, getParameterProxyHandler);
+        WebGL2RenderingContext.prototype.getParameter = new Proxy(originalGetParameter2, getParameter2ProxyHandler);
     }}
     
+    // Detection scripts read UNMASKED_*_WEBGL off the extension object before
+    // calling getParameter, and some engines don't expose the extension at all.
+    // Always hand out one extension object per context so both steps line up
+    // with the spoofed values above.
+    const DEBUG_RENDERER_INFO = 'WEBGL_debug_renderer_info';
+    const debugRendererInfo = new WeakMap();
+    const getExtensionProxyHandler = {{
+        apply: function(target, thisArg, args) {{
+            // Extension names are matched case-insensitively
+            if (String(args[0]).toLowerCase() !== DEBUG_RENDERER_INFO.toLowerCase()) {{
+                return Reflect.apply(target, thisArg, args);
+            }}
+            let ext = debugRendererInfo.get(thisArg);
+            if (!ext) {{
+                ext = Reflect.apply(target, thisArg, args) || Object.freeze(Object.defineProperties({{}}, {{
+                    UNMASKED_VENDOR_WEBGL: {{ value: 37445, enumerable: true }},
+                    UNMASKED_RENDERER_WEBGL: {{ value: 37446, enumerable: true }},
+                    [Symbol.toStringTag]: {{ value: 'WebGLDebugRendererInfo' }}
+                }}));
+                debugRendererInfo.set(thisArg, ext);
+            }}
+            return ext;
+        }}
+    }};
+    const getSupportedExtensionsProxyHandler = {{
+        apply: function(target, thisArg, args) {{
+            const result = Reflect.apply(target, thisArg, args);
+            if (Array.isArray(result) && !result.includes(DEBUG_RENDERER_INFO)) {{
+                result.push(DEBUG_RENDERER_INFO);
+            }}
+            return result;
+        }}
+    }};
+    [window.WebGLRenderingContext, window.WebGL2RenderingContext].forEach(function(context) {{
+        if (!context) return;
+        context.prototype.getExtension = new Proxy(context.prototype.getExtension, getExtensionProxyHandler);
+        context.prototype.getSupportedExtensions =
+            new Proxy(context.prototype.getSupportedExtensions, getSupportedExtensionsProxyHandler);
+    }});
+    
     // ============================================
     // CANVAS FINGERPRINT PROTECTION (PERSISTENT NOISE)
     // ============================================
@@ -512,26 +2104,29 @@ pub fn generate_spoof_script(fingerprint: &Fingerprint, profile_id: &str) -> Str
         return x - Math.floor(x);
     }}
     
+    // Noise density and strength from the profile's canvas_noise_level
+    const CANVAS_NOISE_MODULUS = {canvas_noise_modulus};
+    const CANVAS_NOISE_AMPLITUDE = {canvas_noise_amplitude};
+    
+    // Only modify specific pixels based on seed, so output is identical on every read
+    function applyCanvasNoise(data) {{
+        for (let i = 0; i < data.length; i += 4) {{
+            const pixelIndex = i / 4;
+            if (pixelIndex % CANVAS_NOISE_MODULUS === CANVAS_SEED % CANVAS_NOISE_MODULUS) {{
+                const noiseSeed = CANVAS_SEED + pixelIndex;
+                const noise = Math.floor(seededRandom(noiseSeed) * (CANVAS_NOISE_AMPLITUDE * 2 + 1)) - CANVAS_NOISE_AMPLITUDE;
+                data[i] = Math.max(0, Math.min(255, data[i] + noise));
+            }}
+        }}
+    }}
+    
     const originalToDataURL = HTMLCanvasElement.prototype.toDataURL;
     HTMLCanvasElement.prototype.toDataURL = function(type, quality) {{
         const ctx = this.getContext('2d');
         if (ctx && this.width > 0 && this.height > 0) {{
             try {{
                 const imageData = ctx.getImageData(0, 0, this.width, this.height);
-                const data = imageData.data;
-                
-                // Use persistent seed for consistent noise
-                for (let i = 0; i < data.length; i += 4) {{
-                    const pixelIndex = i / 4;
-                    const noiseSeed = CANVAS_SEED + pixelIndex;
-                    
-                    // Only modify specific pixels based on seed
-                    if (pixelIndex % 97 === CANVAS_SEED % 97) {{
-                        const noise = Math.floor(seededRandom(noiseSeed) * 3) - 1;
-                        data[i] = Math.max(0, Math.min(255, data[i] + noise));
-                    }}
-                }}
-                
+                applyCanvasNoise(imageData.data);
                 ctx.putImageData(imageData, 0, 0);
             }} catch (e) {{}}
         }}
@@ -544,18 +2139,7 @@ pub fn generate_spoof_script(fingerprint: &Fingerprint, profile_id: &str) -> Str
         if (ctx && this.width > 0 && this.height > 0) {{
             try {{
                 const imageData = ctx.getImageData(0, 0, this.width, this.height);
-                const data = imageData.data;
-                
-                for (let i = 0; i < data.length; i += 4) {{
-                    const pixelIndex = i / 4;
-                    const noiseSeed = CANVAS_SEED + pixelIndex;
-                    
-                    if (pixelIndex % 97 === CANVAS_SEED % 97) {{
-                        const noise = Math.floor(seededRandom(noiseSeed) * 3) - 1;
-                        data[i] = Math.max(0, Math.min(255, data[i] + noise));
-                    }}
-                }}
-                
+                applyCanvasNoise(imageData.data);
                 ctx.putImageData(imageData, 0, 0);
             }} catch (e) {{}}
         }}
@@ -566,52 +2150,159 @@ pub fn generate_spoof_script(fingerprint: &Fingerprint, profile_id: &str) -> Str
     const originalGetImageData = CanvasRenderingContext2D.prototype.getImageData;
     CanvasRenderingContext2D.prototype.getImageData = function(sx, sy, sw, sh) {{
         const imageData = originalGetImageData.call(this, sx, sy, sw, sh);
-        const data = imageData.data;
-        
-        for (let i = 0; i < data.length; i += 4) {{
-            const pixelIndex = i / 4;
-            const noiseSeed = CANVAS_SEED + pixelIndex;
-            
-            if (pixelIndex % 97 === CANVAS_SEED % 97) {{
-                const noise = Math.floor(seededRandom(noiseSeed) * 3) - 1;
-                data[i] = Math.max(0, Math.min(255, data[i] + noise));
-            }}
-        }}
-        
+        applyCanvasNoise(imageData.data);
         return imageData;
     }};
     
     // ============================================
-    // WEBRTC LEAK PROTECTION (COMPLETE DISABLE)
+    // WEBGL OUTPUT NOISE (PERSISTENT)
     // ============================================
     
-    // Completely disable WebRTC
-    if (typeof RTCPeerConnection !== 'undefined') {{
-        window.RTCPeerConnection = function() {{
-            throw new Error('RTCPeerConnection is disabled');
+    // Rendered pixels depend on the host GPU and driver, so WebGL readback is
+    // noised like 2D canvases. Noise is keyed by GL pixel coordinates, so
+    // readPixels and exported images agree however the region is read.
+    const WEBGL_NOISE = {webgl_noise};
+    const WEBGL_SEED = {webgl_seed};
+    
+    if (WEBGL_NOISE) {{
+        // RGBA bytes of a width x height region at GL (x, y); `topDown` rows
+        // come in image order, otherwise GL order (bottom row first)
+        function applyWebglNoise(data, x, y, width, height, topDown) {{
+            for (let row = 0; row < height; row++) {{
+                const py = topDown ? y + height - 1 - row : y + row;
+                for (let col = 0; col < width; col++) {{
+                    const px = x + col;
+                    if ((px * 7919 + py) % CANVAS_NOISE_MODULUS !== WEBGL_SEED % CANVAS_NOISE_MODULUS) continue;
+                    const i = (row * width + col) * 4 + (px + py) % 3;
+                    if (i >= data.length) return;
+                    const noise = Math.floor(seededRandom(WEBGL_SEED + py * 8192 + px) * (CANVAS_NOISE_AMPLITUDE * 2 + 1)) - CANVAS_NOISE_AMPLITUDE;
+                    data[i] = Math.max(0, Math.min(255, data[i] + noise));
+                }}
+            }}
+        }}
+        
+        const readPixelsProxyHandler = {{
+            apply: function(target, thisArg, args) {{
+                const result = Reflect.apply(target, thisArg, args);
+                const [x, y, width, height, format, type, pixels] = args;
+                // Reads into pixel buffer objects pass an offset instead of an array
+                if (format === thisArg.RGBA && type === thisArg.UNSIGNED_BYTE &&
+                    (pixels instanceof Uint8Array || pixels instanceof Uint8ClampedArray)) {{
+                    applyWebglNoise(pixels.subarray(args[7] || 0), x, y, width, height, false);
+                }}
+                return result;
+            }}
         }};
+        [window.WebGLRenderingContext, window.WebGL2RenderingContext].forEach(function(context) {{
+            if (!context) return;
+            context.prototype.readPixels = new Proxy(context.prototype.readPixels, readPixelsProxyHandler);
+        }});
+        
+        // Remember which canvases render with WebGL, so exports never create a context
+        const webglCanvases = new WeakSet();
+        const originalGetContext = HTMLCanvasElement.prototype.getContext;
+        HTMLCanvasElement.prototype.getContext = new Proxy(originalGetContext, {{
+            apply: function(target, thisArg, args) {{
+                const context = Reflect.apply(target, thisArg, args);
+                if (context && String(args[0]).indexOf('webgl') !== -1) {{
+                    webglCanvases.add(thisArg);
+                }}
+                return context;
+            }}
+        }});
+        
+        // A noised 2D copy of a WebGL canvas, or null to export it as is
+        function noisedWebglCopy(canvas) {{
+            if (!webglCanvases.has(canvas) || !canvas.width || !canvas.height) return null;
+            try {{
+                const copy = document.createElement('canvas');
+                copy.width = canvas.width;
+                copy.height = canvas.height;
+                const ctx = originalGetContext.call(copy, '2d');
+                ctx.drawImage(canvas, 0, 0);
+                const imageData = originalGetImageData.call(ctx, 0, 0, copy.width, copy.height);
+                applyWebglNoise(imageData.data, 0, 0, copy.width, copy.height, true);
+                ctx.putImageData(imageData, 0, 0);
+                return copy;
+            }} catch (e) {{
+                return null;
+            }}
+        }}
+        
+        // Exports of WebGL canvases go through the unwrapped originals on the copy
+        HTMLCanvasElement.prototype.toDataURL = new Proxy(HTMLCanvasElement.prototype.toDataURL, {{
+            apply: function(target, thisArg, args) {{
+                const copy = noisedWebglCopy(thisArg);
+                return copy ? Reflect.apply(originalToDataURL, copy, args) : Reflect.apply(target, thisArg, args);
+            }}
+        }});
+        HTMLCanvasElement.prototype.toBlob = new Proxy(HTMLCanvasElement.prototype.toBlob, {{
+            apply: function(target, thisArg, args) {{
+                const copy = noisedWebglCopy(thisArg);
+                return copy ? Reflect.apply(originalToBlob, copy, args) : Reflect.apply(target, thisArg, args);
+            }}
+        }});
     }}
     
-    if (typeof webkitRTCPeerConnection !== 'undefined') {{
-        window.webkitRTCPeerConnection = function() {{
-            throw new Error('webkitRTCPeerConnection is disabled');
-        }};
-    }}
+    // ============================================
+    // WEBRTC LEAK PROTECTION
+    // ============================================
     
-    if (typeof RTCDataChannel !== 'undefined') {{
-        window.RTCDataChannel = function() {{
-            throw new Error('RTCDataChannel is disabled');
-        }};
-    }}
+    const WEBRTC_MODE = '{webrtc_mode}';
     
-    // Remove mediaDevices.getUserMedia to prevent WebRTC enumeration
-    if (navigator.mediaDevices) {{
-        navigator.mediaDevices.getUserMedia = function() {{
-            return Promise.reject(new Error('getUserMedia is disabled'));
+    if (WEBRTC_MODE === 'disabled') {{
+        if (typeof RTCPeerConnection !== 'undefined') {{
+            window.RTCPeerConnection = function() {{
+                throw new Error('RTCPeerConnection is disabled');
+            }};
+        }}
+        
+        if (typeof webkitRTCPeerConnection !== 'undefined') {{
+            window.webkitRTCPeerConnection = function() {{
+                throw new Error('webkitRTCPeerConnection is disabled');
+            }};
+        }}
+        
+        if (typeof RTCDataChannel !== 'undefined') {{
+            window.RTCDataChannel = function() {{
+                throw new Error('RTCDataChannel is disabled');
+            }};
+        }}
+        
+        // Remove mediaDevices.getUserMedia to prevent WebRTC enumeration
+        if (navigator.mediaDevices) {{
+            navigator.mediaDevices.getUserMedia = function() {{
+                return Promise.reject(new Error('getUserMedia is disabled'));
+            }};
+            navigator.mediaDevices.enumerateDevices = function() {{
+                return Promise.resolve([]);
+            }};
+        }}
+    }} else if (WEBRTC_MODE === 'relay' && typeof RTCPeerConnection !== 'undefined') {{
+        // Gather TURN relay candidates only, so ICE never exposes the local
+        // address or the real public IP behind the proxy
+        const NativeRTCPeerConnection = RTCPeerConnection;
+        const relayOnly = function(config) {{
+            return Object.assign({{}}, config, {{ iceTransportPolicy: 'relay' }});
         }};
-        navigator.mediaDevices.enumerateDevices = function() {{
-            return Promise.resolve([]);
+        
+        const RelayRTCPeerConnection = function(config, constraints) {{
+            return new NativeRTCPeerConnection(relayOnly(config), constraints);
         }};
+        RelayRTCPeerConnection.prototype = NativeRTCPeerConnection.prototype;
+        RelayRTCPeerConnection.generateCertificate = NativeRTCPeerConnection.generateCertificate;
+        window.RTCPeerConnection = RelayRTCPeerConnection;
+        if (typeof webkitRTCPeerConnection !== 'undefined') {{
+            window.webkitRTCPeerConnection = RelayRTCPeerConnection;
+        }}
+        
+        // setConfiguration could otherwise switch the policy back
+        const originalSetConfiguration = NativeRTCPeerConnection.prototype.setConfiguration;
+        if (originalSetConfiguration) {{
+            NativeRTCPeerConnection.prototype.setConfiguration = function(config) {{
+                return originalSetConfiguration.call(this, relayOnly(config));
+            }};
+        }}
     }}
     
     // ============================================
@@ -619,11 +2310,33 @@ pub fn generate_spoof_script(fingerprint: &Fingerprint, profile_id: &str) -> Str
     // ============================================
     
     const targetTimezone = '{timezone}';
-    const targetOffset = {tz_offset};
+    // Offset before the first transition, then [time (ms), offset] for each
+    // change, precomputed from the tz database
+    const TZ_INITIAL_OFFSET = {tz_initial_offset};
+    const TZ_TRANSITIONS = {tz_transitions_json};
+    
+    function offsetAt(time) {{
+        // Binary search for the first transition after `time`
+        let low = 0;
+        let high = TZ_TRANSITIONS.length;
+        while (low < high) {{
+            const mid = (low + high) >> 1;
+            if (TZ_TRANSITIONS[mid][0] <= time) {{
+                low = mid + 1;
+            }} else {{
+                high = mid;
+            }}
+        }}
+        return low === 0 ? TZ_INITIAL_OFFSET : TZ_TRANSITIONS[low - 1][1];
+    }}
     
     const originalGetTimezoneOffset = Date.prototype.getTimezoneOffset;
     Date.prototype.getTimezoneOffset = function() {{
-        return targetOffset;
+        const time = this.getTime();
+        if (isNaN(time)) {{
+            return NaN;
+        }}
+        return offsetAt(time);
     }};
     
     const originalDateTimeFormat = Intl.DateTimeFormat;
@@ -649,53 +2362,74 @@ pub fn generate_spoof_script(fingerprint: &Fingerprint, profile_id: &str) -> Str
     // AUDIO FINGERPRINT PROTECTION (PERSISTENT NOISE)
     // ============================================
     
-    if (typeof AudioContext !== 'undefined' || typeof webkitAudioContext !== 'undefined') {{
-        const AudioContextClass = window.AudioContext || window.webkitAudioContext;
-        
-        const originalCreateAnalyser = AudioContextClass.prototype.createAnalyser;
-        AudioContextClass.prototype.createAnalyser = function() {{
-            const analyser = originalCreateAnalyser.apply(this, arguments);
-            
-            const originalGetFloatFrequencyData = analyser.getFloatFrequencyData.bind(analyser);
-            analyser.getFloatFrequencyData = function(array) {{
-                originalGetFloatFrequencyData(array);
-                for (let i = 0; i < array.length; i++) {{
-                    if (i % 10 === AUDIO_SEED % 10) {{
-                        array[i] = array[i] + seededRandom(AUDIO_SEED + i) * 0.0001;
-                    }}
+    // Analyser reads are patched on the prototype, so nodes from
+    // `new AnalyserNode()` and offline contexts are covered along with createAnalyser()
+    if (typeof AnalyserNode !== 'undefined') {{
+        const noiseAnalyserRead = (name, perturb) => {{
+            const original = AnalyserNode.prototype[name];
+            if (!original) return;
+            AnalyserNode.prototype[name] = function(array) {{
+                original.apply(this, arguments);
+                for (let i = AUDIO_SEED % 10; i < array.length; i += 10) {{
+                    array[i] = perturb(array[i], i);
                 }}
             }};
-            
-            const originalGetByteFrequencyData = analyser.getByteFrequencyData.bind(analyser);
-            analyser.getByteFrequencyData = function(array) {{
-                originalGetByteFrequencyData(array);
-                for (let i = 0; i < array.length; i++) {{
-                    if (i % 10 === AUDIO_SEED % 10) {{
-                        array[i] = Math.max(0, Math.min(255, array[i] + Math.floor(seededRandom(AUDIO_SEED + i) * 2)));
+        }};
+        const floatNoise = (value, i) => value + seededRandom(AUDIO_SEED + i) * 0.0001;
+        const byteNoise = (value, i) => Math.max(0, Math.min(255, value + Math.floor(seededRandom(AUDIO_SEED + i) * 2)));
+        noiseAnalyserRead('getFloatFrequencyData', floatNoise);
+        noiseAnalyserRead('getFloatTimeDomainData', floatNoise);
+        noiseAnalyserRead('getByteFrequencyData', byteNoise);
+        noiseAnalyserRead('getByteTimeDomainData', byteNoise);
+    }}
+    
+    // Audio fingerprints render an oscillator through an OfflineAudioContext and
+    // hash the output. Perturb that rendered buffer rather than the source nodes,
+    // so audio that is actually played keeps its real frequencies.
+    if (typeof OfflineAudioContext !== 'undefined' && typeof AudioBuffer !== 'undefined') {{
+        const renderedBuffers = new WeakSet();
+        const noisedChannels = new WeakMap();
+        const markRendered = (buffer) => {{
+            if (buffer) renderedBuffers.add(buffer);
+            return buffer;
+        }};
+        
+        const originalStartRendering = OfflineAudioContext.prototype.startRendering;
+        OfflineAudioContext.prototype.startRendering = function() {{
+            // Capture phase runs before the page's own oncomplete handler
+            this.addEventListener('complete', (e) => markRendered(e.renderedBuffer), true);
+            return originalStartRendering.apply(this, arguments).then(markRendered);
+        }};
+        
+        // Noise each channel once, in place, so repeated reads agree
+        const originalGetChannelData = AudioBuffer.prototype.getChannelData;
+        AudioBuffer.prototype.getChannelData = function(channel) {{
+            const data = originalGetChannelData.apply(this, arguments);
+            if (renderedBuffers.has(this)) {{
+                let noised = noisedChannels.get(this);
+                if (!noised) {{
+                    noised = new Set();
+                    noisedChannels.set(this, noised);
+                }}
+                if (!noised.has(Number(channel))) {{
+                    noised.add(Number(channel));
+                    for (let i = AUDIO_SEED % 100; i < data.length; i += 100) {{
+                        data[i] += (seededRandom(AUDIO_SEED + i) - 0.5) * 1e-6;
                     }}
                 }}
-            }};
-            
-            return analyser;
+            }}
+            return data;
         }};
         
-        // Also protect createOscillator for audio fingerprinting
-        const originalCreateOscillator = AudioContextClass.prototype.createOscillator;
-        AudioContextClass.prototype.createOscillator = function() {{
-            const oscillator = originalCreateOscillator.apply(this, arguments);
-            const originalStart = oscillator.start.bind(oscillator);
-            
-            oscillator.start = function(when) {{
-                // Add tiny frequency offset based on seed
-                if (oscillator.frequency) {{
-                    const currentFreq = oscillator.frequency.value;
-                    oscillator.frequency.value = currentFreq + (seededRandom(AUDIO_SEED) * 0.001);
+        const originalCopyFromChannel = AudioBuffer.prototype.copyFromChannel;
+        if (originalCopyFromChannel) {{
+            AudioBuffer.prototype.copyFromChannel = function(destination, channel) {{
+                if (renderedBuffers.has(this)) {{
+                    this.getChannelData(channel);
                 }}
-                return originalStart(when);
+                return originalCopyFromChannel.apply(this, arguments);
             }};
-            
-            return oscillator;
-        }};
+        }}
     }}
     
     // ============================================
@@ -785,6 +2519,12 @@ pub fn generate_spoof_script(fingerprint: &Fingerprint, profile_id: &str) -> Str
         configurable: true
     }});
     
+    // Matches the PDF plugins above
+    Object.defineProperty(navigator, 'pdfViewerEnabled', {{
+        get: function() {{ return true; }},
+        configurable: true
+    }});
+    
     // ============================================
     // BATTERY API SPOOFING
     // ============================================
@@ -802,6 +2542,69 @@ pub fn generate_spoof_script(fingerprint: &Fingerprint, profile_id: &str) -> Str
         }};
     }}
     
+    // ============================================
+    // KEYBOARD LAYOUT SPOOFING
+    // ============================================
+    
+    // getLayoutMap exposes the physical keyboard layout; report the one the
+    // profile's language implies. The real call still runs first, so its
+    // rejections (e.g. from a cross-origin frame) are unchanged.
+    if (navigator.keyboard && typeof navigator.keyboard.getLayoutMap === 'function') {{
+        const KEYBOARD_LAYOUT = {keyboard_layout_json};
+        // Spoofed maps are bare KeyboardLayoutMap instances whose prototype
+        // methods answer from the entries kept here
+        const layoutEntries = new WeakMap();
+        const layoutProto = typeof KeyboardLayoutMap === 'function' ? KeyboardLayoutMap.prototype : null;
+        function spoofedLayoutMap() {{
+            const entries = new Map(Object.entries(KEYBOARD_LAYOUT));
+            if (!layoutProto) return entries;
+            const map = Object.create(layoutProto);
+            layoutEntries.set(map, entries);
+            return map;
+        }}
+        if (layoutProto) {{
+            const originalEntries = layoutProto.entries;
+            const wrapped = {{}};
+            ['get', 'has', 'entries', 'keys', 'values', 'forEach'].forEach(function(name) {{
+                if (typeof layoutProto[name] !== 'function') return;
+                wrapped[name] = layoutProto[name] = new Proxy(layoutProto[name], {{
+                    apply: function(target, thisArg, args) {{
+                        const entries = layoutEntries.get(thisArg);
+                        if (!entries) return Reflect.apply(target, thisArg, args);
+                        if (name === 'forEach') {{
+                            const [callback, callbackThis] = args;
+                            entries.forEach(function(value, key) {{ callback.call(callbackThis, value, key, thisArg); }});
+                            return undefined;
+                        }}
+                        return Reflect.apply(Map.prototype[name], entries, args);
+                    }}
+                }});
+            }});
+            // Maplike interfaces iterate with the same function as entries()
+            if (wrapped.entries && layoutProto[Symbol.iterator] === originalEntries) {{
+                layoutProto[Symbol.iterator] = wrapped.entries;
+            }}
+            const sizeDesc = Object.getOwnPropertyDescriptor(layoutProto, 'size');
+            if (sizeDesc && sizeDesc.get) {{
+                sizeDesc.get = new Proxy(sizeDesc.get, {{
+                    apply: function(target, thisArg, args) {{
+                        const entries = layoutEntries.get(thisArg);
+                        return entries ? entries.size : Reflect.apply(target, thisArg, args);
+                    }}
+                }});
+                Object.defineProperty(layoutProto, 'size', sizeDesc);
+            }}
+        }}
+        const keyboardOwner = Object.prototype.hasOwnProperty.call(navigator.keyboard, 'getLayoutMap')
+            ? navigator.keyboard
+            : Object.getPrototypeOf(navigator.keyboard);
+        keyboardOwner.getLayoutMap = new Proxy(keyboardOwner.getLayoutMap, {{
+            apply: function(target, thisArg, args) {{
+                return Reflect.apply(target, thisArg, args).then(spoofedLayoutMap);
+            }}
+        }});
+    }}
+    
     // ============================================
     // PERFORMANCE API PROTECTION
     // ============================================
@@ -812,6 +2615,21 @@ pub fn generate_spoof_script(fingerprint: &Fingerprint, profile_id: &str) -> Str
         return originalPerformanceNow() + seededRandom(CANVAS_SEED) * 0.1;
     }};
     
+    // Chrome-only heap sizes, scaled to the spoofed deviceMemory
+    if ('memory' in performance) {{
+        const spoofedMemory = {{
+            jsHeapSizeLimit: {js_heap_limit},
+            totalJSHeapSize: {js_heap_total},
+            usedJSHeapSize: {js_heap_used}
+        }};
+        try {{
+            Object.defineProperty(performance, 'memory', {{
+                get: function() {{ return spoofedMemory; }},
+                configurable: true
+            }});
+        }} catch (e) {{}}
+    }}
+    
     // ============================================
     // CLIENTRECTS PROTECTION
     // ============================================
@@ -857,30 +2675,170 @@ pub fn generate_spoof_script(fingerprint: &Fingerprint, profile_id: &str) -> Str
             left: rect.left + noise,
             toJSON: rect.toJSON
         }};
-    }};
+    }};
+    
+    // ============================================
+    // CUSTOM HEADERS
+    // ============================================
+    // Webviews expose no hook for top-level navigation headers, so these are
+    // added to the page's same-origin fetch/XHR calls only. Cross-origin
+    // requests are left alone to avoid triggering CORS preflights. Sec-*
+    // names are forbidden to page scripts and refused when saving; any stored
+    // before that are skipped rather than logging a refusal on every call.
+    
+    const CUSTOM_HEADERS = {custom_headers_json};
+    const CUSTOM_HEADER_NAMES = Object.keys(CUSTOM_HEADERS).filter(function(name) {{
+        return name.toLowerCase().indexOf('sec-') !== 0;
+    }});
+    
+    if (CUSTOM_HEADER_NAMES.length > 0) {{
+        const isSameOrigin = function(url) {{
+            try {{
+                return new URL(url, window.location.href).origin === window.location.origin;
+            }} catch (e) {{
+                return false;
+            }}
+        }};
+        
+        const originalFetch = window.fetch;
+        window.fetch = function(input, init) {{
+            const url = (input && input.url) || String(input);
+            if (!isSameOrigin(url)) {{
+                return originalFetch.call(this, input, init);
+            }}
+            const request = new Request(input, init);
+            CUSTOM_HEADER_NAMES.forEach(function(name) {{
+                if (!request.headers.has(name)) {{
+                    request.headers.set(name, CUSTOM_HEADERS[name]);
+                }}
+            }});
+            return originalFetch.call(this, request);
+        }};
+        
+        const originalXhrOpen = XMLHttpRequest.prototype.open;
+        const originalXhrSetRequestHeader = XMLHttpRequest.prototype.setRequestHeader;
+        const originalXhrSend = XMLHttpRequest.prototype.send;
+        
+        XMLHttpRequest.prototype.open = function(method, url) {{
+            this.__ifSameOrigin = isSameOrigin(url);
+            this.__ifHeadersSet = [];
+            return originalXhrOpen.apply(this, arguments);
+        }};
+        
+        XMLHttpRequest.prototype.setRequestHeader = function(name, value) {{
+            if (this.__ifHeadersSet) {{
+                this.__ifHeadersSet.push(String(name).toLowerCase());
+            }}
+            return originalXhrSetRequestHeader.call(this, name, value);
+        }};
+        
+        XMLHttpRequest.prototype.send = function(body) {{
+            if (this.__ifSameOrigin) {{
+                const alreadySet = this.__ifHeadersSet || [];
+                CUSTOM_HEADER_NAMES.forEach((name) => {{
+                    if (alreadySet.indexOf(name.toLowerCase()) === -1) {{
+                        originalXhrSetRequestHeader.call(this, name, CUSTOM_HEADERS[name]);
+                    }}
+                }});
+            }}
+            return originalXhrSend.call(this, body);
+        }};
+    }}
     
     console.log('[IdentityForge] Advanced fingerprint protection active - Profile: ' + PROFILE_ID);
 }})();
 "#,
-        user_agent = fingerprint.user_agent.replace('\'', "\\'"),
-        platform = fingerprint.platform.replace('\'', "\\'"),
-        hardware_concurrency = fingerprint.hardware_concurrency,
-        device_memory = fingerprint.device_memory,
-        language = fingerprint.language.replace('\'', "\\'"),
         screen_width = fingerprint.screen_width,
         screen_height = fingerprint.screen_height,
-        webgl_vendor = fingerprint.webgl_vendor.replace('\'', "\\'"),
-        webgl_renderer = fingerprint.webgl_renderer.replace('\'', "\\'"),
-        timezone = fingerprint.timezone.replace('\'', "\\'"),
-        tz_offset = tz_offset,
+        webgl_vendor = js_single_quoted(&fingerprint.webgl_vendor),
+        webgl_renderer = js_single_quoted(&fingerprint.webgl_renderer),
+        timezone = js_single_quoted(&fingerprint.timezone),
+        tz_initial_offset = tz_initial_offset,
+        firefox_build_id = FIREFOX_BUILD_ID,
+        tz_transitions_json = tz_transitions_json,
+        dnt_value = dnt_value,
+        ua_family = ua_family,
+        oscpu_json = oscpu_json,
+        ua_data_json = ua_data_json,
+        keyboard_layout_json = keyboard_layout_json,
+        inner_width = inner_width,
+        inner_height = inner_height,
+        webgl_max_texture_size = webgl_max_texture_size,
+        webgl2_parameters_json = webgl2_parameters_json,
+        webgl_max_vertex_attribs = webgl_max_vertex_attribs,
+        webgl_max_vertex_uniform_vectors = webgl_max_vertex_uniform_vectors,
+        webgl_max_fragment_uniform_vectors = webgl_max_fragment_uniform_vectors,
+        custom_headers_json = custom_headers_json,
+        languages_json = languages_json,
+        js_heap_limit = js_heap_limit,
+        js_heap_total = js_heap_total,
+        js_heap_used = js_heap_used,
         canvas_seed = canvas_seed,
+        canvas_noise_modulus = canvas_noise_modulus,
+        canvas_noise_amplitude = canvas_noise_amplitude,
+        webrtc_mode = webrtc_mode,
         audio_seed = audio_seed,
         font_seed = font_seed,
+        webgl_noise = fingerprint.webgl_noise,
+        webgl_seed = webgl_seed,
         fonts_array = fonts_array,
-        profile_id = profile_id.replace('\'', "\\'"),
+        profile_id = js_single_quoted(profile_id),
+        navigator_overrides = navigator_overrides,
+        screen_overrides = screen_overrides,
+        window_overrides = window_overrides,
     )
 }
 
+/// Short, stable identifier for a generated script: the first 12 hex digits
+/// of its SHA-256. Changes whenever the script a profile would receive changes.
+pub fn spoof_script_version(script: &str) -> String {
+    let digest = hex::encode(Sha256::digest(script.as_bytes()));
+    digest[..12].to_string()
+}
+
+/// Package a spoof script for external automation tools: a comment header
+/// summarizing the fingerprint and how to inject it, then the script itself
+pub fn export_spoof_script(fingerprint: &Fingerprint, profile_id: &str, profile_name: &str) -> String {
+    let script = generate_spoof_script(fingerprint, profile_id);
+    // Keep interpolated values from closing the comment early
+    let safe = |value: &str| value.replace("*/", "* /");
+    let languages = navigator_languages(&fingerprint.language, &fingerprint.languages);
+
+    let header = format!(
+        "/*
+ * IdentityForge spoof script for profile \"{name}\" ({id})
+ * Script version: {version}
+ *
+ * User agent: {user_agent}
+ * Platform: {platform}, screen {width}x{height}, timezone {timezone}
+ * Languages: {languages}
+ * WebGL: {vendor} / {renderer}
+ * CPU cores: {cores}, device memory: {memory} GB
+ *
+ * Inject before any page script runs:
+ *   Puppeteer:  await page.evaluateOnNewDocument(script);
+ *   Playwright: await context.addInitScript(script);
+ * Also set the user agent in the tool itself (page.setUserAgent or
+ * newContext({{ userAgent }})); a page script can't change the request header.
+ */
+",
+        name = safe(profile_name),
+        id = safe(profile_id),
+        version = spoof_script_version(&script),
+        user_agent = safe(&fingerprint.user_agent),
+        platform = safe(&fingerprint.platform),
+        width = fingerprint.screen_width,
+        height = fingerprint.screen_height,
+        timezone = safe(&fingerprint.timezone),
+        languages = safe(&languages.join(", ")),
+        vendor = safe(&fingerprint.webgl_vendor),
+        renderer = safe(&fingerprint.webgl_renderer),
+        cores = fingerprint.hardware_concurrency,
+        memory = fingerprint.device_memory,
+    );
+    header + &script
+}
+
 /// Legacy function for backward compatibility
 pub fn generate_spoof_script_legacy(fingerprint: &Fingerprint) -> String {
     generate_spoof_script(fingerprint, "default")
@@ -890,6 +2848,11 @@ pub fn generate_spoof_script_legacy(fingerprint: &Fingerprint) -> String {
 mod tests {
     use super::*;
 
+    /// Unix seconds of a UTC date and hour
+    fn utc(year: i32, month: u32, day: u32, hour: u32) -> i64 {
+        chrono::Utc.with_ymd_and_hms(year, month, day, hour, 0, 0).unwrap().timestamp()
+    }
+
     #[test]
     fn test_fingerprint_generation() {
         let mut generator = FingerprintGenerator::new();
@@ -924,5 +2887,889 @@ mod tests {
         assert!(script.contains(&fp.user_agent));
         assert!(script.contains("CANVAS_SEED"));
         assert!(script.contains("AUDIO_SEED"));
+        assert!(script.contains("AudioBuffer.prototype.getChannelData"));
+        assert!(script.contains("const DEBUG_RENDERER_INFO = 'WEBGL_debug_renderer_info';"));
+        assert!(script.contains("noisedChannels.get(this)"));
+        assert!(!script.contains("frequency.value ="));
+        assert_eq!(run_spoof_script(&script, "String(navigator.webdriver)").unwrap(), "false");
+        assert!(script.contains("Object.defineProperty(navigator, 'pdfViewerEnabled'"));
+
+        // Every analyser read is noised, the same way on each call
+        let probe = "['getFloatFrequencyData', 'getFloatTimeDomainData', 'getByteFrequencyData', 'getByteTimeDomainData']\
+                     .map(name => {
+                         const read = () => {
+                             const array = name.includes('Byte') ? new Uint8Array(64) : new Float32Array(64);
+                             new AnalyserNode()[name](array);
+                             return Array.from(array).join(',');
+                         };
+                         const first = read();
+                         return first === read() && first.split(',').some(v => v !== '0');
+                     }).join(',')";
+        assert_eq!(run_spoof_script(&script, probe).unwrap(), "true,true,true,true");
+    }
+
+    /// Bare-bones browser globals: just the constructors and objects the spoof
+    /// script patches, with methods that return null
+    const DOM_STUB: &str = r#"
+        var window = globalThis;
+        function stubClass(name, methods) {
+            var ctor = function () {};
+            methods.forEach(function (m) { ctor.prototype[m] = function () { return null; }; });
+            window[name] = ctor;
+        }
+        stubClass('EventTarget', ['addEventListener']);
+        stubClass('Element', ['getBoundingClientRect', 'getClientRects']);
+        stubClass('HTMLCanvasElement', ['toDataURL', 'toBlob', 'getContext']);
+        stubClass('CanvasRenderingContext2D', ['getImageData', 'fillText', 'measureText']);
+        stubClass('WebGLRenderingContext', ['getParameter', 'getExtension', 'getSupportedExtensions', 'readPixels']);
+        stubClass('WebGL2RenderingContext', ['getParameter', 'getExtension', 'getSupportedExtensions', 'readPixels']);
+        stubClass('AudioBuffer', ['getChannelData', 'copyFromChannel']);
+        stubClass('AudioContext', ['createAnalyser', 'createOscillator']);
+        stubClass('AnalyserNode', [
+            'getFloatFrequencyData', 'getFloatTimeDomainData', 'getByteFrequencyData', 'getByteTimeDomainData'
+        ]);
+        stubClass('OfflineAudioContext', ['startRendering']);
+        stubClass('RTCPeerConnection', ['setConfiguration', 'createDataChannel']);
+        stubClass('ScreenOrientation', ['addEventListener']);
+        stubClass('XMLHttpRequest', ['open', 'send', 'setRequestHeader']);
+        var navigator = { mediaDevices: { enumerateDevices: function () { return Promise.resolve([]); } } };
+        var screen = {};
+        var document = {
+            fonts: { check: function () { return false; } },
+            createElement: function () { return new HTMLCanvasElement(); }
+        };
+        var performance = { now: function () { return 0; } };
+        var console = { log: function () {} };
+        // QuickJS has no Intl
+        var Intl = { DateTimeFormat: function () {} };
+        Intl.DateTimeFormat.prototype.resolvedOptions = function () { return {}; };
+        var location = { href: 'about:blank' };
+        window.fetch = function () { return Promise.resolve(); };
+        window.matchMedia = function () { return { matches: false }; };
+    "#;
+
+    /// Run `script` after `DOM_STUB` in QuickJS, then return what `probe`
+    /// evaluates to. Errors carry the JS exception message.
+    fn run_spoof_script(script: &str, probe: &str) -> Result<String, String> {
+        let runtime = rquickjs::Runtime::new().map_err(|e| e.to_string())?;
+        let context = rquickjs::Context::full(&runtime).map_err(|e| e.to_string())?;
+        let describe = |ctx: &rquickjs::Ctx, e: rquickjs::Error| match e {
+            rquickjs::Error::Exception => ctx
+                .catch()
+                .as_exception()
+                .map(|exception| exception.to_string())
+                .unwrap_or_else(|| "uncaught exception".to_string()),
+            e => e.to_string(),
+        };
+        context.with(|ctx| {
+            ctx.eval::<(), _>(DOM_STUB).map_err(|e| describe(&ctx, e))?;
+            ctx.eval::<(), _>(script).map_err(|e| describe(&ctx, e))
+        })?;
+        // Settle promises the script started before probing
+        while runtime.execute_pending_job().unwrap_or(false) {}
+        context.with(|ctx| ctx.eval::<String, _>(probe).map_err(|e| describe(&ctx, e)))
+    }
+
+    #[test]
+    fn test_spoof_script_runs_in_js_engine() {
+        let probe = "[navigator.userAgent, navigator.platform, screen.width, \
+                     new WebGLRenderingContext().getParameter(37446), \
+                     new Intl.DateTimeFormat().resolvedOptions().timeZone].join('|')";
+        let mut generator = FingerprintGenerator::new();
+        for platform in ["windows", "macos", "linux"] {
+            for (level, webrtc_mode) in CANVAS_NOISE_LEVELS.iter().zip(WEBRTC_MODES) {
+                let mut fp = generator.generate_for_platform(platform);
+                fp.canvas_noise_level = level.to_string();
+                fp.webrtc_mode = Some(webrtc_mode.to_string());
+                let script = generate_spoof_script(&fp, "test-profile");
+                let observed = run_spoof_script(&script, probe)
+                    .unwrap_or_else(|e| panic!("{} script threw: {}", platform, e));
+                assert_eq!(
+                    observed,
+                    format!(
+                        "{}|{}|{}|{}|{}",
+                        fp.user_agent, fp.platform, fp.screen_width, fp.webgl_renderer, fp.timezone
+                    )
+                );
+            }
+        }
+
+        // Values users can edit must come through intact, not break the script
+        let mut fp = generator.generate_for_platform("windows");
+        fp.user_agent = r"Mozilla/5.0 (it's \'odd\')".to_string();
+        fp.webgl_renderer = "ANGLE (Line\nBreak)\\".to_string();
+        let script = generate_spoof_script(&fp, "test-profile");
+        let observed = run_spoof_script(&script, probe).expect("edited values broke the script");
+        assert_eq!(
+            observed,
+            format!(
+                "{}|{}|{}|{}|{}",
+                fp.user_agent, fp.platform, fp.screen_width, fp.webgl_renderer, fp.timezone
+            )
+        );
+    }
+
+    #[test]
+    fn test_keyboard_layout_follows_language() {
+        let us = keyboard_layout("en-US", "Win32");
+        assert_eq!(us.len(), 47);
+        assert_eq!((us["KeyQ"].as_str(), us["Semicolon"].as_str()), ("q", ";"));
+        assert_eq!(keyboard_layout("zh-CN", "Win32"), us);
+        assert_eq!(keyboard_layout("de-DE", "Win32")["KeyY"], "z");
+        assert_eq!(keyboard_layout("fr-FR", "Win32")["KeyA"], "q");
+        assert_eq!(keyboard_layout("fr-FR", "MacIntel")["Backquote"], "@");
+        assert_eq!(keyboard_layout("en-GB", "MacIntel")["Backquote"], "§");
+
+        // Resolves a Map from the real call, and leaves browsers without the API alone
+        let keyboard = "navigator.keyboard = { getLayoutMap: function () { return Promise.resolve(null); } };";
+        let mut fp = FingerprintGenerator::new().generate_for_platform("windows");
+        fp.language = "de-DE".to_string();
+        let script = format!(
+            "{}{}navigator.keyboard.getLayoutMap().then(function (m) {{ window.layout = m; }});",
+            keyboard,
+            generate_spoof_script(&fp, "test-profile")
+        );
+        let probe = "(window.layout instanceof Map) + '|' + window.layout.get('KeyZ') + window.layout.get('Semicolon')";
+        assert_eq!(run_spoof_script(&script, probe).unwrap(), "true|yö");
+        assert!(run_spoof_script(&generate_spoof_script(&fp, "test-profile"), "String(navigator.keyboard)").is_ok());
+
+        // Where KeyboardLayoutMap exists the result is one, and its native
+        // methods (which reject other receivers) answer for it
+        let native_map = "function KeyboardLayoutMap() { throw new TypeError('Illegal constructor'); }\
+            ['get', 'has', 'entries', 'keys', 'values', 'forEach'].forEach(function (name) {\
+                KeyboardLayoutMap.prototype[name] = function () { throw new TypeError('Illegal invocation'); };\
+            });\
+            KeyboardLayoutMap.prototype[Symbol.iterator] = KeyboardLayoutMap.prototype.entries;\
+            Object.defineProperty(KeyboardLayoutMap.prototype, 'size', {\
+                get: function () { throw new TypeError('Illegal invocation'); }, configurable: true });";
+        let script = format!(
+            "{}{}{}navigator.keyboard.getLayoutMap().then(function (m) {{ window.layout = m; }});",
+            native_map,
+            keyboard,
+            generate_spoof_script(&fp, "test-profile")
+        );
+        let probe = "var m = window.layout, seen = 0; m.forEach(function (v, k, owner) { seen += owner === m; });\
+                     [Object.getPrototypeOf(m) === KeyboardLayoutMap.prototype, Object.keys(m).length,\
+                      m.get('KeyZ'), m.has('KeyA'), seen > 0 && m.size === seen, Array.from(m).length === seen,\
+                      typeof navigator.keyboard.getLayoutMap].join('|')";
+        assert_eq!(run_spoof_script(&script, probe).unwrap(), "true|0|y|true|true|true|function");
+    }
+
+    #[test]
+    fn test_webgl_noise_is_stable_per_profile() {
+        // Full 32x32 read, then the same pixels read back as an 8x4 region at (8, 12)
+        let probe = "var gl = new WebGLRenderingContext(); gl.RGBA = 6408; gl.UNSIGNED_BYTE = 5121;\
+                     function read(x, y, w, h) {\
+                         var px = new Uint8Array(w * h * 4).fill(128);\
+                         gl.readPixels(x, y, w, h, gl.RGBA, gl.UNSIGNED_BYTE, px);\
+                         return px;\
+                     }\
+                     var full = read(0, 0, 32, 32), part = read(8, 12, 8, 4), same = true;\
+                     for (var row = 0; row < 4; row++) for (var i = 0; i < 32; i++)\
+                         same = same && part[row * 32 + i] === full[((12 + row) * 32 + 8) * 4 + i];\
+                     Array.prototype.join.call(full, ',') + '|' + same";
+        let mut fp = FingerprintGenerator::new().generate();
+        fp.canvas_noise_level = "high".to_string();
+        let read = |fp: &Fingerprint, profile_id: &str| {
+            let observed = run_spoof_script(&generate_spoof_script(fp, profile_id), probe).unwrap();
+            let (pixels, region_matches) = observed.split_once('|').unwrap();
+            assert_eq!(region_matches, "true");
+            pixels.to_string()
+        };
+
+        let first = read(&fp, "profile-a");
+        assert_eq!(first, read(&fp, "profile-a"));
+        assert_ne!(first, read(&fp, "profile-b"));
+        assert!(first.split(',').any(|v| v != "128"));
+
+        fp.webgl_noise = false;
+        assert!(read(&fp, "profile-a").split(',').all(|v| v == "128"));
+    }
+
+    #[test]
+    fn test_spoof_structure_varies_by_profile() {
+        let fp = FingerprintGenerator::new().generate_for_platform("windows");
+        let navigator_order = |script: &str| {
+            let mut found: Vec<(usize, &str)> = ["userAgent", "platform", "webdriver", "languages", "vendor"]
+                .into_iter()
+                .filter_map(|name| {
+                    let at = script.find(&format!("navigator, '{}'", name))
+                        .or_else(|| script.find(&format!("navigator, \"{}\"", name)))?;
+                    Some((at, name))
+                })
+                .collect();
+            found.sort();
+            found.into_iter().map(|(_, name)| name).collect::<Vec<_>>()
+        };
+
+        let scripts: Vec<String> = ["profile-0", "profile-1", "profile-2", "profile-3"]
+            .iter()
+            .map(|id| generate_spoof_script(&fp, id))
+            .collect();
+        assert_eq!(scripts[0], generate_spoof_script(&fp, "profile-0"));
+        let orders: std::collections::HashSet<Vec<&str>> =
+            scripts.iter().map(|script| navigator_order(script)).collect();
+        assert!(orders.len() > 1, "every profile got the same override order");
+
+        // Only the structure moves; every profile still reads the same values
+        for script in &scripts {
+            let observed = run_spoof_script(
+                script,
+                "[navigator.userAgent, navigator.appVersion, screen.availHeight, window.innerWidth > 0].join('|')",
+            )
+            .unwrap();
+            assert_eq!(
+                observed,
+                format!("{}|{}|{}|true", fp.user_agent, &fp.user_agent[8..], fp.screen_height - 40)
+            );
+        }
+    }
+
+    #[test]
+    fn test_exposed_device_memory_is_spec_compliant() {
+        for &memory in DEVICE_MEMORY.iter().chain(&[0, 1, 3, 6, 64]) {
+            let exposed = exposed_device_memory(memory);
+            assert!(SPEC_DEVICE_MEMORY.contains(&exposed), "{} GB -> {}", memory, exposed);
+        }
+        assert_eq!(exposed_device_memory(32), 8.0);
+        assert_eq!(exposed_device_memory(6), 4.0);
+
+        let mut fp = FingerprintGenerator::new().generate();
+        fp.device_memory = 16;
+        let script = generate_spoof_script(&fp, "test-profile");
+        assert_eq!(run_spoof_script(&script, "String(navigator.deviceMemory)").unwrap(), "8");
+    }
+
+    #[test]
+    fn test_firefox_oscpu() {
+        let windows = "Mozilla/5.0 (Windows NT 10.0; Win64; x64; rv:121.0) Gecko/20100101 Firefox/121.0";
+        let mac = "Mozilla/5.0 (Macintosh; Intel Mac OS X 10.15; rv:121.0) Gecko/20100101 Firefox/121.0";
+        let ubuntu = "Mozilla/5.0 (X11; Ubuntu; Linux x86_64; rv:120.0) Gecko/20100101 Firefox/120.0";
+        assert_eq!(firefox_oscpu(windows, "Win32").as_deref(), Some("Windows NT 10.0; Win64; x64"));
+        assert_eq!(firefox_oscpu(mac, "MacIntel").as_deref(), Some("Intel Mac OS X 10.15"));
+        assert_eq!(firefox_oscpu(ubuntu, "Linux x86_64").as_deref(), Some("Linux x86_64"));
+        // A UA from another OS still yields an oscpu matching the platform
+        assert_eq!(firefox_oscpu(mac, "Win32").as_deref(), Some("Windows NT 10.0; Win64; x64"));
+
+        let mut fp = FingerprintGenerator::new().generate();
+        fp.user_agent = ubuntu.to_string();
+        fp.platform = "Linux x86_64".to_string();
+        let script = generate_spoof_script(&fp, "test-profile");
+        assert!(script.contains("const UA_FAMILY = 'firefox';"));
+        assert!(script.contains("const OSCPU = \"Linux x86_64\";"));
+
+        fp.user_agent = USER_AGENTS[0].1.to_string();
+        assert_eq!(firefox_oscpu(&fp.user_agent, "Win32"), None);
+        let script = generate_spoof_script(&fp, "test-profile");
+        assert!(script.contains("const UA_FAMILY = 'chrome';"));
+        assert!(script.contains("const OSCPU = null;"));
+    }
+
+    #[test]
+    fn test_navigator_vendor_follows_ua_family() {
+        let probe = "[navigator.vendor, navigator.productSub, String(navigator.buildID)].join('|')";
+        let mut fp = FingerprintGenerator::new().generate();
+        for (_, user_agent) in USER_AGENTS.iter().filter(|(_, ua)| ua.contains("Firefox/")) {
+            fp.user_agent = user_agent.to_string();
+            let script = generate_spoof_script(&fp, "test-profile");
+            assert!(!script.contains("'Google Inc.'"));
+            assert_eq!(run_spoof_script(&script, probe).unwrap(), "|20100101|20181001000000");
+        }
+
+        fp.user_agent = USER_AGENTS.iter().find(|(_, ua)| ua.contains("Chrome/")).unwrap().1.to_string();
+        let script = generate_spoof_script(&fp, "test-profile");
+        assert_eq!(run_spoof_script(&script, probe).unwrap(), "Google Inc.|20030107|undefined");
+
+        fp.user_agent = USER_AGENTS.iter().find(|(_, ua)| ua.contains("Version/")).unwrap().1.to_string();
+        let script = generate_spoof_script(&fp, "test-profile");
+        assert_eq!(run_spoof_script(&script, probe).unwrap(), "Apple Computer, Inc.|20030107|undefined");
+    }
+
+    #[test]
+    fn test_user_agent_data_follows_ua() {
+        let mut fp = FingerprintGenerator::new().generate();
+        fp.user_agent = USER_AGENTS.iter().find(|(_, ua)| ua.contains("Firefox/")).unwrap().1.to_string();
+        assert!(user_agent_data(&fp).is_none());
+        let script = generate_spoof_script(&fp, "test-profile");
+        assert_eq!(run_spoof_script(&script, "String('userAgentData' in navigator)").unwrap(), "false");
+
+        fp.platform = "Win32".to_string();
+        fp.user_agent = "Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36 (KHTML, like Gecko) \
+            Chrome/120.0.6099.130 Safari/537.36 Edg/120.0.2210.91"
+            .to_string();
+        let data = user_agent_data(&fp).unwrap();
+        let brands: Vec<_> = data.brands.iter().map(|b| format!("{} {}", b.brand, b.version)).collect();
+        assert_eq!(brands, ["Not_A Brand 8", "Chromium 120", "Microsoft Edge 120"]);
+        assert_eq!(data.full_version_list[1].version, "120.0.6099.130");
+        assert_eq!(data.ua_full_version, "120.0.2210.91");
+        assert_eq!((data.platform.as_str(), data.platform_version.as_str()), ("Windows", "10.0.0"));
+
+        let script = generate_spoof_script(&fp, "test-profile");
+        let probe = "navigator.userAgentData.brands.map(b => b.brand).join(',') + '|' + navigator.userAgentData.platform";
+        assert_eq!(run_spoof_script(&script, probe).unwrap(), "Not_A Brand,Chromium,Microsoft Edge|Windows");
+
+        fp.platform = "MacIntel".to_string();
+        fp.webgl_renderer = "Apple M1".to_string();
+        fp.user_agent = "Mozilla/5.0 (Macintosh; Intel Mac OS X 10_15_7) AppleWebKit/537.36 (KHTML, like Gecko) \
+            Chrome/120.0.0.0 Safari/537.36"
+            .to_string();
+        let data = user_agent_data(&fp).unwrap();
+        assert_eq!(data.brands[2].brand, "Google Chrome");
+        assert_eq!((data.platform.as_str(), data.platform_version.as_str()), ("macOS", "10.15.7"));
+        assert_eq!(data.architecture, "arm");
+
+        // GREASE brand, version and order as Chrome sends them for each major version
+        let brands_for = |major: u32| {
+            let mut fp = fp.clone();
+            fp.user_agent = fp.user_agent.replace("Chrome/120.0.0.0", &format!("Chrome/{}.0.0.0", major));
+            let data = user_agent_data(&fp).unwrap();
+            assert_eq!(data.full_version_list.len(), 3);
+            data.brands.iter().map(|b| format!("{} {}", b.brand, b.version)).collect::<Vec<_>>()
+        };
+        assert_eq!(brands_for(119), ["Google Chrome 119", "Chromium 119", "Not?A_Brand 24"]);
+        assert_eq!(brands_for(120), ["Not_A Brand 8", "Chromium 120", "Google Chrome 120"]);
+        assert_eq!(brands_for(121), ["Not A(Brand 99", "Google Chrome 121", "Chromium 121"]);
+    }
+
+    #[test]
+    fn test_screen_orientation_follows_screen() {
+        let fp = FingerprintGenerator::new().generate();
+        let script = generate_spoof_script(&fp, "test-profile");
+        assert!(script.contains(
+            "const SPOOF_ORIENTATION = SPOOF_WIDTH >= SPOOF_HEIGHT ? 'landscape-primary' : 'portrait-primary';"
+        ));
+        assert!(script.contains("Object.defineProperty(ScreenOrientation.prototype, 'angle'"));
+    }
+
+    #[test]
+    fn test_webrtc_mode() {
+        let mut fp = FingerprintGenerator::new().generate();
+        let script = generate_spoof_script(&fp, "test-profile");
+        assert!(script.contains("const WEBRTC_MODE = 'disabled';"));
+
+        fp.webrtc_mode = Some("relay".to_string());
+        let script = generate_spoof_script(&fp, "test-profile");
+        assert!(script.contains("const WEBRTC_MODE = 'relay';"));
+
+        fp.webrtc_mode = Some("bogus".to_string());
+        let script = generate_spoof_script(&fp, "test-profile");
+        assert!(script.contains("const WEBRTC_MODE = 'disabled';"));
+    }
+
+    #[test]
+    fn test_export_spoof_script() {
+        let fp = FingerprintGenerator::new().generate();
+        let exported = export_spoof_script(&fp, "test-profile", "Team */ alert(1)");
+        let script = generate_spoof_script(&fp, "test-profile");
+
+        assert!(exported.ends_with(&script));
+        let header = &exported[..exported.len() - script.len()];
+        assert!(header.contains(&fp.user_agent));
+        assert!(header.contains(&spoof_script_version(&script)));
+        assert_eq!(header.matches("*/").count(), 1);
+    }
+
+    #[test]
+    fn test_do_not_track_signal() {
+        let mut generator = FingerprintGenerator::new();
+        let mut fp = generator.generate();
+
+        fp.do_not_track = Some(true);
+        let script = generate_spoof_script(&fp, "test-profile");
+        assert!(script.contains("const DO_NOT_TRACK = '1';"));
+
+        fp.do_not_track = Some(false);
+        let script = generate_spoof_script(&fp, "test-profile");
+        assert!(script.contains("const DO_NOT_TRACK = null;"));
+
+        // GPC only appears for UAs of browsers that ship it
+        fp.do_not_track = Some(true);
+        for (marker, expected) in [("Firefox/", "true"), ("Chrome/", "false")] {
+            fp.user_agent = USER_AGENTS.iter().find(|(_, ua)| ua.contains(marker)).unwrap().1.to_string();
+            let script = generate_spoof_script(&fp, "test-profile");
+            assert_eq!(run_spoof_script(&script, "String('globalPrivacyControl' in navigator)").unwrap(), expected);
+        }
+
+        // Unpinned value is stable per profile
+        fp.do_not_track = None;
+        assert_eq!(
+            generate_spoof_script(&fp, "profile-123"),
+            generate_spoof_script(&fp, "profile-123")
+        );
+    }
+
+    #[test]
+    fn test_identity_hash() {
+        let mut generator = FingerprintGenerator::new();
+        let fp = generator.generate();
+
+        // Non-identifying fields don't affect the hash
+        let mut same = fp.clone();
+        same.proxy_host = "10.0.0.1".to_string();
+        same.default_url = "https://example.com".to_string();
+        assert_eq!(fp.identity_hash(), same.identity_hash());
+        assert_eq!(fp.identity_hash().len(), 64);
+
+        let mut different = fp.clone();
+        different.screen_width += 1;
+        assert_ne!(fp.identity_hash(), different.identity_hash());
+    }
+
+    #[test]
+    fn test_diversity_tracker() {
+        let fp = FingerprintGenerator::new().generate();
+        let mut other_gpu = fp.clone();
+        other_gpu.webgl_renderer.push_str(" (2)");
+
+        // Up to 1 in 10 of a batch may share a combination
+        let mut tracker = DiversityTracker::new(25);
+        for _ in 0..3 {
+            assert!(tracker.accepts(&fp));
+            tracker.record(&fp);
+        }
+        assert!(!tracker.accepts(&fp));
+        assert!(tracker.accepts(&other_gpu));
+
+        // Even a tiny batch allows each combination once
+        let mut tracker = DiversityTracker::new(1);
+        tracker.record(&fp);
+        assert!(!tracker.accepts(&fp));
+    }
+
+    #[test]
+    fn test_parse_hash_blocklist() {
+        let hash = FingerprintGenerator::new().generate().identity_hash();
+        let entries = vec![format!(" {} ", hash.to_uppercase()), String::new(), "abc123".to_string()];
+        let (hashes, invalid) = parse_hash_blocklist(&entries);
+        assert!(hashes.contains(&hash));
+        assert_eq!(hashes.len(), 1);
+        assert_eq!(invalid, vec!["abc123".to_string()]);
+    }
+
+    #[test]
+    fn test_describe_identity() {
+        let mut fp = FingerprintGenerator::new().generate();
+        fp.user_agent = USER_AGENTS[0].1.to_string();
+        fp.platform = "Win32".to_string();
+        fp.timezone = "Europe/Berlin".to_string();
+        fp.device_memory = 32;
+        fp.proxy_enabled = true;
+        fp.proxy_host = "gate.provider.de".to_string();
+
+        let summer = utc(2024, 7, 1, 0);
+        let identity = fp.describe(summer);
+        assert_eq!(identity.browser, "Chrome");
+        assert_eq!(identity.browser_version, Some(120));
+        assert_eq!(identity.os, "Windows 10/11");
+        assert_eq!(identity.utc_offset, "UTC+02:00");
+        assert_eq!(identity.device_memory, 8.0);
+        assert_eq!(identity.proxy_country.as_deref(), Some("DE"));
+
+        fp.user_agent = USER_AGENTS.iter().find(|(_, ua)| ua.contains("Version/")).unwrap().1.to_string();
+        fp.timezone = "America/New_York".to_string();
+        fp.proxy_enabled = false;
+        let identity = fp.describe(summer);
+        assert_eq!(identity.browser, "Safari");
+        assert_eq!(identity.os, "macOS 10.15.7");
+        assert_eq!(identity.utc_offset, "UTC-04:00");
+        assert!(identity.proxy_host.is_none());
+    }
+
+    #[test]
+    fn test_location_issues() {
+        let mut fp = FingerprintGenerator::new().generate();
+        fp.timezone = "Asia/Tokyo".to_string();
+        fp.language = "ja-JP".to_string();
+        assert!(fp.location_issues("JP").is_empty());
+        let issues = fp.location_issues("US");
+        assert_eq!(issues.len(), 2);
+        assert_eq!(issues[0], "Proxy exits in US but timezone Asia/Tokyo is in JP");
+
+        // en-US is used worldwide, so only its timezone is judged
+        fp.language = "en-US".to_string();
+        assert_eq!(fp.location_issues("US").len(), 1);
+        assert!(fp.location_issues("JP").is_empty());
+
+        // Unknown zones and region-less languages say nothing
+        fp.timezone = "Pacific/Fiji".to_string();
+        fp.language = "ja".to_string();
+        assert!(fp.location_issues("US").is_empty());
+        assert_eq!(language_region("zh-Hans-CN").as_deref(), Some("CN"));
+    }
+
+    #[test]
+    fn test_validate_screen_size() {
+        for (width, height) in SCREEN_RESOLUTIONS {
+            assert!(validate_screen_size(*width, *height).is_ok());
+        }
+        assert!(validate_screen_size(MAX_SCREEN_DIMENSION, 4320).is_ok());
+        assert!(validate_screen_size(100000, 1080).unwrap_err().contains("out of range"));
+        assert!(validate_screen_size(1920, 0).is_err());
+        assert!(validate_screen_size(1080, 1920).unwrap_err().contains("portrait"));
+    }
+
+    #[test]
+    fn test_spread_timezones_within_country() {
+        // Every generator zone has a country, and the tz database knows them all
+        for tz in TIMEZONES {
+            assert!(timezone_country(tz).is_some(), "{}", tz);
+        }
+        for (tz, _, _) in TIMEZONE_COUNTRIES {
+            assert!(tz.parse::<Tz>().is_ok(), "{}", tz);
+        }
+
+        let mut generator = FingerprintGenerator::new();
+        let mut spread = generator.spread_timezones("us", 5);
+        spread.sort();
+        assert_eq!(
+            spread,
+            vec!["America/Chicago", "America/Chicago", "America/Los_Angeles", "America/New_York", "America/New_York"]
+        );
+        assert_eq!(generator.spread_timezones("JP", 2), vec!["Asia/Tokyo", "Asia/Tokyo"]);
+        assert!(generator.spread_timezones("ZZ", 3).is_empty());
+
+        for _ in 0..50 {
+            assert_eq!(timezone_country(generator.spread_timezone("America/Denver")), Some("US"));
+        }
+        assert_eq!(generator.spread_timezone("Pacific/Fiji"), "Pacific/Fiji");
+
+        // Without spreading, the minor zones are never drawn
+        for _ in 0..200 {
+            assert!(TIMEZONES.contains(&generator.pick_timezone()));
+        }
+
+        // Half-hour zones report their offset to the minute
+        assert_eq!(timezone_offset_at("America/St_Johns", utc(2024, 1, 15, 0)), 210);
+        assert_eq!(timezone_offset_at("Australia/Adelaide", utc(2024, 1, 15, 0)), -630);
+    }
+
+    #[test]
+    fn test_generation_seed_repeats_sequence() {
+        let dir = std::env::temp_dir().join(format!("identityforge-generation-seed-{}", std::process::id()));
+        std::fs::remove_dir_all(&dir).ok();
+        let db = Database::new(&dir.join("test.db"), dir.join("profiles")).unwrap();
+        let seeds = GenerationSeed::default();
+        let run = |count: usize| -> Vec<String> {
+            (0..count)
+                .map(|_| serde_json::to_string(&FingerprintGenerator::from_settings(&db, &seeds).generate()).unwrap())
+                .collect()
+        };
+
+        db.set_setting(GENERATION_SEED_SETTING, "42").unwrap();
+        let preview = serde_json::to_string(&FingerprintGenerator::for_preview(&db, &seeds).generate()).unwrap();
+        let first = run(3);
+        assert_eq!(preview, first[0]);
+        assert_ne!(first[0], first[1]);
+        seeds.restart();
+        assert_eq!(run(3), first);
+
+        // A different seed starts its own sequence, and unset is random again
+        db.set_setting(GENERATION_SEED_SETTING, "7").unwrap();
+        assert_ne!(run(1)[0], first[0]);
+        db.set_setting(GENERATION_SEED_SETTING, "").unwrap();
+        assert!(pinned_generation_seed(&db).is_none());
+
+        std::fs::remove_dir_all(&dir).ok();
+    }
+
+    #[test]
+    fn test_guess_proxy_country() {
+        assert_eq!(guess_proxy_country("us.proxy-provider.com").as_deref(), Some("US"));
+        assert_eq!(guess_proxy_country("proxy.example.fr.").as_deref(), Some("FR"));
+        assert_eq!(guess_proxy_country("gate.provider.io"), None);
+        assert_eq!(guess_proxy_country("203.0.113.7"), None);
+        assert_eq!(guess_proxy_country("localhost"), None);
+    }
+
+    #[test]
+    fn test_resolutions_match_platform() {
+        let implausible_on_mac = [(1366, 768), (1536, 864), (1280, 1024), (1600, 900), (1280, 720)];
+        let mut generator = FingerprintGenerator::new();
+        for _ in 0..200 {
+            let fp = generator.generate_for_platform("macos");
+            assert_eq!(fp.platform, "MacIntel");
+            let size = (fp.screen_width, fp.screen_height);
+            assert!(!implausible_on_mac.contains(&size), "{:?}", size);
+        }
+
+        for list in [WINDOWS_RESOLUTIONS, MAC_RESOLUTIONS, LINUX_RESOLUTIONS] {
+            assert!(list.iter().all(|size| SCREEN_RESOLUTIONS.contains(size)));
+        }
+    }
+
+    #[test]
+    fn test_canvas_noise_level_templating() {
+        let mut generator = FingerprintGenerator::new();
+        let mut fp = generator.generate();
+        assert_eq!(fp.canvas_noise_level, DEFAULT_CANVAS_NOISE_LEVEL);
+
+        fp.canvas_noise_level = "high".to_string();
+        let script = generate_spoof_script(&fp, "test-profile");
+        assert!(script.contains("const CANVAS_NOISE_MODULUS = 31;"));
+        assert!(script.contains("const CANVAS_NOISE_AMPLITUDE = 2;"));
+
+        fp.canvas_noise_level = "bogus".to_string();
+        let script = generate_spoof_script(&fp, "test-profile");
+        assert!(script.contains("const CANVAS_NOISE_MODULUS = 97;"));
+    }
+
+    #[test]
+    fn test_timezone_dst_offsets() {
+        // New York: EST in winter, EDT in summer, switching at 07:00 UTC on 2024-03-10
+        assert_eq!(timezone_offset_at("America/New_York", utc(2024, 1, 15, 12)), 300);
+        assert_eq!(timezone_offset_at("America/New_York", utc(2024, 7, 15, 12)), 240);
+        assert_eq!(timezone_offset_at("America/New_York", utc(2024, 3, 10, 7) - 1), 300);
+        assert_eq!(timezone_offset_at("America/New_York", utc(2024, 3, 10, 7)), 240);
+        assert_eq!(timezone_offset_at("America/New_York", utc(2024, 11, 3, 6)), 300);
+
+        // London: BST from 2024-03-31 01:00 UTC
+        assert_eq!(timezone_offset_at("Europe/London", utc(2024, 3, 31, 1) - 1), 0);
+        assert_eq!(timezone_offset_at("Europe/London", utc(2024, 3, 31, 1)), -60);
+        assert_eq!(timezone_offset_at("Europe/London", utc(2024, 10, 27, 1)), 0);
+
+        // Sydney: southern hemisphere, daylight time over New Year
+        assert_eq!(timezone_offset_at("Australia/Sydney", utc(2024, 1, 15, 0)), -660);
+        assert_eq!(timezone_offset_at("Australia/Sydney", utc(2024, 7, 15, 0)), -600);
+
+        // No DST
+        assert_eq!(timezone_offset_at("Asia/Tokyo", utc(2024, 7, 15, 0)), -540);
+        assert_eq!(timezone_offset_at("Unknown/Zone", utc(2024, 7, 15, 0)), 0);
+    }
+
+    #[test]
+    fn test_dst_transitions_for_every_zone() {
+        let mut fp = FingerprintGenerator::new().generate();
+        for zone in known_timezones() {
+            let (_, transitions) = timezone_transitions(zone);
+            let window: Vec<(i64, i32)> = transitions
+                .into_iter()
+                .filter(|(time, _)| (utc(2024, 1, 1, 0)..utc(2026, 1, 1, 0)).contains(time))
+                .collect();
+
+            let mut expected = Vec::new();
+            for &(time, after) in &window {
+                let before = timezone_offset_at(zone, time - 1);
+                assert_ne!(before, after, "{} at {}", zone, time);
+                assert_eq!(timezone_offset_at(zone, time), after, "{} at {}", zone, time);
+                expected.push(format!("{},{}", before, after));
+            }
+
+            // The script switches at the same instants
+            fp.timezone = zone.to_string();
+            let script = generate_spoof_script(&fp, "test-profile");
+            let times: Vec<String> = window.iter().map(|(time, _)| (time * 1000).to_string()).collect();
+            let probe = format!(
+                "[{}].map(t => new Date(t - 1000).getTimezoneOffset() + ',' + new Date(t).getTimezoneOffset()).join('|')",
+                times.join(", ")
+            );
+            assert_eq!(run_spoof_script(&script, &probe).unwrap(), expected.join("|"), "{}", zone);
+        }
+
+        // Zones that observe DST switch twice a year, the rest never
+        let count = |zone: &str| {
+            let (_, transitions) = timezone_transitions(zone);
+            transitions.iter().filter(|(time, _)| (utc(2024, 1, 1, 0)..utc(2025, 1, 1, 0)).contains(time)).count()
+        };
+        assert_eq!(count("America/New_York"), 2);
+        assert_eq!(count("Australia/Adelaide"), 2);
+        assert_eq!(count("Asia/Tokyo"), 0);
+        assert_eq!(count("America/Phoenix"), 0);
+        assert_eq!(timezone_transitions("Unknown/Zone"), (0, Vec::new()));
+    }
+
+    #[test]
+    fn test_js_heap_sizes() {
+        let seed = generate_persistent_seed("profile-123");
+        let (limit_high, total, used) = js_heap_sizes(16, seed);
+        let (limit_low, _, _) = js_heap_sizes(2, seed);
+
+        assert!(limit_high > limit_low);
+        assert!(used <= total && total <= limit_high);
+        assert_eq!(js_heap_sizes(16, seed), (limit_high, total, used));
+    }
+
+    #[test]
+    fn test_viewport_size() {
+        let mut generator = FingerprintGenerator::new();
+        let mut fp = generator.generate();
+        fp.screen_width = 1920;
+        fp.screen_height = 1080;
+
+        let seed_a = generate_persistent_seed("profile-a");
+        let (width, height) = viewport_size(&fp, seed_a);
+        assert!(width <= 1920 && width > 1900);
+        assert!(height < 1080 - 40);
+        assert_eq!(viewport_size(&fp, seed_a), (width, height));
+
+        fp.inner_width = Some(1500);
+        fp.inner_height = Some(800);
+        assert_eq!(viewport_size(&fp, seed_a), (1500, 800));
+    }
+
+    #[test]
+    fn test_generate_from_template() {
+        let template = crate::templates::builtin_templates()
+            .into_iter()
+            .find(|t| t.name == "German Mac Safari")
+            .unwrap();
+        let mut generator = FingerprintGenerator::new();
+        for _ in 0..20 {
+            let fp = generator.generate_from_template(&template, Some("linux"));
+            assert_eq!(fp.platform, "MacIntel");
+            assert_eq!(ua_browser_name(&fp.user_agent), "safari");
+            assert_eq!(fp.timezone, "Europe/Berlin");
+            assert_eq!(fp.language, "de-DE");
+            assert_eq!(fp.languages[..2], ["de-DE", "de"]);
+            assert!(fp.screen_width >= 1440);
+        }
+    }
+
+    #[test]
+    fn test_navigator_languages() {
+        let list = |l: &[&str]| l.iter().map(|s| s.to_string()).collect::<Vec<_>>();
+        assert_eq!(navigator_languages("en-US", &[]), list(&["en-US", "en"]));
+        assert_eq!(navigator_languages("ja", &[]), list(&["ja"]));
+        assert_eq!(
+            navigator_languages("fr-FR", &list(&["en-US", "fr-FR", "en"])),
+            list(&["fr-FR", "en-US", "en"])
+        );
+
+        let mut fp = FingerprintGenerator::new().generate();
+        fp.languages = list(&["de-DE", "de", "en-US", "en"]);
+        fp.language = "de-DE".to_string();
+        let script = generate_spoof_script(&fp, "test-profile");
+        assert!(script.contains(r#"Object.freeze(["de-DE","de","en-US","en"])"#));
+    }
+
+    #[test]
+    fn test_coherence_issues() {
+        let mut generator = FingerprintGenerator::new();
+        let mut fp = generator.generate_for_platform("windows");
+        fp.webgl_renderer = "ANGLE (Intel, Intel(R) UHD Graphics 630 Direct3D11 vs_5_0 ps_5_0, D3D11)".to_string();
+        assert!(fp.coherence_issues().is_empty());
+
+        fp.platform = "MacIntel".to_string();
+        fp.webgl_renderer = "Apple M1".to_string();
+        assert_eq!(fp.coherence_issues().len(), 1);
+
+        fp.platform = "Linux x86_64".to_string();
+        assert_eq!(fp.coherence_issues().len(), 2);
+    }
+
+    #[test]
+    fn test_fingerprint_diff() {
+        let mut generator = FingerprintGenerator::new();
+        let a = generator.generate();
+        let mut b = a.clone();
+        b.timezone = "Somewhere/Else".to_string();
+        b.proxy_enabled = true;
+        b.proxy_host = "10.0.0.1".to_string();
+
+        let differing: Vec<String> = a
+            .diff(&b)
+            .into_iter()
+            .filter(|d| !d.matches)
+            .map(|d| d.field)
+            .collect();
+        assert_eq!(differing, vec!["timezone", "proxy_host"]);
+    }
+
+    #[test]
+    fn test_webgl_limits() {
+        let angle = "ANGLE (NVIDIA, NVIDIA GeForce GTX 1080 Direct3D11 vs_5_0 ps_5_0, D3D11)";
+        assert_eq!(webgl_limits(angle, 0), (16384, 16));
+        assert_eq!(webgl_limits("Apple M1", u64::MAX), (16384, 16));
+
+        let nvidia = "NVIDIA GeForce RTX 3060/PCIe/SSE2";
+        let sizes: std::collections::HashSet<u32> = (0..3u64)
+            .map(|i| webgl_limits(nvidia, i << 50).0)
+            .collect();
+        assert_eq!(sizes.len(), 2);
+        let seed = generate_persistent_seed("profile-a");
+        assert_eq!(webgl_limits(nvidia, seed), webgl_limits(nvidia, seed));
+    }
+
+    #[test]
+    fn test_webgl2_limits() {
+        for (_, renderer) in WEBGL_CONFIGS {
+            for seed in [0, u64::MAX, generate_persistent_seed("profile-a")] {
+                let (max_texture_size, _) = webgl_limits(renderer, seed);
+                let limits = webgl2_limits(renderer, max_texture_size, seed);
+                assert_eq!(limits.len(), WEBGL2_LIMIT_PARAMS.len());
+                assert!(limits[&0x8073] <= max_texture_size as i64, "{}", renderer);
+                assert!(limits[&0x8A2E] >= limits[&0x8A2B].max(limits[&0x8A2D]));
+                assert!(limits[&0x8A2E] <= limits[&0x8A2F]);
+                assert!(limits[&0x8B4B] <= limits[&0x9122]);
+                // Combined = uniform blocks (in components) + default block
+                let block_components = limits[&0x8A30] / 4;
+                assert_eq!(limits[&0x8A31], limits[&0x8A2B] * block_components + limits[&0x8B4A], "{}", renderer);
+                assert_eq!(limits[&0x8A33], limits[&0x8A2D] * block_components + limits[&0x8B49], "{}", renderer);
+                assert!((limits[&0x8A34] as u64).is_power_of_two());
+                assert!(limits[&0x8904] < 0 && limits[&0x8905] > 0);
+                assert_eq!(limits, webgl2_limits(renderer, max_texture_size, seed));
+            }
+        }
+
+        // Only WebGL2 contexts answer the extra parameters
+        let mut fp = FingerprintGenerator::new().generate();
+        fp.webgl_renderer = WEBGL_CONFIGS
+            .iter()
+            .map(|(_, renderer)| *renderer)
+            .find(|renderer| renderer.starts_with("ANGLE (NVIDIA"))
+            .unwrap()
+            .to_string();
+        let script = generate_spoof_script(&fp, "test-profile");
+        let probe = "[new WebGL2RenderingContext().getParameter(0x8073), new WebGLRenderingContext().getParameter(0x8073), \
+                     new WebGL2RenderingContext().getParameter(0x8904), new WebGL2RenderingContext().getParameter(37446)].join('|')";
+        assert_eq!(run_spoof_script(&script, probe).unwrap(), format!("2048||-8|{}", fp.webgl_renderer));
+        // Uniform vectors are the spoofed components in vec4s, in both contexts
+        let probe = "[new WebGLRenderingContext().getParameter(0x8DFB), new WebGL2RenderingContext().getParameter(0x8DFD)].join('|')";
+        assert_eq!(run_spoof_script(&script, probe).unwrap(), "4096|1024");
+    }
+
+    #[test]
+    fn test_age_user_agent() {
+        let chrome = "Mozilla/5.0 (X11; Linux x86_64) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/119.0.0.0 Safari/537.36";
+        assert_eq!(
+            age_user_agent(chrome).as_deref(),
+            Some("Mozilla/5.0 (X11; Linux x86_64) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/120.0.0.0 Safari/537.36")
+        );
+
+        let firefox = "Mozilla/5.0 (Windows NT 10.0; Win64; x64; rv:120.0) Gecko/20100101 Firefox/120.0";
+        assert_eq!(
+            age_user_agent(firefox).as_deref(),
+            Some("Mozilla/5.0 (Windows NT 10.0; Win64; x64; rv:121.0) Gecko/20100101 Firefox/121.0")
+        );
+
+        // Newest known version and non-Chrome/Firefox browsers stay put
+        assert!(age_user_agent(&firefox.replace("120.0", "121.0")).is_none());
+        assert!(age_user_agent("Mozilla/5.0 (Macintosh; Intel Mac OS X 10_15_7) AppleWebKit/605.1.15 (KHTML, like Gecko) Version/17.2 Safari/605.1.15").is_none());
+    }
+
+    #[test]
+    fn test_spoof_script_version() {
+        let mut generator = FingerprintGenerator::new();
+        let fp = generator.generate();
+
+        let version = spoof_script_version(&generate_spoof_script(&fp, "profile-a"));
+        assert_eq!(version.len(), 12);
+        assert_eq!(version, spoof_script_version(&generate_spoof_script(&fp, "profile-a")));
+        assert_ne!(version, spoof_script_version(&generate_spoof_script(&fp, "profile-b")));
+    }
+
+    #[test]
+    fn test_fingerprint_options() {
+        let options = fingerprint_options();
+        assert_eq!(options.platforms, vec!["Win32", "MacIntel", "Linux x86_64"]);
+        assert_eq!(options.timezones.len(), TIMEZONE_COUNTRIES.len());
+        assert_eq!(options.webgl_configs.len(), WEBGL_CONFIGS.len());
+
+        let mut generator = FingerprintGenerator::new();
+        for platform in &options.platforms {
+            assert_eq!(&generator.generate_for_platform(platform).platform, platform);
+            assert!(is_known_platform(platform));
+        }
+        assert!(is_known_platform("Windows"));
+        assert!(!is_known_platform("android"));
     }
 }
@@ -0,0 +1,456 @@
+use crate::app_proxy;
+use crate::database::ProxyConfig;
+use serde::Serialize;
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::sync::Notify;
+use tokio::task::JoinSet;
+use url::Url;
+
+/// Setting key: connections to the upstream proxy each forwarder keeps open
+/// ahead of need, saving a round trip (two for SOCKS5) per new tunnel. 0 = none.
+pub const PROXY_POOL_SIZE_SETTING: &str = "proxy_pool_size";
+
+pub const DEFAULT_PROXY_POOL_SIZE: usize = 2;
+
+pub const MAX_PROXY_POOL_SIZE: usize = 16;
+
+/// Pooled connections are replaced after this long, before proxies give up
+/// waiting for a request on them
+const POOL_IDLE_LIMIT: Duration = Duration::from_secs(20);
+
+/// Longest wait after repeated failures to accept a webview connection
+const MAX_ACCEPT_BACKOFF: Duration = Duration::from_secs(1);
+
+/// Longest request head accepted from the webview
+const MAX_REQUEST_HEAD: usize = 16 * 1024;
+
+/// Traffic through one forwarder since it started
+#[derive(Debug, Clone, Serialize)]
+pub struct ForwarderStats {
+    pub port: u16,
+    /// Webview connections being relayed now
+    pub active_connections: usize,
+    pub total_connections: u64,
+    /// Upstream connections open and waiting in the pool
+    pub pooled_connections: usize,
+    /// Tunnels that started on a pooled connection
+    pub pool_hits: u64,
+    /// Bytes from the webview to the proxy
+    pub bytes_sent: u64,
+    /// Bytes from the proxy to the webview
+    pub bytes_received: u64,
+}
+
+/// State shared by a forwarder's tasks
+struct Shared {
+    upstream: ProxyConfig,
+    pool_size: usize,
+    pool: Mutex<Vec<(TcpStream, Instant)>>,
+    refill: Notify,
+    active_connections: AtomicUsize,
+    total_connections: AtomicU64,
+    pool_hits: AtomicU64,
+    bytes_sent: AtomicU64,
+    bytes_received: AtomicU64,
+}
+
+impl Shared {
+    fn new(upstream: ProxyConfig, pool_size: usize) -> Self {
+        Shared {
+            upstream,
+            pool_size: pool_size.min(MAX_PROXY_POOL_SIZE),
+            pool: Mutex::new(Vec::new()),
+            refill: Notify::new(),
+            active_connections: AtomicUsize::new(0),
+            total_connections: AtomicU64::new(0),
+            pool_hits: AtomicU64::new(0),
+            bytes_sent: AtomicU64::new(0),
+            bytes_received: AtomicU64::new(0),
+        }
+    }
+
+    /// A pooled connection young enough to use, if any
+    fn take_pooled(&self) -> Option<TcpStream> {
+        let mut pool = self.pool.lock().unwrap();
+        pool.retain(|(_, opened)| opened.elapsed() < POOL_IDLE_LIMIT);
+        let stream = pool.pop().map(|(stream, _)| stream);
+        drop(pool);
+        self.refill.notify_one();
+        stream
+    }
+}
+
+/// Counts a relayed connection as active until dropped, even when aborted
+struct ActiveGuard<'a>(&'a AtomicUsize);
+
+impl Drop for ActiveGuard<'_> {
+    fn drop(&mut self) {
+        self.0.fetch_sub(1, Ordering::Relaxed);
+    }
+}
+
+/// Loopback HTTP proxy serving one profile window, which tunnels every
+/// connection through the profile's proxy. Webviews are only handed a proxy
+/// host and port, so this is how an authenticated proxy gets its
+/// credentials, and how every webview reaches SOCKS5 and HTTP proxies alike.
+/// Stops, closing its connections, when dropped.
+///
+/// The forwarder itself asks for no credentials (webviews couldn't supply
+/// them), so while it runs any local process that finds its port can use the
+/// profile's proxy account. It only listens on 127.0.0.1.
+pub struct Forwarder {
+    port: u16,
+    shared: Arc<Shared>,
+    task: tauri::async_runtime::JoinHandle<()>,
+}
+
+impl Forwarder {
+    /// Listen on a free loopback port and forward to `upstream`, keeping up
+    /// to `pool_size` upstream connections ready
+    pub fn start(upstream: ProxyConfig, pool_size: usize) -> std::io::Result<Self> {
+        let listener = std::net::TcpListener::bind(("127.0.0.1", 0))?;
+        listener.set_nonblocking(true)?;
+        let port = listener.local_addr()?.port();
+        let shared = Arc::new(Shared::new(upstream, pool_size));
+        let task = tauri::async_runtime::spawn({
+            let shared = shared.clone();
+            async move {
+                match TcpListener::from_std(listener) {
+                    Ok(listener) => serve(listener, shared).await,
+                    Err(e) => log::warn!("Proxy forwarder on port {} failed to start: {}", port, e),
+                }
+            }
+        });
+        Ok(Forwarder { port, shared, task })
+    }
+
+    pub fn stats(&self) -> ForwarderStats {
+        let shared = &self.shared;
+        ForwarderStats {
+            port: self.port,
+            active_connections: shared.active_connections.load(Ordering::Relaxed),
+            total_connections: shared.total_connections.load(Ordering::Relaxed),
+            pooled_connections: shared.pool.lock().unwrap().len(),
+            pool_hits: shared.pool_hits.load(Ordering::Relaxed),
+            bytes_sent: shared.bytes_sent.load(Ordering::Relaxed),
+            bytes_received: shared.bytes_received.load(Ordering::Relaxed),
+        }
+    }
+
+    /// Proxy URL to give the webview
+    pub fn url(&self) -> Url {
+        Url::parse(&format!("http://127.0.0.1:{}", self.port)).expect("loopback URL")
+    }
+}
+
+impl Drop for Forwarder {
+    fn drop(&mut self) {
+        self.task.abort();
+    }
+}
+
+/// Accept webview connections and top the pool up as it's used until the
+/// task is aborted, which drops (and so aborts) every connection still open
+async fn serve(listener: TcpListener, shared: Arc<Shared>) {
+    let accept = async {
+        let mut connections = JoinSet::new();
+        let mut failures = 0;
+        loop {
+            let client = match listener.accept().await {
+                Ok((client, _)) => {
+                    failures = 0;
+                    client
+                }
+                // e.g. out of file descriptors, which fails again at once
+                Err(e) => {
+                    failures += 1;
+                    log::warn!("Proxy forwarder could not accept a connection: {}", e);
+                    tokio::time::sleep(accept_backoff(failures)).await;
+                    continue;
+                }
+            };
+            while connections.try_join_next().is_some() {}
+            let shared = shared.clone();
+            connections.spawn(async move {
+                shared.active_connections.fetch_add(1, Ordering::Relaxed);
+                shared.total_connections.fetch_add(1, Ordering::Relaxed);
+                let _active = ActiveGuard(&shared.active_connections);
+                if let Err(e) = forward(client, &shared).await {
+                    log::debug!("Proxy forwarder connection ended: {}", e);
+                }
+            });
+        }
+    };
+    tokio::join!(accept, fill_pool(&shared));
+}
+
+/// Wait before accepting again after `failures` failed accepts in a row:
+/// 10 ms, doubling up to `MAX_ACCEPT_BACKOFF`
+fn accept_backoff(failures: u32) -> Duration {
+    let shift = failures.saturating_sub(1).min(10);
+    (Duration::from_millis(10) * (1 << shift)).min(MAX_ACCEPT_BACKOFF)
+}
+
+/// Fill the pool on start and top it up each time a tunnel is opened. Stale
+/// connections are dropped but not replaced, so an idle window's pool drains
+/// instead of holding proxy connections open indefinitely. A failed connect
+/// waits for the next tunnel rather than retrying at once.
+async fn fill_pool(shared: &Shared) {
+    if shared.pool_size == 0 {
+        return;
+    }
+    loop {
+        shared.pool.lock().unwrap().retain(|(_, opened)| opened.elapsed() < POOL_IDLE_LIMIT);
+        while shared.pool.lock().unwrap().len() < shared.pool_size {
+            match app_proxy::open(&shared.upstream).await {
+                Ok(stream) => shared.pool.lock().unwrap().push((stream, Instant::now())),
+                Err(e) => {
+                    log::debug!("Proxy forwarder could not pre-open a connection: {}", e);
+                    break;
+                }
+            }
+        }
+        loop {
+            tokio::select! {
+                _ = shared.refill.notified() => break,
+                _ = tokio::time::sleep(POOL_IDLE_LIMIT / 2) => {
+                    shared.pool.lock().unwrap().retain(|(_, opened)| opened.elapsed() < POOL_IDLE_LIMIT);
+                }
+            }
+        }
+    }
+}
+
+/// A tunnel to `host:port`, on a pooled connection when one works
+async fn open_tunnel(shared: &Shared, host: &str, port: u16) -> std::io::Result<TcpStream> {
+    if let Some(mut stream) = shared.take_pooled() {
+        match app_proxy::tunnel(&mut stream, &shared.upstream, host, port).await {
+            Ok(()) => {
+                shared.pool_hits.fetch_add(1, Ordering::Relaxed);
+                return Ok(stream);
+            }
+            // The proxy may have closed it; a fresh connection decides
+            Err(e) => log::debug!("Pooled proxy connection failed, opening another: {}", e),
+        }
+    }
+    app_proxy::connect(Some(&shared.upstream), host, port).await
+}
+
+/// A parsed request head from the webview
+#[derive(Debug, PartialEq)]
+struct Request {
+    host: String,
+    port: u16,
+    /// Bytes to send once the tunnel is up; empty for `CONNECT`
+    rewritten: Vec<u8>,
+}
+
+/// Turn a proxy request head into its target and what to send through the
+/// tunnel: nothing for `CONNECT`, else the request in origin form with the
+/// hop-by-hop proxy headers removed and the connection closed after one
+/// response, since the next request may be for another host
+fn parse_request(head: &str) -> Result<Request, String> {
+    let mut lines = head.split("\r\n");
+    let request_line = lines.next().unwrap_or_default();
+    let mut parts = request_line.split(' ');
+    let (Some(method), Some(target), Some(version)) = (parts.next(), parts.next(), parts.next()) else {
+        return Err(format!("malformed request line '{}'", request_line));
+    };
+
+    if method.eq_ignore_ascii_case("CONNECT") {
+        let url = Url::parse(&format!("http://{}", target)).map_err(|e| format!("bad CONNECT target: {}", e))?;
+        let host = url.host_str().ok_or("CONNECT target has no host")?;
+        let port = url.port().ok_or("CONNECT target has no port")?;
+        return Ok(Request {
+            host: host.trim_start_matches('[').trim_end_matches(']').to_string(),
+            port,
+            rewritten: Vec::new(),
+        });
+    }
+
+    let url = Url::parse(target).map_err(|e| format!("bad request target '{}': {}", target, e))?;
+    if url.scheme() != "http" {
+        return Err(format!("unsupported scheme '{}'", url.scheme()));
+    }
+    let host = url.host_str().ok_or("request target has no host")?;
+    let mut path = url.path().to_string();
+    if let Some(query) = url.query() {
+        path.push('?');
+        path.push_str(query);
+    }
+
+    let mut rewritten = format!("{} {} {}\r\n", method, path, version);
+    for line in lines.filter(|line| !line.is_empty()) {
+        let name = line.split(':').next().unwrap_or_default().trim();
+        let hop_by_hop = ["proxy-authorization", "proxy-connection", "connection", "keep-alive"]
+            .iter()
+            .any(|h| name.eq_ignore_ascii_case(h));
+        if !hop_by_hop {
+            rewritten.push_str(line);
+            rewritten.push_str("\r\n");
+        }
+    }
+    rewritten.push_str("Connection: close\r\n\r\n");
+
+    Ok(Request {
+        host: host.trim_start_matches('[').trim_end_matches(']').to_string(),
+        port: url.port_or_known_default().unwrap_or(80),
+        rewritten: rewritten.into_bytes(),
+    })
+}
+
+/// Serve one webview connection: read its request head, open a tunnel
+/// through the upstream proxy and relay bytes both ways
+async fn forward(mut client: TcpStream, shared: &Shared) -> std::io::Result<()> {
+    let mut head = Vec::new();
+    while !head.ends_with(b"\r\n\r\n") {
+        if head.len() >= MAX_REQUEST_HEAD {
+            return reply(&mut client, "431 Request Header Fields Too Large").await;
+        }
+        head.push(client.read_u8().await?);
+    }
+
+    let request = match parse_request(&String::from_utf8_lossy(&head)) {
+        Ok(request) => request,
+        Err(_) => return reply(&mut client, "400 Bad Request").await,
+    };
+    let mut tunnel = match open_tunnel(shared, &request.host, request.port).await {
+        Ok(tunnel) => tunnel,
+        Err(e) => {
+            log::warn!("Proxy forwarder could not reach {}:{}: {}", request.host, request.port, e);
+            return reply(&mut client, "502 Bad Gateway").await;
+        }
+    };
+
+    if request.rewritten.is_empty() {
+        client.write_all(b"HTTP/1.1 200 Connection Established\r\n\r\n").await?;
+    } else {
+        tunnel.write_all(&request.rewritten).await?;
+    }
+    let (client_read, client_write) = client.split();
+    let (tunnel_read, tunnel_write) = tunnel.split();
+    tokio::try_join!(
+        relay(client_read, tunnel_write, &shared.bytes_sent),
+        relay(tunnel_read, client_write, &shared.bytes_received),
+    )?;
+    Ok(())
+}
+
+/// Copy until `from` ends, then end `to`, counting bytes as they pass so
+/// long-lived tunnels show up in the stats
+async fn relay(
+    mut from: impl AsyncRead + Unpin,
+    mut to: impl AsyncWrite + Unpin,
+    counter: &AtomicU64,
+) -> std::io::Result<()> {
+    let mut buffer = vec![0u8; 16 * 1024];
+    loop {
+        let read = from.read(&mut buffer).await?;
+        if read == 0 {
+            return to.shutdown().await;
+        }
+        to.write_all(&buffer[..read]).await?;
+        counter.fetch_add(read as u64, Ordering::Relaxed);
+    }
+}
+
+async fn reply(client: &mut TcpStream, status: &str) -> std::io::Result<()> {
+    let response = format!("HTTP/1.1 {}\r\nContent-Length: 0\r\nConnection: close\r\n\r\n", status);
+    client.write_all(response.as_bytes()).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_request() {
+        let connect = parse_request("CONNECT example.com:443 HTTP/1.1\r\nHost: example.com:443\r\n\r\n").unwrap();
+        assert_eq!((connect.host.as_str(), connect.port), ("example.com", 443));
+        assert!(connect.rewritten.is_empty());
+        let ipv6 = parse_request("CONNECT [::1]:8443 HTTP/1.1\r\n\r\n").unwrap();
+        assert_eq!((ipv6.host.as_str(), ipv6.port), ("::1", 8443));
+
+        let get = parse_request(
+            "GET http://example.com/a?b=1 HTTP/1.1\r\nHost: example.com\r\n\
+             Proxy-Authorization: Basic eA==\r\nProxy-Connection: keep-alive\r\nAccept: */*\r\n\r\n",
+        )
+        .unwrap();
+        assert_eq!((get.host.as_str(), get.port), ("example.com", 80));
+        assert_eq!(
+            String::from_utf8(get.rewritten).unwrap(),
+            "GET /a?b=1 HTTP/1.1\r\nHost: example.com\r\nAccept: */*\r\nConnection: close\r\n\r\n"
+        );
+
+        assert!(parse_request("GET /relative HTTP/1.1\r\n\r\n").is_err());
+        assert!(parse_request("CONNECT example.com HTTP/1.1\r\n\r\n").is_err());
+        assert!(parse_request("garbage\r\n\r\n").is_err());
+    }
+
+    #[test]
+    fn test_accept_backoff() {
+        assert_eq!(accept_backoff(1), Duration::from_millis(10));
+        assert_eq!(accept_backoff(3), Duration::from_millis(40));
+        assert_eq!(accept_backoff(8), MAX_ACCEPT_BACKOFF);
+        assert_eq!(accept_backoff(u32::MAX), MAX_ACCEPT_BACKOFF);
+    }
+
+    #[test]
+    fn test_forwarder_tunnels_through_upstream() {
+        let runtime = tokio::runtime::Builder::new_current_thread().enable_all().build().unwrap();
+        runtime.block_on(async {
+            // Upstream HTTP proxy that requires credentials, then echoes one line
+            let upstream = TcpListener::bind("127.0.0.1:0").await.unwrap();
+            let upstream_port = upstream.local_addr().unwrap().port();
+            tokio::spawn(async move {
+                let (mut socket, _) = upstream.accept().await.unwrap();
+                let mut head = Vec::new();
+                while !head.ends_with(b"\r\n\r\n") {
+                    head.push(socket.read_u8().await.unwrap());
+                }
+                let head = String::from_utf8(head).unwrap();
+                assert!(head.starts_with("CONNECT example.com:443 HTTP/1.1\r\n"));
+                assert!(head.contains("Proxy-Authorization: Basic dTpw\r\n"));
+                socket.write_all(b"HTTP/1.1 200 OK\r\n\r\n").await.unwrap();
+                let mut ping = [0u8; 4];
+                socket.read_exact(&mut ping).await.unwrap();
+                socket.write_all(&ping).await.unwrap();
+            });
+
+            let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+            let port = listener.local_addr().unwrap().port();
+            let proxy = ProxyConfig {
+                enabled: true,
+                proxy_type: "http".to_string(),
+                host: "127.0.0.1".to_string(),
+                port: upstream_port as i32,
+                username: Some("u".to_string()),
+                password: Some("p".to_string()),
+                proxy_bypass: Vec::new(),
+            };
+            let shared = Arc::new(Shared::new(proxy, 1));
+            tokio::spawn(serve(listener, shared.clone()));
+            while shared.pool.lock().unwrap().is_empty() {
+                tokio::time::sleep(Duration::from_millis(5)).await;
+            }
+
+            // What the webview sends: an unauthenticated CONNECT to the loopback port
+            let mut client = TcpStream::connect(("127.0.0.1", port)).await.unwrap();
+            client.write_all(b"CONNECT example.com:443 HTTP/1.1\r\n\r\n").await.unwrap();
+            let mut established = [0u8; 39];
+            client.read_exact(&mut established).await.unwrap();
+            assert_eq!(&established, b"HTTP/1.1 200 Connection Established\r\n\r\n");
+            client.write_all(b"ping").await.unwrap();
+            let mut pong = [0u8; 4];
+            client.read_exact(&mut pong).await.unwrap();
+            assert_eq!(&pong, b"ping");
+            assert_eq!(shared.pool_hits.load(Ordering::Relaxed), 1);
+            assert_eq!(shared.bytes_sent.load(Ordering::Relaxed), 4);
+            assert_eq!(shared.active_connections.load(Ordering::Relaxed), 1);
+        });
+    }
+}
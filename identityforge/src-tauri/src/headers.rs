@@ -0,0 +1,105 @@
+use std::collections::BTreeMap;
+
+/// Connection-scoped headers that must not be forwarded (RFC 9110 §7.6.1)
+const HOP_BY_HOP_HEADERS: &[&str] = &[
+    "connection",
+    "keep-alive",
+    "proxy-authenticate",
+    "proxy-authorization",
+    "proxy-connection",
+    "te",
+    "trailer",
+    "transfer-encoding",
+    "upgrade",
+];
+
+/// Headers the browser owns; pages can't set them, so pinning them would be a silent no-op
+const BROWSER_CONTROLLED_HEADERS: &[&str] = &[
+    "accept-charset",
+    "accept-encoding",
+    "access-control-request-headers",
+    "access-control-request-method",
+    "content-length",
+    "cookie",
+    "cookie2",
+    "date",
+    "dnt",
+    "expect",
+    "host",
+    "origin",
+    "referer",
+    "via",
+];
+
+/// RFC 9110 `tchar`
+fn is_token_char(c: char) -> bool {
+    c.is_ascii_alphanumeric() || "!#$%&'*+-.^_`|~".contains(c)
+}
+
+/// Check a single header name against RFC token rules and the deny lists.
+/// All `Sec-*` names are refused, client hints included: pages can't set them,
+/// so the app's windows would drop them and only automation exports would send them.
+fn validate_header_name(name: &str) -> Result<(), String> {
+    if name.is_empty() || !name.chars().all(is_token_char) {
+        return Err(format!("'{}' is not a valid header name", name));
+    }
+
+    let lower = name.to_ascii_lowercase();
+    if HOP_BY_HOP_HEADERS.contains(&lower.as_str()) {
+        return Err(format!("'{}' is a hop-by-hop header", name));
+    }
+    if BROWSER_CONTROLLED_HEADERS.contains(&lower.as_str())
+        || lower.starts_with("sec-")
+        || lower.starts_with("proxy-")
+    {
+        return Err(format!("'{}' is controlled by the browser and can't be overridden", name));
+    }
+    Ok(())
+}
+
+/// Validate a profile's custom header map. Names are case-insensitive, so
+/// two entries differing only in case are rejected as duplicates.
+pub fn validate_custom_headers(headers: &BTreeMap<String, String>) -> Result<(), String> {
+    let mut seen = Vec::with_capacity(headers.len());
+    for (name, value) in headers {
+        validate_header_name(name)?;
+        if value.chars().any(|c| c == '\r' || c == '\n' || c == '\0') {
+            return Err(format!("Value for '{}' contains a line break or NUL", name));
+        }
+
+        let lower = name.to_ascii_lowercase();
+        if seen.contains(&lower) {
+            return Err(format!("Header '{}' is set more than once", name));
+        }
+        seen.push(lower);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn headers(pairs: &[(&str, &str)]) -> BTreeMap<String, String> {
+        pairs.iter().map(|(k, v)| (k.to_string(), v.to_string())).collect()
+    }
+
+    #[test]
+    fn test_validate_custom_headers() {
+        assert!(validate_custom_headers(&headers(&[
+            ("X-Requested-With", "XMLHttpRequest"),
+            ("Accept-Language", "de-DE,de;q=0.9"),
+        ]))
+        .is_ok());
+
+        assert!(validate_custom_headers(&headers(&[("Bad Header", "x")])).is_err());
+        assert!(validate_custom_headers(&headers(&[("X-Ok", "a\r\nInjected: 1")])).is_err());
+        assert!(validate_custom_headers(&headers(&[("X-A", "1"), ("x-a", "2")])).is_err());
+
+        let err = validate_custom_headers(&headers(&[("Transfer-Encoding", "chunked")])).unwrap_err();
+        assert!(err.contains("hop-by-hop"));
+        assert!(validate_custom_headers(&headers(&[("Sec-CH-UA-Platform-Version", "\"15.0.0\"")])).is_err());
+        let err = validate_custom_headers(&headers(&[("Sec-Fetch-Site", "none")])).unwrap_err();
+        assert!(err.contains("browser"));
+    }
+}
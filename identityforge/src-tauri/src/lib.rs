@@ -1,9 +1,23 @@
+mod aging;
+mod app_proxy;
+mod audit;
+mod automation_export;
 mod commands;
+mod cookies;
+mod data_location;
 mod database;
 mod fingerprint;
+mod forwarder;
+mod headers;
 mod launcher;
+mod migrations;
+mod profile_csv;
+mod proxy_list;
+mod selftest;
+mod snapshots;
+mod templates;
 
-use tauri::{Manager, WindowEvent};
+use tauri::{Manager, RunEvent, WindowEvent};
 
 use commands::AppState;
 use database::Database;
@@ -24,28 +38,49 @@ pub fn run() {
                 )?;
             }
 
-            // Initialize database
-            let app_data_dir = app
+            // Initialize database, in the relocated data directory if one is set
+            let default_data_dir = app
                 .path()
                 .app_data_dir()
                 .expect("Failed to get app data directory");
-            
-            let db_path = app_data_dir.join("identityforge.db");
-            let profiles_dir = app_data_dir.join("profiles");
+            let data_dir = data_location::resolve_data_dir(
+                &default_data_dir,
+                std::env::var_os(data_location::DATA_DIR_ENV),
+            );
+
+            let db_path = data_dir.join(data_location::DB_FILE);
+            let profiles_dir = data_dir.join(data_location::PROFILES_DIR);
 
             log::info!("Database path: {:?}", db_path);
             log::info!("Profiles directory: {:?}", profiles_dir);
 
-            let db = Database::new(&db_path, profiles_dir)
-                .expect("Failed to initialize database");
+            let db = Arc::new(
+                Database::new(&db_path, profiles_dir).expect("Failed to initialize database"),
+            );
+            data_location::remove_previous_location(&db, &data_dir);
+
+            // Periodically age fingerprints if auto_age_interval_days is set
+            tauri::async_runtime::spawn(aging::run_scheduler(db.clone()));
 
-            // Initialize launcher
-            let launcher = BrowserLauncher::new();
+            // Initialize launcher, clearing stale launch state and adopting any
+            // profile windows Tauri restored before setup ran
+            let launcher = Arc::new(BrowserLauncher::new());
+            launcher.reconcile(app.handle());
+
+            // Close windows left idle past idle_timeout_minutes
+            tauri::async_runtime::spawn(launcher::run_idle_monitor(
+                app.handle().clone(),
+                db.clone(),
+                launcher.clone(),
+            ));
 
             // Create app state
             let state = AppState {
-                db: Arc::new(db),
-                launcher: Arc::new(launcher),
+                db,
+                launcher,
+                bulk_operations: Default::default(),
+                data_dir,
+                generation_seed: Default::default(),
             };
 
             // Manage state
@@ -53,54 +88,155 @@ pub fn run() {
 
             Ok(())
         })
+        // Console batches posted by captured profile windows
+        .register_uri_scheme_protocol(launcher::CONSOLE_PROTOCOL, |ctx, request| {
+            if let Some(state) = ctx.app_handle().try_state::<AppState>() {
+                state
+                    .launcher
+                    .receive_console_report(ctx.app_handle(), ctx.webview_label(), request.body());
+            }
+            tauri::http::Response::builder()
+                .status(204)
+                .header("Access-Control-Allow-Origin", "*")
+                .body(Vec::new())
+                .unwrap()
+        })
         .invoke_handler(tauri::generate_handler![
             // Profile commands
             commands::get_profiles,
             commands::get_profile,
+            commands::toggle_favorite,
+            commands::reorder_profiles,
             commands::create_profile,
+            commands::create_system_profile,
             commands::update_profile,
+            commands::set_profile_proxy_url,
+            commands::get_profile_proxy_url,
+            commands::set_default_url_for_tag,
             commands::delete_profile,
             commands::delete_all_inactive_profiles,
             commands::bulk_create_profiles,
+            commands::dry_run_bulk_create,
+            commands::cancel_bulk_operation,
+            commands::precreate_profile_dirs,
+            commands::reconcile_profile_dirs,
             commands::regenerate_fingerprint,
+            commands::bulk_regenerate_fingerprints,
+            commands::spread_timezones_for_tag,
+            commands::set_custom_headers,
+            commands::age_fingerprint,
+            commands::find_duplicate_fingerprints,
+            commands::check_against_blocklist,
+            commands::diff_profiles,
+            commands::describe_identity,
+            commands::audit_profiles,
+            commands::export_profiles_csv,
+            commands::import_profiles_csv,
             // Launcher commands
             commands::launch_profile,
+            commands::launch_profiles,
+            commands::lease_profile,
+            commands::release_profile,
             commands::close_profile_window,
             commands::get_active_profiles,
+            commands::get_active_window_details,
+            commands::get_forwarder_stats,
+            commands::suspend_profile,
+            commands::resume_profile,
+            commands::start_profile_console_capture,
+            commands::stop_profile_console_capture,
             commands::navigate_profile,
+            commands::get_profile_public_ip,
+            commands::guess_proxy_location,
+            commands::profile_eval,
+            commands::profile_click,
+            commands::profile_type,
+            commands::reveal_profile_data_dir,
+            commands::verify_profile_isolation,
+            commands::run_fingerprint_self_test,
             // Cookie commands
             commands::export_cookies,
+            commands::export_cookies_format,
             commands::import_cookies,
+            commands::validate_cookies,
+            commands::merge_cookies,
             commands::clear_cookies,
+            commands::purge_expired_cookies,
+            commands::flush_profile_cookies,
+            // Session snapshot commands
+            commands::snapshot_session,
+            commands::restore_session,
+            commands::list_session_snapshots,
+            commands::delete_session_snapshot,
             // Settings commands
             commands::get_setting,
             commands::set_setting,
+            commands::export_config,
+            commands::import_config,
             // Utility commands
             commands::preview_fingerprint,
+            commands::get_fingerprint_options,
+            commands::parse_proxy_list,
+            commands::get_fingerprint_templates,
+            commands::save_fingerprint_template,
+            commands::delete_fingerprint_template,
+            commands::preview_spoof_script,
+            commands::export_spoof_script,
+            commands::preview_user_agent_data,
+            commands::export_automation_config,
+            commands::spoof_script_version,
+            commands::get_engine_info,
+            commands::get_data_dir,
+            commands::relocate_data_dir,
+            commands::check_database_integrity,
         ])
         .on_window_event(|window, event| {
-            // Handle window close events for profile windows
-            if let WindowEvent::CloseRequested { .. } = event {
-                let label = window.label();
-                
-                // Check if this is a profile window (starts with "profile_")
-                if label.starts_with("profile_") {
-                    // Extract profile_id from window label
-                    // Label format: "profile_{uuid_with_underscores}"
-                    let profile_id = label
-                        .strip_prefix("profile_")
-                        .map(|s| s.replace("_", "-"))
-                        .unwrap_or_default();
-                    
-                    log::info!("Profile window closed: {}", profile_id);
-                    
-                    // Get the launcher from app state and remove the window
-                    if let Some(state) = window.try_state::<AppState>() {
-                        state.launcher.on_window_closed(&profile_id);
+            // Check if this is a profile window (label "profile_{uuid_with_underscores}")
+            let Some(profile_id) = launcher::profile_id_from_label(window.label()) else {
+                return;
+            };
+            let Some(state) = window.try_state::<AppState>() else {
+                return;
+            };
+
+            match event {
+                // Handle window close events for profile windows
+                WindowEvent::CloseRequested { .. } => {
+                    state.launcher.on_window_closed(&profile_id);
+                }
+                WindowEvent::Focused(false)
+                    if state
+                        .db
+                        .get_setting_or(launcher::AUTO_SUSPEND_ON_BLUR_SETTING, false) =>
+                {
+                    if let Err(e) = state.launcher.suspend_profile(window.app_handle(), &profile_id) {
+                        log::warn!("Failed to auto-suspend profile {}: {}", profile_id, e);
                     }
                 }
+                _ => {}
             }
         })
-        .run(tauri::generate_context!())
-        .expect("error while running tauri application");
+        .build(tauri::generate_context!())
+        .expect("error while building tauri application")
+        .run(|app, event| {
+            // Hold the first exit request until open profiles' cookies are on disk;
+            // the app.exit below raises a second request, which goes through
+            let RunEvent::ExitRequested { code, api, .. } = &event else {
+                return;
+            };
+            let Some(state) = app.try_state::<AppState>() else {
+                return;
+            };
+            if state.launcher.active_count() == 0 || !state.launcher.begin_shutdown() {
+                return;
+            }
+
+            api.prevent_exit();
+            let code = code.unwrap_or(0);
+            let (app, db, launcher) = (app.clone(), state.db.clone(), state.launcher.clone());
+            tauri::async_runtime::spawn(async move {
+                launcher::flush_all_cookies(app.clone(), db, launcher).await;
+                app.exit(code);
+            });
+        });
 }
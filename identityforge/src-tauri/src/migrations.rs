@@ -0,0 +1,233 @@
+use rusqlite::{params, Connection, Transaction};
+
+/// One schema upgrade step. Steps are applied once each, in version order.
+/// Append new steps to `MIGRATIONS`; never edit or renumber a released one.
+struct Migration {
+    version: i64,
+    name: &'static str,
+    apply: fn(&Transaction) -> rusqlite::Result<()>,
+}
+
+/// Add a column unless it already exists. Databases created before versioned
+/// migrations may already have columns the early steps add.
+fn add_column(tx: &Transaction, table: &str, column: &str, decl: &str) -> rusqlite::Result<()> {
+    let exists = tx
+        .prepare(&format!("PRAGMA table_info({})", table))?
+        .query_map([], |row| row.get::<_, String>(1))?
+        .collect::<rusqlite::Result<Vec<_>>>()?
+        .iter()
+        .any(|name| name == column);
+
+    if !exists {
+        tx.execute(&format!("ALTER TABLE {} ADD COLUMN {} {}", table, column, decl), [])?;
+    }
+    Ok(())
+}
+
+const MIGRATIONS: &[Migration] = &[
+    Migration {
+        version: 1,
+        name: "default_url and proxy columns",
+        apply: |tx| {
+            add_column(tx, "profiles", "default_url", "TEXT NOT NULL DEFAULT 'https://www.google.com'")?;
+            add_column(tx, "profiles", "proxy_enabled", "INTEGER NOT NULL DEFAULT 0")?;
+            add_column(tx, "profiles", "proxy_type", "TEXT NOT NULL DEFAULT 'http'")?;
+            add_column(tx, "profiles", "proxy_host", "TEXT NOT NULL DEFAULT ''")?;
+            add_column(tx, "profiles", "proxy_port", "INTEGER NOT NULL DEFAULT 0")?;
+            add_column(tx, "profiles", "proxy_username", "TEXT")?;
+            add_column(tx, "profiles", "proxy_password", "TEXT")
+        },
+    },
+    Migration {
+        version: 2,
+        name: "do_not_track",
+        apply: |tx| add_column(tx, "profiles", "do_not_track", "INTEGER"),
+    },
+    Migration {
+        version: 3,
+        name: "canvas_noise_level",
+        apply: |tx| add_column(tx, "profiles", "canvas_noise_level", "TEXT NOT NULL DEFAULT 'medium'"),
+    },
+    Migration {
+        version: 4,
+        name: "inner_width and inner_height",
+        apply: |tx| {
+            add_column(tx, "profiles", "inner_width", "INTEGER")?;
+            add_column(tx, "profiles", "inner_height", "INTEGER")
+        },
+    },
+    Migration {
+        version: 5,
+        name: "custom_headers",
+        apply: |tx| add_column(tx, "profiles", "custom_headers", "TEXT NOT NULL DEFAULT '{}'"),
+    },
+    Migration {
+        version: 6,
+        name: "last_aged",
+        apply: |tx| add_column(tx, "profiles", "last_aged", "TEXT"),
+    },
+    Migration {
+        version: 7,
+        name: "is_system",
+        apply: |tx| add_column(tx, "profiles", "is_system", "INTEGER NOT NULL DEFAULT 0"),
+    },
+    Migration {
+        version: 8,
+        name: "proxy_bypass",
+        apply: |tx| add_column(tx, "profiles", "proxy_bypass", "TEXT NOT NULL DEFAULT '[]'"),
+    },
+    Migration {
+        version: 9,
+        name: "fingerprint_templates table",
+        apply: |tx| {
+            tx.execute(
+                "CREATE TABLE IF NOT EXISTS fingerprint_templates (
+                    name TEXT PRIMARY KEY,
+                    template TEXT NOT NULL,
+                    builtin INTEGER NOT NULL DEFAULT 0
+                )",
+                [],
+            )?;
+            Ok(())
+        },
+    },
+    Migration {
+        version: 10,
+        name: "languages",
+        apply: |tx| add_column(tx, "profiles", "languages", "TEXT NOT NULL DEFAULT '[]'"),
+    },
+    Migration {
+        version: 11,
+        name: "tags",
+        apply: |tx| add_column(tx, "profiles", "tags", "TEXT NOT NULL DEFAULT '[]'"),
+    },
+    Migration {
+        version: 12,
+        name: "webrtc_mode",
+        apply: |tx| add_column(tx, "profiles", "webrtc_mode", "TEXT"),
+    },
+    Migration {
+        version: 13,
+        name: "launch_count",
+        apply: |tx| add_column(tx, "profiles", "launch_count", "INTEGER NOT NULL DEFAULT 0"),
+    },
+    Migration {
+        version: 14,
+        name: "leases",
+        apply: |tx| {
+            add_column(tx, "profiles", "leased_by", "TEXT")?;
+            add_column(tx, "profiles", "leased_at", "INTEGER")
+        },
+    },
+    Migration {
+        version: 15,
+        name: "favorites",
+        apply: |tx| {
+            add_column(tx, "profiles", "is_favorite", "INTEGER NOT NULL DEFAULT 0")?;
+            add_column(tx, "profiles", "sort_order", "INTEGER NOT NULL DEFAULT 0")
+        },
+    },
+    Migration {
+        version: 16,
+        name: "environment",
+        apply: |tx| add_column(tx, "profiles", "environment", "TEXT NOT NULL DEFAULT 'default'"),
+    },
+    Migration {
+        version: 17,
+        name: "disable_cache",
+        apply: |tx| add_column(tx, "profiles", "disable_cache", "INTEGER NOT NULL DEFAULT 0"),
+    },
+    Migration {
+        version: 18,
+        name: "idempotency_keys table",
+        apply: |tx| {
+            tx.execute(
+                "CREATE TABLE IF NOT EXISTS idempotency_keys (
+                    key TEXT PRIMARY KEY,
+                    profile_id TEXT NOT NULL,
+                    created_at INTEGER NOT NULL
+                )",
+                [],
+            )?;
+            Ok(())
+        },
+    },
+];
+
+/// Apply every migration newer than the recorded schema version. Each step
+/// runs in its own transaction together with its `schema_migrations` row, so
+/// a failure leaves the database at the last fully applied version.
+pub fn run(conn: &mut Connection) -> rusqlite::Result<()> {
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS schema_migrations (
+            version INTEGER PRIMARY KEY,
+            name TEXT NOT NULL,
+            applied_at TEXT NOT NULL
+        )",
+        [],
+    )?;
+
+    let current: i64 = conn.query_row(
+        "SELECT COALESCE(MAX(version), 0) FROM schema_migrations",
+        [],
+        |row| row.get(0),
+    )?;
+
+    for migration in MIGRATIONS.iter().filter(|m| m.version > current) {
+        let tx = conn.transaction()?;
+        (migration.apply)(&tx)?;
+        tx.execute(
+            "INSERT INTO schema_migrations (version, name, applied_at)
+             VALUES (?1, ?2, strftime('%s', 'now'))",
+            params![migration.version, migration.name],
+        )?;
+        tx.commit()?;
+        log::info!("Applied migration {}: {}", migration.version, migration.name);
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn columns(conn: &Connection) -> Vec<String> {
+        conn.prepare("PRAGMA table_info(profiles)")
+            .unwrap()
+            .query_map([], |row| row.get(1))
+            .unwrap()
+            .collect::<rusqlite::Result<_>>()
+            .unwrap()
+    }
+
+    #[test]
+    fn test_migrations_upgrade_legacy_schema_once() {
+        let mut conn = Connection::open_in_memory().unwrap();
+        // Pre-versioning database that already picked up one of the early columns
+        conn.execute(
+            "CREATE TABLE profiles (id TEXT PRIMARY KEY, default_url TEXT NOT NULL DEFAULT '')",
+            [],
+        )
+        .unwrap();
+
+        run(&mut conn).unwrap();
+        let after_first = columns(&conn);
+        assert!(after_first.contains(&"proxy_password".to_string()));
+        assert!(after_first.contains(&"proxy_bypass".to_string()));
+
+        // Re-running applies nothing new
+        run(&mut conn).unwrap();
+        assert_eq!(columns(&conn), after_first);
+
+        let latest: i64 = conn
+            .query_row("SELECT MAX(version) FROM schema_migrations", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(latest, MIGRATIONS.last().unwrap().version);
+    }
+
+    #[test]
+    fn test_migration_versions_are_increasing() {
+        assert!(MIGRATIONS.windows(2).all(|w| w[0].version < w[1].version));
+    }
+}
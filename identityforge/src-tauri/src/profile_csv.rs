@@ -0,0 +1,379 @@
+use crate::database::Profile;
+use serde::Serialize;
+
+/// Columns written for every profile, in order
+const COLUMNS: &[&str] = &[
+    "id", "name", "platform", "user_agent", "resolution", "timezone", "language",
+    "proxy_host", "proxy_port", "created_at", "last_used",
+];
+
+/// Extra columns written only when secrets are requested
+const SECRET_COLUMNS: &[&str] = &["proxy_username", "proxy_password"];
+
+/// Quote a field per RFC 4180 when it holds a separator, quote or line break.
+/// Leading `=`, `+`, `-` and `@` get a `'` prefix so spreadsheets don't run
+/// a profile name as a formula.
+fn escape(field: &str) -> String {
+    let field = if field.starts_with(['=', '+', '-', '@']) {
+        format!("'{}", field)
+    } else {
+        field.to_string()
+    };
+    if field.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field
+    }
+}
+
+/// One CSV row per profile with a header line. Proxy credentials are only
+/// included with `include_secrets`.
+pub fn profiles_to_csv(profiles: &[Profile], include_secrets: bool) -> String {
+    let mut header: Vec<&str> = COLUMNS.to_vec();
+    if include_secrets {
+        header.extend(SECRET_COLUMNS);
+    }
+    let mut out = header.join(",");
+    out.push_str("\r\n");
+
+    for profile in profiles {
+        let proxy_port = if profile.proxy_host.is_empty() {
+            String::new()
+        } else {
+            profile.proxy_port.to_string()
+        };
+        let mut row = vec![
+            profile.id.clone(),
+            profile.name.clone(),
+            profile.platform.clone(),
+            profile.user_agent.clone(),
+            format!("{}x{}", profile.screen_width, profile.screen_height),
+            profile.timezone.clone(),
+            profile.language.clone(),
+            profile.proxy_host.clone(),
+            proxy_port,
+            profile.created_at.clone(),
+            profile.last_used.clone().unwrap_or_default(),
+        ];
+        if include_secrets {
+            row.push(profile.proxy_username.clone().unwrap_or_default());
+            row.push(profile.proxy_password.clone().unwrap_or_default());
+        }
+        let escaped: Vec<String> = row.iter().map(|field| escape(field)).collect();
+        out.push_str(&escaped.join(","));
+        out.push_str("\r\n");
+    }
+    out
+}
+
+/// Identity columns a row must fill when missing values aren't generated
+const IDENTITY_COLUMNS: &[&str] = &["platform", "user_agent", "resolution", "timezone", "language"];
+
+/// A CSV row that can't be imported, by the line it starts on
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct CsvRowError {
+    pub line: usize,
+    pub message: String,
+}
+
+/// One profile row read by `parse_profiles_csv`; `None` where the row leaves
+/// a value out
+#[derive(Debug, Default, PartialEq)]
+pub struct CsvProfile {
+    pub line: usize,
+    pub name: String,
+    pub platform: Option<String>,
+    pub user_agent: Option<String>,
+    pub screen: Option<(i32, i32)>,
+    pub timezone: Option<String>,
+    pub language: Option<String>,
+    pub webgl_vendor: Option<String>,
+    pub webgl_renderer: Option<String>,
+    pub hardware_concurrency: Option<i32>,
+    pub device_memory: Option<i32>,
+    pub default_url: Option<String>,
+    pub proxy_type: Option<String>,
+    pub proxy_host: Option<String>,
+    pub proxy_port: Option<i32>,
+    pub proxy_username: Option<String>,
+    pub proxy_password: Option<String>,
+    pub environment: Option<String>,
+    /// Split from a `;`-separated `tags` field
+    pub tags: Option<Vec<String>>,
+    pub disable_cache: Option<bool>,
+}
+
+impl CsvProfile {
+    /// Identity columns this row leaves empty
+    pub fn missing_identity(&self) -> Vec<&'static str> {
+        let present = [
+            self.platform.is_some(),
+            self.user_agent.is_some(),
+            self.screen.is_some(),
+            self.timezone.is_some(),
+            self.language.is_some(),
+        ];
+        IDENTITY_COLUMNS
+            .iter()
+            .zip(present)
+            .filter(|(_, present)| !present)
+            .map(|(column, _)| *column)
+            .collect()
+    }
+}
+
+/// Split CSV text into records per RFC 4180, each with the line it starts on.
+/// Lines may end in CRLF or LF, and quoted fields may span lines.
+fn records(csv: &str) -> Result<Vec<(usize, Vec<String>)>, CsvRowError> {
+    let mut records = Vec::new();
+    let mut fields = Vec::new();
+    let mut field = String::new();
+    let mut in_quotes = false;
+    let mut line = 1;
+    let mut record_line = 1;
+    let mut chars = csv.chars().peekable();
+    while let Some(c) = chars.next() {
+        if in_quotes {
+            match c {
+                '"' if chars.peek() == Some(&'"') => {
+                    chars.next();
+                    field.push('"');
+                }
+                '"' => in_quotes = false,
+                _ => {
+                    if c == '\n' {
+                        line += 1;
+                    }
+                    field.push(c);
+                }
+            }
+            continue;
+        }
+        match c {
+            '"' if field.is_empty() => in_quotes = true,
+            ',' => fields.push(std::mem::take(&mut field)),
+            '\r' if chars.peek() == Some(&'\n') => {}
+            '\n' => {
+                fields.push(std::mem::take(&mut field));
+                records.push((record_line, std::mem::take(&mut fields)));
+                line += 1;
+                record_line = line;
+            }
+            _ => field.push(c),
+        }
+    }
+    if in_quotes {
+        return Err(CsvRowError {
+            line: record_line,
+            message: "quoted field is never closed".to_string(),
+        });
+    }
+    if !field.is_empty() || !fields.is_empty() {
+        fields.push(field);
+        records.push((record_line, fields));
+    }
+    Ok(records)
+}
+
+/// Undo `escape`'s formula guard and trim; empty fields are `None`
+fn unescape(field: &str) -> Option<String> {
+    let field = field.trim();
+    let field = match field.strip_prefix('\'') {
+        Some(rest) if rest.starts_with(['=', '+', '-', '@']) => rest,
+        _ => field,
+    };
+    (!field.is_empty()).then(|| field.to_string())
+}
+
+fn parse_number(column: &str, value: Option<String>, max: i32) -> Result<Option<i32>, String> {
+    value
+        .map(|v| match v.parse::<i32>() {
+            Ok(n) if (1..=max).contains(&n) => Ok(n),
+            _ => Err(format!("{} must be a number from 1 to {}, got '{}'", column, max, v)),
+        })
+        .transpose()
+}
+
+/// `true`/`false`, `yes`/`no` or `1`/`0`, in any case
+fn parse_bool(column: &str, value: Option<String>) -> Result<Option<bool>, String> {
+    value
+        .map(|v| match v.to_lowercase().as_str() {
+            "true" | "yes" | "1" => Ok(true),
+            "false" | "no" | "0" => Ok(false),
+            _ => Err(format!("{} must be true or false, got '{}'", column, v)),
+        })
+        .transpose()
+}
+
+/// `WIDTHxHEIGHT`, as `profiles_to_csv` writes it
+fn parse_resolution(value: &str) -> Result<(i32, i32), String> {
+    let parsed = value
+        .split_once(['x', 'X'])
+        .and_then(|(w, h)| Some((w.trim().parse::<i32>().ok()?, h.trim().parse::<i32>().ok()?)))
+        .filter(|(w, h)| *w > 0 && *h > 0);
+    parsed.ok_or_else(|| format!("resolution must look like 1920x1080, got '{}'", value))
+}
+
+/// Read profile rows from CSV with a header line. Columns are matched by name
+/// in any order: `name` is required, and the others `profiles_to_csv` writes
+/// are read back along with `webgl_vendor`, `webgl_renderer`,
+/// `hardware_concurrency`, `device_memory`, `default_url`, `proxy_type`,
+/// `environment`, `tags` (separated by `;`) and `disable_cache`.
+/// Unknown columns, such as an export's `id`, are ignored.
+pub fn parse_profiles_csv(csv: &str) -> (Vec<CsvProfile>, Vec<CsvRowError>) {
+    // Spreadsheets often save UTF-8 with a byte order mark
+    let csv = csv.strip_prefix('\u{feff}').unwrap_or(csv);
+    let records = match records(csv) {
+        Ok(records) => records,
+        Err(e) => return (Vec::new(), vec![e]),
+    };
+    let mut records = records
+        .into_iter()
+        .filter(|(_, fields)| fields.iter().any(|f| !f.trim().is_empty()));
+    let Some((header_line, header)) = records.next() else {
+        let error = CsvRowError { line: 1, message: "CSV has no header line".to_string() };
+        return (Vec::new(), vec![error]);
+    };
+    let header: Vec<String> = header.iter().map(|h| h.trim().to_lowercase()).collect();
+    if !header.iter().any(|h| h == "name") {
+        let error = CsvRowError { line: header_line, message: "header has no 'name' column".to_string() };
+        return (Vec::new(), vec![error]);
+    }
+
+    let mut profiles = Vec::new();
+    let mut errors = Vec::new();
+    for (line, fields) in records {
+        if fields.len() > header.len() {
+            errors.push(CsvRowError {
+                line,
+                message: format!("row has {} fields but the header has {}", fields.len(), header.len()),
+            });
+            continue;
+        }
+        let get = |column: &str| {
+            let index = header.iter().position(|h| h == column)?;
+            unescape(fields.get(index)?)
+        };
+        match parse_row(line, get) {
+            Ok(profile) => profiles.push(profile),
+            Err(message) => errors.push(CsvRowError { line, message }),
+        }
+    }
+    (profiles, errors)
+}
+
+fn parse_row(line: usize, get: impl Fn(&str) -> Option<String>) -> Result<CsvProfile, String> {
+    let name = get("name").ok_or("name is empty")?;
+    let screen = get("resolution").map(|r| parse_resolution(&r)).transpose()?;
+    let proxy_host = get("proxy_host");
+    let proxy_port = parse_number("proxy_port", get("proxy_port"), u16::MAX as i32)?;
+    if proxy_host.is_some() && proxy_port.is_none() {
+        return Err("proxy_host is set without a proxy_port".to_string());
+    }
+    Ok(CsvProfile {
+        line,
+        name,
+        platform: get("platform"),
+        user_agent: get("user_agent"),
+        screen,
+        timezone: get("timezone"),
+        language: get("language"),
+        webgl_vendor: get("webgl_vendor"),
+        webgl_renderer: get("webgl_renderer"),
+        hardware_concurrency: parse_number("hardware_concurrency", get("hardware_concurrency"), 256)?,
+        device_memory: parse_number("device_memory", get("device_memory"), 1024)?,
+        default_url: get("default_url"),
+        proxy_type: get("proxy_type"),
+        proxy_host,
+        proxy_port,
+        proxy_username: get("proxy_username"),
+        proxy_password: get("proxy_password"),
+        environment: get("environment"),
+        tags: get("tags").map(|tags| tags.split(';').map(str::to_string).collect()),
+        disable_cache: parse_bool("disable_cache", get("disable_cache"))?,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::test_profile;
+
+    #[test]
+    fn test_escape() {
+        assert_eq!(escape("plain"), "plain");
+        assert_eq!(escape("a,b"), "\"a,b\"");
+        assert_eq!(escape("say \"hi\""), "\"say \"\"hi\"\"\"");
+        assert_eq!(escape("two\nlines"), "\"two\nlines\"");
+        assert_eq!(escape("=1+1"), "'=1+1");
+    }
+
+    #[test]
+    fn test_profiles_to_csv_hides_secrets_by_default() {
+        let profile = Profile {
+            name: "Shop, EU".to_string(),
+            timezone: "Europe/Berlin".to_string(),
+            language: "de-DE".to_string(),
+            proxy_enabled: true,
+            proxy_host: "gw.example.net".to_string(),
+            proxy_port: 8080,
+            proxy_username: Some("user".to_string()),
+            proxy_password: Some("hunter2".to_string()),
+            created_at: "1700000000".to_string(),
+            ..test_profile("p")
+        };
+
+        let csv = profiles_to_csv(std::slice::from_ref(&profile), false);
+        let lines: Vec<&str> = csv.split("\r\n").collect();
+        assert_eq!(lines[0], COLUMNS.join(","));
+        assert_eq!(
+            lines[1],
+            "p,\"Shop, EU\",Win32,Mozilla/5.0,1920x1080,Europe/Berlin,de-DE,gw.example.net,8080,1700000000,"
+        );
+        assert!(!csv.contains("hunter2"));
+
+        let csv = profiles_to_csv(&[profile], true);
+        assert!(csv.starts_with(&format!("{},proxy_username,proxy_password\r\n", COLUMNS.join(","))));
+        assert!(csv.contains(",user,hunter2\r\n"));
+    }
+
+    #[test]
+    fn test_parse_profiles_csv() {
+        let csv = "\u{feff}ID,Name,Platform,Resolution,Proxy_Host,Proxy_Port,Notes\r\n\
+                   x,\"Shop, EU\",Win32,1920x1080,gw.example.net,8080,\"two\nlines\"\r\n\
+                   \r\n\
+                   y,'=SUM,,,,\n\
+                   ,,MacIntel,wide,,\n\
+                   z,Proxy,,,gw.example.net,,\n\
+                   w,Extra,,,,,,surplus\n";
+        let (profiles, errors) = parse_profiles_csv(csv);
+        assert_eq!(profiles.len(), 2);
+        assert_eq!(profiles[0].line, 2);
+        assert_eq!(profiles[0].name, "Shop, EU");
+        assert_eq!(profiles[0].platform.as_deref(), Some("Win32"));
+        assert_eq!(profiles[0].screen, Some((1920, 1080)));
+        assert_eq!(profiles[0].proxy_port, Some(8080));
+        assert_eq!(profiles[0].missing_identity(), vec!["user_agent", "timezone", "language"]);
+        // The export's formula guard is undone
+        assert_eq!((profiles[1].line, profiles[1].name.as_str()), (5, "=SUM"));
+
+        let lines: Vec<usize> = errors.iter().map(|e| e.line).collect();
+        assert_eq!(lines, vec![6, 7, 8], "{:?}", errors);
+        assert!(errors[0].message.contains("name"));
+        assert!(errors[1].message.contains("proxy_port"));
+        assert!(errors[2].message.contains("8 fields"));
+
+        let (profiles, errors) = parse_profiles_csv("name\n\"open\n");
+        assert!(profiles.is_empty());
+        assert_eq!(errors[0].line, 2);
+        assert!(!parse_profiles_csv("platform\nWin32\n").1.is_empty());
+
+        let csv = "name,environment,tags,disable_cache\nA,staging,shop; eu,Yes\nB,,,maybe\n";
+        let (profiles, errors) = parse_profiles_csv(csv);
+        assert_eq!(profiles[0].environment.as_deref(), Some("staging"));
+        assert_eq!(profiles[0].tags, Some(vec!["shop".to_string(), " eu".to_string()]));
+        assert_eq!(profiles[0].disable_cache, Some(true));
+        assert_eq!(errors[0].line, 3);
+        assert!(errors[0].message.contains("disable_cache"));
+    }
+}
@@ -0,0 +1,245 @@
+use crate::database::Database;
+use crate::fingerprint::{generate_spoof_script, launch_fingerprint, navigator_languages, Fingerprint};
+use percent_encoding::percent_decode_str;
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+use std::sync::Mutex;
+use std::time::Duration;
+use tauri::{AppHandle, WebviewUrl, WebviewWindowBuilder};
+use thiserror::Error;
+use tokio::sync::oneshot;
+use url::Url;
+
+/// Host the bundled self-test page navigates to with its findings.
+/// `.invalid` is reserved, so the navigation can never leave the machine.
+const REPORT_HOST: &str = "selftest.identityforge.invalid";
+
+/// How long to wait for the self-test page to report back
+const REPORT_TIMEOUT: Duration = Duration::from_secs(15);
+
+#[derive(Error, Debug)]
+pub enum SelfTestError {
+    #[error("Database error: {0}")]
+    Database(#[from] crate::database::DatabaseError),
+    #[error("Tauri error: {0}")]
+    Tauri(#[from] tauri::Error),
+    #[error("Self-test page did not report within {0} seconds")]
+    Timeout(u64),
+}
+
+/// Attribute values as read by the self-test page
+#[derive(Deserialize, Debug, Clone)]
+pub struct ObservedFingerprint {
+    pub user_agent: String,
+    pub platform: String,
+    pub screen_width: i32,
+    pub screen_height: i32,
+    pub webgl_vendor: Option<String>,
+    pub webgl_renderer: Option<String>,
+    /// `UNMASKED_VENDOR_WEBGL,UNMASKED_RENDERER_WEBGL` as read off the
+    /// `WEBGL_debug_renderer_info` extension object; `None` if it was missing
+    #[serde(default)]
+    pub webgl_debug_constants: Option<String>,
+    pub timezone: String,
+    pub hardware_concurrency: i32,
+    pub languages: Vec<String>,
+    /// `None` if the page saw `undefined`
+    pub webdriver: Option<bool>,
+}
+
+/// One intended-vs-observed comparison
+#[derive(Serialize, Debug, Clone)]
+pub struct SelfTestCheck {
+    pub attribute: String,
+    pub expected: String,
+    pub observed: String,
+    pub passed: bool,
+}
+
+/// Result of a self-test run; any failed check means a spoof didn't apply
+#[derive(Serialize, Debug, Clone)]
+pub struct SelfTestReport {
+    pub profile_id: String,
+    pub passed: bool,
+    pub checks: Vec<SelfTestCheck>,
+}
+
+/// Extract the observed fingerprint from a report navigation, if `url` is one
+fn parse_report_url(url: &Url) -> Option<ObservedFingerprint> {
+    if url.host_str() != Some(REPORT_HOST) {
+        return None;
+    }
+    let json = percent_decode_str(url.fragment()?).decode_utf8().ok()?;
+    serde_json::from_str(&json).ok()
+}
+
+/// Compare what the page saw against what the profile intended
+fn compare(fingerprint: &Fingerprint, observed: &ObservedFingerprint) -> Vec<SelfTestCheck> {
+    let check = |attribute: &str, expected: String, observed: String| SelfTestCheck {
+        attribute: attribute.to_string(),
+        passed: expected == observed,
+        expected,
+        observed,
+    };
+
+    vec![
+        check("userAgent", fingerprint.user_agent.clone(), observed.user_agent.clone()),
+        check("platform", fingerprint.platform.clone(), observed.platform.clone()),
+        check(
+            "screen",
+            format!("{}x{}", fingerprint.screen_width, fingerprint.screen_height),
+            format!("{}x{}", observed.screen_width, observed.screen_height),
+        ),
+        check(
+            "webglVendor",
+            fingerprint.webgl_vendor.clone(),
+            observed.webgl_vendor.clone().unwrap_or_default(),
+        ),
+        check(
+            "webglRenderer",
+            fingerprint.webgl_renderer.clone(),
+            observed.webgl_renderer.clone().unwrap_or_default(),
+        ),
+        check(
+            "webglDebugRendererInfo",
+            "37445,37446".to_string(),
+            observed
+                .webgl_debug_constants
+                .clone()
+                .unwrap_or_else(|| "missing".to_string()),
+        ),
+        check("timezone", fingerprint.timezone.clone(), observed.timezone.clone()),
+        check(
+            "hardwareConcurrency",
+            fingerprint.hardware_concurrency.to_string(),
+            observed.hardware_concurrency.to_string(),
+        ),
+        check(
+            "languages",
+            navigator_languages(&fingerprint.language, &fingerprint.languages).join(","),
+            observed.languages.join(","),
+        ),
+        check(
+            "webdriver",
+            "false".to_string(),
+            observed
+                .webdriver
+                .map(|w| w.to_string())
+                .unwrap_or_else(|| "undefined".to_string()),
+        ),
+    ]
+}
+
+/// Load the bundled self-test page in a hidden window with the profile's spoof
+/// applied, and report which attributes came through as intended.
+/// Uses a throwaway data directory so the profile's own storage is untouched.
+pub async fn run_self_test(
+    app: &AppHandle,
+    db: &Database,
+    profile_id: &str,
+) -> Result<SelfTestReport, SelfTestError> {
+    let profile = db.get_profile(profile_id)?;
+    let fingerprint = launch_fingerprint(db, &profile);
+    let spoof_script = generate_spoof_script(&fingerprint, profile_id);
+
+    let window_label = format!("selftest_{}", profile_id.replace("-", "_"));
+    let data_dir = std::env::temp_dir().join(format!("identityforge-{}", window_label));
+    // Start empty, in case an earlier run was cut short before cleaning up
+    std::fs::remove_dir_all(&data_dir).ok();
+    std::fs::create_dir_all(&data_dir).ok();
+
+    let (tx, rx) = oneshot::channel();
+    let tx = Mutex::new(Some(tx));
+
+    let built = WebviewWindowBuilder::new(
+        app,
+        &window_label,
+        WebviewUrl::App(PathBuf::from("selftest.html")),
+    )
+    .title("IdentityForge Self-Test")
+    .inner_size(
+        fingerprint.screen_width as f64 * 0.8,
+        fingerprint.screen_height as f64 * 0.8,
+    )
+    .visible(false)
+    .data_directory(data_dir.clone())
+    .user_agent(&fingerprint.user_agent)
+    .initialization_script(&spoof_script)
+    .on_navigation(move |url| match parse_report_url(url) {
+        Some(observed) => {
+            if let Some(tx) = tx.lock().unwrap().take() {
+                let _ = tx.send(observed);
+            }
+            false
+        }
+        None => true,
+    })
+    .build();
+    let window = match built {
+        Ok(window) => window,
+        Err(e) => {
+            std::fs::remove_dir_all(&data_dir).ok();
+            return Err(e.into());
+        }
+    };
+
+    let observed = tokio::time::timeout(REPORT_TIMEOUT, rx).await;
+
+    // Clean up regardless of outcome
+    window.destroy().ok();
+    std::fs::remove_dir_all(&data_dir).ok();
+
+    let observed = match observed {
+        Ok(Ok(observed)) => observed,
+        _ => return Err(SelfTestError::Timeout(REPORT_TIMEOUT.as_secs())),
+    };
+
+    let checks = compare(&fingerprint, &observed);
+    Ok(SelfTestReport {
+        profile_id: profile_id.to_string(),
+        passed: checks.iter().all(|c| c.passed),
+        checks,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fingerprint::FingerprintGenerator;
+
+    #[test]
+    fn test_report_parsing_and_comparison() {
+        let mut generator = FingerprintGenerator::new();
+        let fp = generator.generate();
+
+        let json = serde_json::json!({
+            "user_agent": fp.user_agent,
+            "platform": fp.platform,
+            "screen_width": fp.screen_width,
+            "screen_height": fp.screen_height,
+            "webgl_vendor": fp.webgl_vendor,
+            "webgl_renderer": null,
+            "webgl_debug_constants": "37445,37446",
+            "timezone": fp.timezone,
+            "hardware_concurrency": fp.hardware_concurrency,
+            "languages": fp.languages,
+            "webdriver": false,
+        });
+        let encoded: String = url::form_urlencoded::byte_serialize(json.to_string().as_bytes())
+            .collect::<String>()
+            .replace('+', "%20");
+        let url = Url::parse(&format!("https://{}/report#{}", REPORT_HOST, encoded)).unwrap();
+
+        let observed = parse_report_url(&url).unwrap();
+        let checks = compare(&fp, &observed);
+        let failed: Vec<&str> = checks
+            .iter()
+            .filter(|c| !c.passed)
+            .map(|c| c.attribute.as_str())
+            .collect();
+        assert_eq!(failed, vec!["webglRenderer"]);
+
+        let other = Url::parse("https://example.com/report#{}").unwrap();
+        assert!(parse_report_url(&other).is_none());
+    }
+}
@@ -0,0 +1,286 @@
+use serde::Serialize;
+use std::path::{Path, PathBuf};
+use std::time::UNIX_EPOCH;
+use thiserror::Error;
+
+/// Longest snapshot name accepted
+const MAX_NAME_LENGTH: usize = 64;
+
+/// Directories `restore_snapshot` stages in a profile's snapshot directory:
+/// the incoming copy, and the data it replaces. The leading dot keeps them
+/// out of the snapshot namespace.
+const RESTORING_DIR: &str = ".restoring";
+const PREVIOUS_DIR: &str = ".previous";
+
+#[derive(Error, Debug)]
+pub enum SnapshotError {
+    #[error("Invalid snapshot name '{0}': use up to 64 letters, digits, spaces, '-', '_' or '.'")]
+    InvalidName(String),
+    #[error("Snapshot not found: {0}")]
+    NotFound(String),
+    #[error("A snapshot named '{0}' already exists")]
+    AlreadyExists(String),
+    #[error("IO error: {0}")]
+    Io(#[from] std::io::Error),
+}
+
+/// A saved copy of a profile's data directory
+#[derive(Debug, Clone, Serialize)]
+pub struct SessionSnapshot {
+    pub name: String,
+    /// Unix seconds
+    pub created_at: u64,
+    pub size_bytes: u64,
+}
+
+/// Names double as directory names, so they're kept to one plain path component
+pub fn validate_snapshot_name(name: &str) -> Result<(), SnapshotError> {
+    let valid = !name.is_empty()
+        && name.chars().count() <= MAX_NAME_LENGTH
+        && !name.starts_with('.')
+        && name.trim() == name
+        && name.chars().all(|c| c.is_ascii_alphanumeric() || " -_.".contains(c));
+    if valid {
+        Ok(())
+    } else {
+        Err(SnapshotError::InvalidName(name.to_string()))
+    }
+}
+
+fn snapshot_dir(root: &Path, profile_id: &str, name: &str) -> PathBuf {
+    root.join(profile_id).join(name)
+}
+
+/// Copy `from` into a new directory `to`. Symlinks are skipped so a snapshot
+/// never captures files from outside the profile.
+pub fn copy_dir(from: &Path, to: &Path) -> std::io::Result<u64> {
+    std::fs::create_dir(to)?;
+    let mut copied = 0;
+    for entry in std::fs::read_dir(from)? {
+        let entry = entry?;
+        let file_type = entry.file_type()?;
+        let target = to.join(entry.file_name());
+        if file_type.is_dir() {
+            copied += copy_dir(&entry.path(), &target)?;
+        } else if file_type.is_file() {
+            copied += std::fs::copy(entry.path(), &target)?;
+        }
+    }
+    Ok(copied)
+}
+
+/// Total size of the regular files under `dir`
+fn dir_size(dir: &Path) -> std::io::Result<u64> {
+    let mut size = 0;
+    for entry in std::fs::read_dir(dir)? {
+        let entry = entry?;
+        let file_type = entry.file_type()?;
+        if file_type.is_dir() {
+            size += dir_size(&entry.path())?;
+        } else if file_type.is_file() {
+            size += entry.metadata()?.len();
+        }
+    }
+    Ok(size)
+}
+
+/// Copy `data_dir` into a new snapshot `name` under `root`. The copy is
+/// built under a temporary name, so a failure never leaves a partial snapshot.
+pub fn create_snapshot(
+    root: &Path,
+    data_dir: &Path,
+    profile_id: &str,
+    name: &str,
+) -> Result<SessionSnapshot, SnapshotError> {
+    validate_snapshot_name(name)?;
+    let target = snapshot_dir(root, profile_id, name);
+    if target.exists() {
+        return Err(SnapshotError::AlreadyExists(name.to_string()));
+    }
+    std::fs::create_dir_all(root.join(profile_id))?;
+
+    let staging = snapshot_dir(root, profile_id, &format!(".partial-{}", name));
+    if staging.exists() {
+        std::fs::remove_dir_all(&staging)?;
+    }
+    let size_bytes = match copy_dir(data_dir, &staging) {
+        Ok(size) => size,
+        Err(e) => {
+            std::fs::remove_dir_all(&staging).ok();
+            return Err(e.into());
+        }
+    };
+    std::fs::rename(&staging, &target)?;
+
+    Ok(SessionSnapshot {
+        name: name.to_string(),
+        created_at: modified_secs(&target),
+        size_bytes,
+    })
+}
+
+/// Replace `data_dir` with a copy of snapshot `name`. The current directory
+/// is only removed once the copy is in place, and is put back if the swap
+/// fails. Both are staged under `root`, never beside the profile
+/// directories. The profile must not be open.
+pub fn restore_snapshot(root: &Path, data_dir: &Path, profile_id: &str, name: &str) -> Result<(), SnapshotError> {
+    validate_snapshot_name(name)?;
+    let restoring = root.join(profile_id).join(RESTORING_DIR);
+    let previous = root.join(profile_id).join(PREVIOUS_DIR);
+    // An interrupted restore can leave the only copy of the data in `.previous`
+    if previous.exists() && !data_dir.exists() {
+        std::fs::rename(&previous, data_dir)?;
+    }
+    let source = snapshot_dir(root, profile_id, name);
+    if !source.is_dir() {
+        return Err(SnapshotError::NotFound(name.to_string()));
+    }
+
+    for leftover in [&restoring, &previous] {
+        if leftover.exists() {
+            std::fs::remove_dir_all(leftover)?;
+        }
+    }
+    if let Err(e) = copy_dir(&source, &restoring) {
+        std::fs::remove_dir_all(&restoring).ok();
+        return Err(e.into());
+    }
+    let had_data = data_dir.exists();
+    if had_data {
+        if let Err(e) = std::fs::rename(data_dir, &previous) {
+            std::fs::remove_dir_all(&restoring).ok();
+            return Err(e.into());
+        }
+    }
+    if let Err(e) = std::fs::rename(&restoring, data_dir) {
+        if had_data {
+            std::fs::rename(&previous, data_dir).ok();
+        }
+        std::fs::remove_dir_all(&restoring).ok();
+        return Err(e.into());
+    }
+    if had_data {
+        std::fs::remove_dir_all(&previous)?;
+    }
+    Ok(())
+}
+
+/// A profile's snapshots, oldest first
+pub fn list_snapshots(root: &Path, profile_id: &str) -> Result<Vec<SessionSnapshot>, SnapshotError> {
+    let dir = root.join(profile_id);
+    if !dir.is_dir() {
+        return Ok(Vec::new());
+    }
+    let mut snapshots = Vec::new();
+    for entry in std::fs::read_dir(&dir)? {
+        let entry = entry?;
+        let Ok(name) = entry.file_name().into_string() else {
+            continue;
+        };
+        // Skips copies still in progress
+        if !entry.file_type()?.is_dir() || validate_snapshot_name(&name).is_err() {
+            continue;
+        }
+        snapshots.push(SessionSnapshot {
+            created_at: modified_secs(&entry.path()),
+            size_bytes: dir_size(&entry.path())?,
+            name,
+        });
+    }
+    snapshots.sort_by(|a, b| a.created_at.cmp(&b.created_at).then_with(|| a.name.cmp(&b.name)));
+    Ok(snapshots)
+}
+
+/// Delete one snapshot
+pub fn delete_snapshot(root: &Path, profile_id: &str, name: &str) -> Result<(), SnapshotError> {
+    validate_snapshot_name(name)?;
+    let dir = snapshot_dir(root, profile_id, name);
+    if !dir.is_dir() {
+        return Err(SnapshotError::NotFound(name.to_string()));
+    }
+    std::fs::remove_dir_all(dir)?;
+    Ok(())
+}
+
+/// Delete every snapshot of a profile, e.g. when the profile itself is deleted
+pub fn delete_all_snapshots(root: &Path, profile_id: &str) -> Result<(), SnapshotError> {
+    let dir = root.join(profile_id);
+    if dir.exists() {
+        std::fs::remove_dir_all(dir)?;
+    }
+    Ok(())
+}
+
+/// Last-modified time in Unix seconds, 0 when the platform can't say
+fn modified_secs(path: &Path) -> u64 {
+    std::fs::metadata(path)
+        .and_then(|meta| meta.modified())
+        .ok()
+        .and_then(|time| time.duration_since(UNIX_EPOCH).ok())
+        .map_or(0, |elapsed| elapsed.as_secs())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_validate_snapshot_name() {
+        assert!(validate_snapshot_name("logged in 2024-05-01_a.b").is_ok());
+        for bad in ["", ".hidden", "../escape", "a/b", "a\\b", " padded", &"x".repeat(65)] {
+            assert!(validate_snapshot_name(bad).is_err(), "{:?}", bad);
+        }
+    }
+
+    #[test]
+    fn test_snapshot_round_trip() {
+        let base = std::env::temp_dir().join(format!("identityforge-snapshots-{}", std::process::id()));
+        std::fs::remove_dir_all(&base).ok();
+        let root = base.join("snapshots");
+        let data_dir = base.join("profiles").join("p");
+        std::fs::create_dir_all(data_dir.join("Local Storage")).unwrap();
+        std::fs::write(data_dir.join("cookies.json"), "[1]").unwrap();
+        std::fs::write(data_dir.join("Local Storage").join("leveldb"), "logged-in").unwrap();
+
+        let snapshot = create_snapshot(&root, &data_dir, "p", "logged in").unwrap();
+        assert_eq!(snapshot.size_bytes, 12);
+        assert!(matches!(
+            create_snapshot(&root, &data_dir, "p", "logged in"),
+            Err(SnapshotError::AlreadyExists(_))
+        ));
+
+        // Roll back over later changes, including files that didn't exist yet
+        std::fs::write(data_dir.join("Local Storage").join("leveldb"), "logged-out").unwrap();
+        std::fs::write(data_dir.join("extra"), "x").unwrap();
+        restore_snapshot(&root, &data_dir, "p", "logged in").unwrap();
+        let restored = std::fs::read_to_string(data_dir.join("Local Storage").join("leveldb")).unwrap();
+        assert_eq!(restored, "logged-in");
+        assert!(!data_dir.join("extra").exists());
+        assert!(!root.join("p").join(PREVIOUS_DIR).exists());
+        assert!(!base.join("profiles").join("p.previous").exists());
+
+        let listed = list_snapshots(&root, "p").unwrap();
+        assert_eq!(listed.len(), 1);
+        assert_eq!(listed[0].name, "logged in");
+        assert!(list_snapshots(&root, "other").unwrap().is_empty());
+
+        // Data an interrupted restore left in `.previous` is moved back, not
+        // deleted as a leftover
+        std::fs::rename(&data_dir, root.join("p").join(PREVIOUS_DIR)).unwrap();
+        assert!(matches!(
+            restore_snapshot(&root, &data_dir, "p", "missing"),
+            Err(SnapshotError::NotFound(_))
+        ));
+        assert!(data_dir.join("cookies.json").exists());
+        assert!(!root.join("p").join(PREVIOUS_DIR).exists());
+
+        delete_snapshot(&root, "p", "logged in").unwrap();
+        assert!(matches!(delete_snapshot(&root, "p", "logged in"), Err(SnapshotError::NotFound(_))));
+        assert!(matches!(
+            restore_snapshot(&root, &data_dir, "p", "logged in"),
+            Err(SnapshotError::NotFound(_))
+        ));
+
+        std::fs::remove_dir_all(&base).ok();
+    }
+}
@@ -0,0 +1,136 @@
+use crate::fingerprint::fingerprint_options;
+use serde::{Deserialize, Serialize};
+
+/// Browsers a template can pin, matched against the UA pool
+pub const TEMPLATE_BROWSERS: &[&str] = &["chrome", "firefox", "safari"];
+
+/// Named persona that constrains fingerprint generation. Unset fields (or
+/// empty lists) leave that attribute to the generator's usual random pick.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FingerprintTemplate {
+    pub name: String,
+    /// "windows", "macos" or "linux"
+    pub platform: Option<String>,
+    /// One of `TEMPLATE_BROWSERS`
+    pub browser: Option<String>,
+    #[serde(default)]
+    pub timezones: Vec<String>,
+    #[serde(default)]
+    pub languages: Vec<String>,
+    pub min_width: Option<i32>,
+    pub max_width: Option<i32>,
+    pub min_height: Option<i32>,
+    pub max_height: Option<i32>,
+    /// Shipped with the app; can't be overwritten or deleted
+    #[serde(default)]
+    pub builtin: bool,
+}
+
+impl FingerprintTemplate {
+    /// Whether a screen resolution falls inside the template's ranges
+    pub fn allows_resolution(&self, width: i32, height: i32) -> bool {
+        !matches!(self.min_width, Some(min) if width < min)
+            && !matches!(self.max_width, Some(max) if width > max)
+            && !matches!(self.min_height, Some(min) if height < min)
+            && !matches!(self.max_height, Some(max) if height > max)
+    }
+}
+
+/// Templates shipped with the app, re-seeded into the database on startup
+pub fn builtin_templates() -> Vec<FingerprintTemplate> {
+    let template = |name: &str, platform: &str, browser: &str, timezones: &[&str], language: &str| {
+        FingerprintTemplate {
+            name: name.to_string(),
+            platform: Some(platform.to_string()),
+            browser: Some(browser.to_string()),
+            timezones: timezones.iter().map(|tz| tz.to_string()).collect(),
+            languages: vec![language.to_string()],
+            min_width: Some(1366),
+            max_width: None,
+            min_height: None,
+            max_height: None,
+            builtin: true,
+        }
+    };
+
+    vec![
+        template(
+            "US Windows Chrome",
+            "windows",
+            "chrome",
+            &["America/New_York", "America/Chicago", "America/Denver", "America/Los_Angeles"],
+            "en-US",
+        ),
+        template("UK Windows Chrome", "windows", "chrome", &["Europe/London"], "en-GB"),
+        FingerprintTemplate {
+            min_width: Some(1440),
+            ..template("German Mac Safari", "macos", "safari", &["Europe/Berlin"], "de-DE")
+        },
+        template("French Windows Firefox", "windows", "firefox", &["Europe/Paris"], "fr-FR"),
+        template("Japanese Windows Chrome", "windows", "chrome", &["Asia/Tokyo"], "ja-JP"),
+    ]
+}
+
+/// Check a template only references values the generator can produce
+pub fn validate_template(template: &FingerprintTemplate) -> Result<(), String> {
+    if template.name.trim().is_empty() {
+        return Err("Template name must not be empty".to_string());
+    }
+
+    if let Some(platform) = template.platform.as_deref() {
+        if !matches!(platform, "windows" | "macos" | "linux") {
+            return Err(format!(
+                "Unknown platform '{}' (expected windows, macos or linux)",
+                platform
+            ));
+        }
+    }
+    if let Some(browser) = template.browser.as_deref() {
+        if !TEMPLATE_BROWSERS.contains(&browser) {
+            return Err(format!(
+                "Unknown browser '{}' (expected one of: {})",
+                browser,
+                TEMPLATE_BROWSERS.join(", ")
+            ));
+        }
+        if browser == "safari" && template.platform.as_deref() != Some("macos") {
+            return Err("Safari templates must pin platform to macos".to_string());
+        }
+    }
+
+    let options = fingerprint_options();
+    if let Some(tz) = template.timezones.iter().find(|tz| !options.timezones.contains(tz)) {
+        return Err(format!("Unknown timezone '{}'", tz));
+    }
+    if let Some(lang) = template.languages.iter().find(|l| !options.languages.contains(l)) {
+        return Err(format!("Unknown language '{}'", lang));
+    }
+    if !options
+        .resolutions
+        .iter()
+        .any(|r| template.allows_resolution(r.width, r.height))
+    {
+        return Err("No known screen resolution fits the template's ranges".to_string());
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_builtin_templates_are_valid() {
+        for template in builtin_templates() {
+            assert!(validate_template(&template).is_ok(), "{}", template.name);
+        }
+
+        let mut bad = builtin_templates().remove(0);
+        bad.browser = Some("safari".to_string());
+        assert!(validate_template(&bad).is_err());
+        bad.browser = None;
+        bad.max_width = Some(100);
+        assert!(validate_template(&bad).is_err());
+    }
+}
@@ -190,6 +190,10 @@ export function EditProfileModal({ profile, onClose, onUpdated }: EditProfileMod
               onChange={(e) => handleChange('webgl_renderer', e.target.value)}
               className="w-full px-3 py-2 bg-gray-900 border border-gray-700 rounded text-white focus:outline-none focus:border-forge-accent"
             />
+            <p className="text-xs text-gray-500 mt-1">
+              WebGL noise is added on top of what this machine's GPU renders, so the same profile
+              gives a different WebGL hash on another machine.
+            </p>
           </div>
 
           {/* Platform */}
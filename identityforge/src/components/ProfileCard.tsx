@@ -292,6 +292,11 @@ export function ProfileCard({ profile, onUpdate, onEdit }: ProfileCardProps) {
               <span className={`text-xs px-2 py-0.5 rounded ${profile.is_active ? 'bg-forge-success/20 text-forge-success' : 'bg-gray-700 text-gray-400'}`}>
                 {profile.is_active ? '● Active' : '○ Inactive'}
               </span>
+              {profile.is_system && (
+                <span className="text-xs px-2 py-0.5 rounded bg-forge-danger/20 text-forge-danger" title="Real host fingerprint, no spoofing applied">
+                  ⚠ Unprotected
+                </span>
+              )}
               {profile.proxy_enabled && (
                 <span className="text-xs px-2 py-0.5 rounded bg-blue-500/20 text-blue-400" title={`${profile.proxy_type.toUpperCase()}://${profile.proxy_host}:${profile.proxy_port}`}>
                   🔒 Proxy
@@ -7,26 +7,84 @@ import {
   CreateProfileInput, 
   UpdateProfileInput,
   LaunchProfileInput,
-  ProxyConfig
+  ProxyConfig,
+  DuplicateFingerprintGroup,
+  BlocklistCheck,
+  LaunchGroupReport,
+  SelfTestReport,
+  FingerprintOptions,
+  FingerprintTemplate,
+  FieldDiff,
+  ProfileIssue,
+  ActiveProfile,
+  ActiveWindowInfo,
+  ReconcileReport,
+  EngineInfo,
+  UserAgentData,
+  IdentityDescription,
+  BulkRegenerateReport,
+  TimezoneSpreadReport,
+  BulkCreateReport,
+  BulkCreatePreview,
+  CsvImportReport,
+  IntegrityReport,
+  ForwarderStats,
+  CookieValidation,
+  PurgeReport,
+  ConfigImportReport,
+  IpReport,
+  SessionSnapshot
 } from '../types/profile';
 
 // Profile API
-export async function getProfiles(): Promise<ApiResponse<ProfileWithStatus[]>> {
-  return await invoke('get_profiles');
+export type ProfileSort = 'manual' | 'name' | 'created' | 'last_used';
+
+export async function getProfiles(
+  environment?: string,
+  sort?: ProfileSort
+): Promise<ApiResponse<ProfileWithStatus[]>> {
+  return await invoke('get_profiles', { environment, sort });
 }
 
 export async function getProfile(profileId: string): Promise<ApiResponse<Profile>> {
   return await invoke('get_profile', { profile_id: profileId });
 }
 
+export async function toggleFavorite(profileId: string): Promise<ApiResponse<boolean>> {
+  return await invoke('toggle_favorite', { profileId });
+}
+
+export async function reorderProfiles(profileIds: string[]): Promise<ApiResponse<void>> {
+  return await invoke('reorder_profiles', { profileIds });
+}
+
 export async function createProfile(input: CreateProfileInput): Promise<ApiResponse<Profile>> {
   return await invoke('create_profile', { input });
 }
 
+export async function createSystemProfile(): Promise<ApiResponse<Profile>> {
+  return await invoke('create_system_profile');
+}
+
 export async function updateProfile(input: UpdateProfileInput): Promise<ApiResponse<Profile>> {
   return await invoke('update_profile', { input });
 }
 
+export async function setProfileProxyUrl(profileId: string, proxyUrl: string): Promise<ApiResponse<Profile>> {
+  return await invoke('set_profile_proxy_url', { profileId, proxyUrl });
+}
+
+export async function getProfileProxyUrl(
+  profileId: string,
+  maskPassword?: boolean
+): Promise<ApiResponse<string | null>> {
+  return await invoke('get_profile_proxy_url', { profileId, maskPassword });
+}
+
+export async function setDefaultUrlForTag(tag: string, url: string): Promise<ApiResponse<number>> {
+  return await invoke('set_default_url_for_tag', { tag, url });
+}
+
 export async function deleteProfile(profileId: string): Promise<ApiResponse<void>> {
   // Using camelCase to match #[tauri::command(rename_all = "camelCase")]
   return await invoke('delete_profile', { profileId });
@@ -40,6 +98,65 @@ export async function regenerateFingerprint(
   return await invoke('regenerate_fingerprint', { profileId, platform });
 }
 
+// Open profiles and the system profile are skipped and listed in the report
+export async function bulkRegenerateFingerprints(
+  profileIds: string[],
+  platform?: string
+): Promise<ApiResponse<BulkRegenerateReport>> {
+  return await invoke('bulk_regenerate_fingerprints', { profileIds, platform });
+}
+
+export async function spreadTimezonesForTag(tag: string): Promise<ApiResponse<TimezoneSpreadReport>> {
+  return await invoke('spread_timezones_for_tag', { tag });
+}
+
+export async function ageFingerprint(profileId: string): Promise<ApiResponse<Profile>> {
+  return await invoke('age_fingerprint', { profileId });
+}
+
+// In app windows only same-origin fetch/XHR get these; automation exports send
+// them on every request. Sec-* headers are rejected.
+export async function setCustomHeaders(
+  profileId: string,
+  headers: Record<string, string>
+): Promise<ApiResponse<Profile>> {
+  return await invoke('set_custom_headers', { profileId, headers });
+}
+
+export async function findDuplicateFingerprints(): Promise<ApiResponse<DuplicateFingerprintGroup[]>> {
+  return await invoke('find_duplicate_fingerprints');
+}
+
+// Blocklist entries are identity hashes as reported by findDuplicateFingerprints
+export async function checkAgainstBlocklist(profileId: string, blocklist: string[]): Promise<ApiResponse<BlocklistCheck>> {
+  return await invoke('check_against_blocklist', { profileId, blocklist });
+}
+
+export async function diffProfiles(idA: string, idB: string): Promise<ApiResponse<FieldDiff[]>> {
+  return await invoke('diff_profiles', { idA, idB });
+}
+
+export async function describeIdentity(profileId: string): Promise<ApiResponse<IdentityDescription>> {
+  return await invoke('describe_identity', { profileId });
+}
+
+export async function auditProfiles(): Promise<ApiResponse<ProfileIssue[]>> {
+  return await invoke('audit_profiles');
+}
+
+// Proxy credentials are only included when includeSecrets is set
+export async function exportProfilesCsv(includeSecrets?: boolean): Promise<ApiResponse<string>> {
+  return await invoke('export_profiles_csv', { includeSecrets });
+}
+
+// Creates nothing unless every row is valid; errors carry line numbers
+export async function importProfilesCsv(
+  csv: string,
+  generateMissingFingerprints: boolean
+): Promise<ApiResponse<CsvImportReport>> {
+  return await invoke('import_profiles_csv', { csv, generateMissingFingerprints });
+}
+
 // Bulk operations
 export async function deleteAllInactiveProfiles(): Promise<ApiResponse<number>> {
   return await invoke('delete_all_inactive_profiles');
@@ -50,9 +167,41 @@ export async function bulkCreateProfiles(
   namePrefix: string, 
   platform?: string,
   defaultUrl?: string,
-  proxy?: ProxyConfig
-): Promise<ApiResponse<Profile[]>> {
-  return await invoke('bulk_create_profiles', { count, namePrefix, platform, defaultUrl, proxy });
+  proxy?: ProxyConfig,
+  template?: string,
+  environment?: string,
+  ensureDiversity?: boolean,
+  operationId?: string  // pass one to be able to cancel this run
+): Promise<ApiResponse<BulkCreateReport>> {
+  return await invoke('bulk_create_profiles', {
+    count, namePrefix, platform, defaultUrl, proxy, template, environment, ensureDiversity, operationId
+  });
+}
+
+export async function dryRunBulkCreate(
+  count: number,
+  namePrefix: string,
+  platform?: string,
+  defaultUrl?: string,
+  proxy?: ProxyConfig,
+  template?: string,
+  environment?: string,
+  ensureDiversity?: boolean
+): Promise<ApiResponse<BulkCreatePreview>> {
+  return await invoke('dry_run_bulk_create', { count, namePrefix, platform, defaultUrl, proxy, template, environment, ensureDiversity });
+}
+
+// Without an id, every running bulk operation is cancelled
+export async function cancelBulkOperation(operationId?: string): Promise<ApiResponse<void>> {
+  return await invoke('cancel_bulk_operation', { operationId });
+}
+
+export async function precreateProfileDirs(profileIds: string[]): Promise<ApiResponse<number>> {
+  return await invoke('precreate_profile_dirs', { profileIds });
+}
+
+export async function reconcileProfileDirs(repair: boolean): Promise<ApiResponse<ReconcileReport>> {
+  return await invoke('reconcile_profile_dirs', { repair });
 }
 
 // Launcher API
@@ -60,33 +209,165 @@ export async function launchProfile(input: LaunchProfileInput): Promise<ApiRespo
   return await invoke('launch_profile', { input });
 }
 
+export async function launchProfiles(
+  profileIds: string[],
+  staggerMs: number
+): Promise<ApiResponse<LaunchGroupReport>> {
+  return await invoke('launch_profiles', { profileIds, staggerMs });
+}
+
+// Resolves to null when no profile is free
+export async function leaseProfile(workerId: string, tag?: string): Promise<ApiResponse<Profile | null>> {
+  return await invoke('lease_profile', { workerId, tag });
+}
+
+// Fails if another worker holds the lease
+export async function releaseProfile(profileId: string, workerId: string): Promise<ApiResponse<void>> {
+  return await invoke('release_profile', { profileId, workerId });
+}
+
 export async function closeProfileWindow(profileId: string): Promise<ApiResponse<void>> {
   // Using camelCase to match #[tauri::command(rename_all = "camelCase")]
   return await invoke('close_profile_window', { profileId });
 }
 
-export async function getActiveProfiles(): Promise<ApiResponse<string[]>> {
+export async function getActiveProfiles(): Promise<ApiResponse<ActiveProfile[]>> {
   return await invoke('get_active_profiles');
 }
 
+export async function getActiveWindowDetails(): Promise<ApiResponse<ActiveWindowInfo[]>> {
+  return await invoke('get_active_window_details');
+}
+
+// Keyed by profile id; every open profile with a proxy has a forwarder
+export async function getForwarderStats(): Promise<ApiResponse<Record<string, ForwarderStats>>> {
+  return await invoke('get_forwarder_stats');
+}
+
+export async function suspendProfile(profileId: string): Promise<ApiResponse<void>> {
+  return await invoke('suspend_profile', { profileId });
+}
+
+export async function resumeProfile(profileId: string): Promise<ApiResponse<void>> {
+  return await invoke('resume_profile', { profileId });
+}
+
+// Messages arrive as 'profile-console' events carrying a ProfileConsolePayload
+export async function startProfileConsoleCapture(profileId: string): Promise<ApiResponse<void>> {
+  return await invoke('start_profile_console_capture', { profileId });
+}
+
+export async function stopProfileConsoleCapture(profileId: string): Promise<ApiResponse<void>> {
+  return await invoke('stop_profile_console_capture', { profileId });
+}
+
 export async function navigateProfile(profileId: string, url: string): Promise<ApiResponse<void>> {
   // Using camelCase to match #[tauri::command(rename_all = "camelCase")]
   return await invoke('navigate_profile', { profileId, url });
 }
 
+export async function getProfilePublicIp(profileId: string): Promise<ApiResponse<string>> {
+  return await invoke('get_profile_public_ip', { profileId });
+}
+
+// Needs the profile's window open; non-string results come back as JSON
+export async function profileEval(profileId: string, script: string): Promise<ApiResponse<string>> {
+  return await invoke('profile_eval', { profileId, script });
+}
+
+export async function profileClick(profileId: string, selector: string): Promise<ApiResponse<void>> {
+  return await invoke('profile_click', { profileId, selector });
+}
+
+// Appends to the field's current value and resolves to the new value
+export async function profileType(profileId: string, selector: string, text: string): Promise<ApiResponse<string>> {
+  return await invoke('profile_type', { profileId, selector, text });
+}
+
+// Needs the profile's window open; results are cached for a few minutes
+export async function guessProxyLocation(profileId: string): Promise<ApiResponse<IpReport>> {
+  return await invoke('guess_proxy_location', { profileId });
+}
+
+export async function revealProfileDataDir(profileId: string): Promise<ApiResponse<void>> {
+  return await invoke('reveal_profile_data_dir', { profileId });
+}
+
+export async function verifyProfileIsolation(profileId: string): Promise<ApiResponse<boolean>> {
+  return await invoke('verify_profile_isolation', { profileId });
+}
+
+export async function runFingerprintSelfTest(profileId: string): Promise<ApiResponse<SelfTestReport>> {
+  return await invoke('run_fingerprint_self_test', { profileId });
+}
+
 // Cookie API
 export async function exportCookies(profileId: string): Promise<ApiResponse<string>> {
   return await invoke('export_cookies', { profileId });
 }
 
-export async function importCookies(profileId: string, cookiesJson: string): Promise<ApiResponse<void>> {
-  return await invoke('import_cookies', { profileId, cookiesJson });
+export async function exportCookiesFormat(
+  profileId: string,
+  format: 'json' | 'netscape'
+): Promise<ApiResponse<string>> {
+  return await invoke('export_cookies_format', { profileId, format });
+}
+
+// secureSameSiteNone marks SameSite=None cookies secure instead of rejecting them
+export async function importCookies(
+  profileId: string,
+  cookiesJson: string,
+  secureSameSiteNone?: boolean
+): Promise<ApiResponse<void>> {
+  return await invoke('import_cookies', { profileId, cookiesJson, secureSameSiteNone });
+}
+
+export async function validateCookies(
+  cookiesJson: string,
+  secureSameSiteNone?: boolean
+): Promise<ApiResponse<CookieValidation>> {
+  return await invoke('validate_cookies', { cookiesJson, secureSameSiteNone });
+}
+
+export async function mergeCookies(
+  profileId: string,
+  cookiesJson: string,
+  secureSameSiteNone?: boolean
+): Promise<ApiResponse<number>> {
+  return await invoke('merge_cookies', { profileId, cookiesJson, secureSameSiteNone });
 }
 
 export async function clearCookies(profileId: string): Promise<ApiResponse<void>> {
   return await invoke('clear_cookies', { profileId });
 }
 
+// olderThanDays also drops session cookies from files saved at least that long ago
+export async function purgeExpiredCookies(olderThanDays?: number): Promise<ApiResponse<PurgeReport>> {
+  return await invoke('purge_expired_cookies', { olderThanDays });
+}
+
+export async function flushProfileCookies(profileId: string): Promise<ApiResponse<number>> {
+  return await invoke('flush_profile_cookies', { profileId });
+}
+
+// Session snapshot API
+export async function snapshotSession(profileId: string, name: string): Promise<ApiResponse<SessionSnapshot>> {
+  return await invoke('snapshot_session', { profileId, name });
+}
+
+// The profile must be closed
+export async function restoreSession(profileId: string, name: string): Promise<ApiResponse<void>> {
+  return await invoke('restore_session', { profileId, name });
+}
+
+export async function listSessionSnapshots(profileId: string): Promise<ApiResponse<SessionSnapshot[]>> {
+  return await invoke('list_session_snapshots', { profileId });
+}
+
+export async function deleteSessionSnapshot(profileId: string, name: string): Promise<ApiResponse<void>> {
+  return await invoke('delete_session_snapshot', { profileId, name });
+}
+
 // Settings API
 export async function getSetting(key: string): Promise<ApiResponse<string | null>> {
   return await invoke('get_setting', { key });
@@ -96,7 +377,83 @@ export async function setSetting(key: string, value: string): Promise<ApiRespons
   return await invoke('set_setting', { key, value });
 }
 
+// Settings and user-defined templates only; profiles are never included.
+// Proxy credentials in settings are left out unless includeSecrets is set.
+export async function exportConfig(includeSecrets?: boolean): Promise<ApiResponse<string>> {
+  return await invoke('export_config', { includeSecrets });
+}
+
+export async function importConfig(data: string, overwrite: boolean): Promise<ApiResponse<ConfigImportReport>> {
+  return await invoke('import_config', { data, overwrite });
+}
+
 // Utility API
 export async function previewFingerprint(platform?: string): Promise<ApiResponse<Fingerprint>> {
   return await invoke('preview_fingerprint', { platform });
 }
+
+export async function getFingerprintOptions(): Promise<ApiResponse<FingerprintOptions>> {
+  return await invoke('get_fingerprint_options');
+}
+
+export async function parseProxyList(text: string): Promise<ApiResponse<ProxyConfig[]>> {
+  return await invoke('parse_proxy_list', { text });
+}
+
+export async function getFingerprintTemplates(): Promise<ApiResponse<FingerprintTemplate[]>> {
+  return await invoke('get_fingerprint_templates');
+}
+
+export async function saveFingerprintTemplate(
+  template: FingerprintTemplate
+): Promise<ApiResponse<FingerprintTemplate>> {
+  return await invoke('save_fingerprint_template', { template });
+}
+
+export async function deleteFingerprintTemplate(name: string): Promise<ApiResponse<void>> {
+  return await invoke('delete_fingerprint_template', { name });
+}
+
+export async function previewSpoofScript(profileId: string): Promise<ApiResponse<string>> {
+  return await invoke('preview_spoof_script', { profileId });
+}
+
+export async function exportSpoofScript(profileId: string): Promise<ApiResponse<string>> {
+  return await invoke('export_spoof_script', { profileId });
+}
+
+// null when the profile's UA is a browser without client hints
+export async function previewUserAgentData(profileId: string): Promise<ApiResponse<UserAgentData | null>> {
+  return await invoke('preview_user_agent_data', { profileId });
+}
+
+// Proxy credentials are read from environment variables unless includeSecrets is set
+export async function exportAutomationConfig(
+  profileId: string,
+  target: 'playwright' | 'puppeteer',
+  includeSecrets?: boolean
+): Promise<ApiResponse<string>> {
+  return await invoke('export_automation_config', { profileId, target, includeSecrets });
+}
+
+export async function spoofScriptVersion(profileId: string): Promise<ApiResponse<string>> {
+  return await invoke('spoof_script_version', { profileId });
+}
+
+export async function getEngineInfo(): Promise<ApiResponse<EngineInfo>> {
+  return await invoke('get_engine_info');
+}
+
+export async function getDataDir(): Promise<ApiResponse<string>> {
+  return await invoke('get_data_dir');
+}
+
+// Needs every profile window closed; the app restarts from the new location on success
+export async function relocateDataDir(newPath: string): Promise<ApiResponse<void>> {
+  return await invoke('relocate_data_dir', { newPath });
+}
+
+// Repair only runs when the integrity check finds damage
+export async function checkDatabaseIntegrity(attemptRepair?: boolean): Promise<ApiResponse<IntegrityReport>> {
+  return await invoke('check_database_integrity', { attemptRepair });
+}
@@ -5,8 +5,13 @@ export interface ProxyConfig {
   port?: number;
   username?: string;
   password?: string;
+  bypass?: string[];    // host patterns that skip the proxy (Windows only)
 }
 
+export type CanvasNoiseLevel = 'low' | 'medium' | 'high';
+
+export type WebrtcMode = 'disabled' | 'relay' | 'enabled';
+
 export interface Profile {
   id: string;
   name: string;
@@ -20,6 +25,12 @@ export interface Profile {
   platform: string;
   timezone: string;
   language: string;
+  languages: string[];  // navigator.languages order, starting with language
+  do_not_track: boolean | null;
+  canvas_noise_level: CanvasNoiseLevel;
+  inner_width: number | null;
+  inner_height: number | null;
+  custom_headers: Record<string, string>;
   default_url: string;
   // Proxy settings
   proxy_enabled: boolean;
@@ -28,8 +39,20 @@ export interface Profile {
   proxy_port: number;
   proxy_username: string | null;
   proxy_password: string | null;
+  proxy_bypass: string[];
   created_at: string;
   last_used: string | null;
+  last_aged: string | null;
+  is_system: boolean;  // real host fingerprint, launched without spoofing
+  tags: string[];
+  webrtc_mode: WebrtcMode | null;  // null = follow the default_webrtc_mode setting
+  launch_count: number;
+  leased_by: string | null;   // automation worker holding the profile
+  leased_at: number | null;   // unix seconds
+  is_favorite: boolean;
+  sort_order: number;         // manual position, 0 = never placed
+  environment: string;        // e.g. "dev", "prod"; "default" when unset
+  disable_cache: boolean;     // load every page fresh; Windows only, elsewhere just fetch/XHR skip the cache
 }
 
 export interface ProfileWithStatus extends Profile {
@@ -47,6 +70,12 @@ export interface Fingerprint {
   device_memory: number;
   timezone: string;
   language: string;
+  languages: string[];  // navigator.languages order, starting with language
+  do_not_track: boolean | null;
+  canvas_noise_level: CanvasNoiseLevel;
+  inner_width: number | null;
+  inner_height: number | null;
+  custom_headers: Record<string, string>;
   default_url: string;
   proxy_enabled: boolean;
   proxy_type: string;
@@ -54,6 +83,8 @@ export interface Fingerprint {
   proxy_port: number;
   proxy_username: string | null;
   proxy_password: string | null;
+  webrtc_mode: WebrtcMode | null;
+  webgl_noise: boolean;  // from the webgl_noise setting at launch
 }
 
 export interface ApiResponse<T> {
@@ -66,7 +97,13 @@ export interface CreateProfileInput {
   name: string;
   platform?: string;
   default_url?: string;
+  canvas_noise_level?: CanvasNoiseLevel;
   proxy?: ProxyConfig;
+  template?: string;  // fingerprint template name
+  tags?: string[];
+  environment?: string;  // blank = "default"
+  disable_cache?: boolean;
+  idempotency_key?: string;  // a retry with the same key within a day returns the first profile
 }
 
 export interface UpdateProfileInput {
@@ -82,13 +119,82 @@ export interface UpdateProfileInput {
   platform?: string;
   timezone?: string;
   language?: string;
+  languages?: string[];  // language is always kept first
+  do_not_track?: boolean | null;  // null = derive from profile seed
+  canvas_noise_level?: CanvasNoiseLevel;
+  inner_width?: number | null;    // null = derive from profile seed
+  inner_height?: number | null;
   default_url?: string;
   proxy?: ProxyConfig;
+  tags?: string[];  // replaces the existing tags
+  webrtc_mode?: WebrtcMode | null;  // null = follow the default_webrtc_mode setting
+  environment?: string;
+  disable_cache?: boolean;
 }
 
 export interface LaunchProfileInput {
   profile_id: string;
   start_url?: string;
+  proxy_override?: ProxyConfig;  // this launch only; enabled defaults to true, bypass to the profile's
+  disable_spoofing?: boolean;  // this launch only: no spoof script, keeps the user agent and proxy
+  worker_id?: string;  // automation worker launching a profile it leased
+}
+
+export interface LaunchResult {
+  profile_id: string;
+  success: boolean;
+  window_label: string | null;
+  error: string | null;
+}
+
+export interface LaunchGroupReport {
+  results: LaunchResult[];
+  limit_reached: boolean;
+}
+
+export interface ActiveProfile {
+  profile_id: string;
+  suspended: boolean;  // window hidden and media paused
+}
+
+export interface ActiveWindowInfo {
+  profile_id: string;
+  window_label: string;
+  title: string | null;  // page's document title; null until the page reports one
+  url: string | null;
+  launched_at: number | null;  // unix seconds
+  suspended: boolean;
+}
+
+export interface ProxyRetryPayload {
+  profile_id: string;
+  attempt: number;
+  max_attempts: number;
+  delay_ms: number;
+}
+
+export interface ProfileConsolePayload {
+  profile_id: string;
+  level: string;    // console method, or "navigation" with the URL as message
+  message: string;
+}
+
+export interface IpReport {
+  ip: string;
+  country: string | null;  // ISO 3166-1 alpha-2, e.g. "US"
+}
+
+export interface SelfTestCheck {
+  attribute: string;
+  expected: string;
+  observed: string;
+  passed: boolean;
+}
+
+export interface SelfTestReport {
+  profile_id: string;
+  passed: boolean;
+  checks: SelfTestCheck[];
 }
 
 export interface Cookie {
@@ -101,3 +207,212 @@ export interface Cookie {
   secure: boolean;
   same_site?: string;
 }
+
+export interface CookieError {
+  index: number;        // position in the imported array
+  name: string | null;
+  message: string;
+}
+
+export interface DuplicateCookie {
+  name: string;
+  domain: string;
+  path: string;
+  indices: number[];
+}
+
+export interface CookieValidation {
+  total: number;
+  errors: CookieError[];
+  duplicates: DuplicateCookie[];
+  expired: CookieError[];
+}
+
+export interface CookiePurgeCount {
+  profile_id: string;
+  removed: number;
+}
+
+export interface PurgeReport {
+  purged: CookiePurgeCount[];        // only profiles that lost cookies
+  total_removed: number;
+  skipped: SkippedProfile[];         // open profiles and unreadable cookie files
+}
+
+export interface SessionSnapshot {
+  name: string;
+  created_at: number;  // unix seconds
+  size_bytes: number;
+}
+
+export interface SkippedProfile {
+  profile_id: string;
+  reason: string;
+}
+
+export interface BulkCreateReport {
+  profiles: Profile[];
+  diversity_retries: number;  // candidates regenerated to keep the batch diverse
+}
+
+export interface BulkCreatePreview {
+  profiles: Profile[];  // not saved; the real create generates afresh
+  diversity_retries: number;
+  issues: ProfileIssue[];
+}
+
+export interface ForwarderStats {
+  port: number;
+  active_connections: number;
+  total_connections: number;
+  pooled_connections: number;  // upstream connections open and waiting
+  pool_hits: number;           // tunnels started on a pooled connection
+  bytes_sent: number;          // webview to proxy
+  bytes_received: number;      // proxy to webview
+}
+
+export interface RepairReport {
+  rows_recovered: number;
+  incomplete_tables: string[];  // a read error cut these short
+  backup_path: string;          // the damaged file, kept beside the database
+}
+
+export interface IntegrityReport {
+  ok: boolean;
+  integrity_issues: string[];
+  foreign_key_issues: string[];
+  repair: RepairReport | null;  // set when a repair ran before the checks
+}
+
+export interface CsvRowError {
+  line: number;  // line the row starts on
+  message: string;
+}
+
+export interface CsvImportReport {
+  profiles: Profile[];    // empty when any row has an error
+  errors: CsvRowError[];
+}
+
+export interface BulkRegenerateReport {
+  regenerated: Profile[];
+  skipped: SkippedProfile[];
+}
+
+export interface TimezoneSpreadReport {
+  updated: Profile[];
+  skipped: SkippedProfile[];
+}
+
+export interface DuplicateFingerprintGroup {
+  identity_hash: string;
+  profile_ids: string[];
+}
+
+export interface BlocklistCheck {
+  profile_id: string;
+  identity_hash: string;
+  burned: boolean;            // identity hash is on the blocklist
+  invalid_entries: string[];  // entries that aren't identity hashes
+}
+
+export interface FieldDiff {
+  field: string;
+  a: string;
+  b: string;
+  matches: boolean;
+}
+
+export interface IdentityDescription {
+  browser: string;                 // e.g. "Chrome", "Safari"
+  browser_version: number | null;
+  os: string;                      // e.g. "Windows 10/11", "macOS 10.15.7"
+  gpu: string;
+  screen: string;                  // "1920x1080"
+  timezone: string;
+  utc_offset: string;              // e.g. "UTC+02:00"
+  languages: string[];
+  hardware_concurrency: number;
+  device_memory: number;           // as exposed to pages (capped at 8)
+  proxy_host: string | null;
+  proxy_country: string | null;    // guessed from the proxy hostname
+}
+
+export interface ProfileIssue {
+  profile_id: string;
+  code: string;  // e.g. "invalid_proxy", "incoherent_fingerprint", "proxy_location_mismatch"
+  message: string;
+}
+
+export interface ReconcileReport {
+  orphan_dirs: string[];   // directories with no matching profile
+  missing_dirs: string[];  // profile ids without a data directory
+  repaired: boolean;
+}
+
+export interface ConfigImportReport {
+  settings_imported: number;
+  settings_skipped: string[];   // already set, kept because overwrite was off
+  templates_imported: number;
+  templates_skipped: string[];  // built-ins, plus existing names without overwrite
+}
+
+export interface EngineInfo {
+  version: string;
+  spoof_engine_version: number;  // bumped when spoof protections change
+  proxy_types: string[];
+  features: string[];            // e.g. "proxy_application"
+}
+
+export interface UaBrand {
+  brand: string;
+  version: string;
+}
+
+// navigator.userAgentData as the spoof script exposes it; field names follow the JS API
+export interface UserAgentData {
+  brands: UaBrand[];
+  mobile: boolean;
+  platform: string;
+  architecture: string;
+  bitness: string;
+  model: string;
+  platformVersion: string;
+  uaFullVersion: string;
+  fullVersionList: UaBrand[];
+  wow64: boolean;
+}
+
+export interface ScreenResolution {
+  width: number;
+  height: number;
+}
+
+export interface WebglConfig {
+  vendor: string;
+  renderer: string;
+}
+
+export interface FingerprintOptions {
+  platforms: string[];  // navigator.platform values accepted by bulk/preview generation
+  timezones: string[];
+  languages: string[];
+  resolutions: ScreenResolution[];
+  webgl_configs: WebglConfig[];
+  hardware_concurrency: number[];
+  device_memory: number[];
+  canvas_noise_levels: CanvasNoiseLevel[];
+}
+
+export interface FingerprintTemplate {
+  name: string;
+  platform: string | null;  // "windows", "macos" or "linux"
+  browser: string | null;   // "chrome", "firefox" or "safari"
+  timezones: string[];
+  languages: string[];
+  min_width: number | null;
+  max_width: number | null;
+  min_height: number | null;
+  max_height: number | null;
+  builtin: boolean;
+}